@@ -0,0 +1,108 @@
+use std::ops::RangeInclusive;
+
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+use crate::validation;
+
+/// Ordered packet filter, to block flows through a drone without changing the topology.
+/// The first matching rule decides, packets that match no rule are allowed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Acl {
+    pub rules: Vec<AclRule>,
+}
+
+/// A rule of an `Acl`, the conditions left to `None` match every packet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AclRule {
+    /// Previous hop, the last node of the path trace for FloodRequests.
+    pub from: Option<NodeId>,
+    /// Last hop of the route. FloodRequests have none and never match.
+    pub destination: Option<NodeId>,
+    pub packet_type: Option<PacketKind>,
+    pub sessions: Option<RangeInclusive<u64>>,
+    pub action: AclAction,
+}
+
+/// `PacketType` without the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketKind {
+    Fragment,
+    Ack,
+    Nack,
+    FloodRequest,
+    FloodResponse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclAction {
+    Allow,
+    /// Reported to the controller with `PacketDropped`.
+    Drop,
+    /// Fragments are Nacked with ErrorInRouting, so that the sender routes around the drone.
+    /// Other packets can't be Nacked and are dropped.
+    Nack,
+}
+
+impl Acl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn rule(mut self, rule: AclRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+    pub fn decide(&self, packet: &Packet) -> AclAction {
+        self.rules.iter().find(|rule| rule.matches(packet)).map_or(AclAction::Allow, |rule| rule.action)
+    }
+}
+
+impl AclRule {
+    /// A rule matching every packet.
+    pub fn new(action: AclAction) -> Self {
+        Self { from: None, destination: None, packet_type: None, sessions: None, action }
+    }
+    pub fn from(mut self, node_id: NodeId) -> Self {
+        self.from = Some(node_id);
+        self
+    }
+    pub fn to(mut self, node_id: NodeId) -> Self {
+        self.destination = Some(node_id);
+        self
+    }
+    pub fn packet_type(mut self, kind: PacketKind) -> Self {
+        self.packet_type = Some(kind);
+        self
+    }
+    pub fn sessions(mut self, sessions: RangeInclusive<u64>) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+    pub fn matches(&self, packet: &Packet) -> bool {
+        if self.from.is_some_and(|from| validation::sender_of(packet) != Some(from)) {
+            return false;
+        }
+        if let Some(destination) = self.destination {
+            let is_flood_request = matches!(packet.pack_type, PacketType::FloodRequest(_));
+            if is_flood_request || packet.routing_header.hops.last() != Some(&destination) {
+                return false;
+            }
+        }
+        if self.packet_type.is_some_and(|kind| kind != PacketKind::of(&packet.pack_type)) {
+            return false;
+        }
+        self.sessions.as_ref().is_none_or(|sessions| sessions.contains(&packet.session_id))
+    }
+}
+
+impl PacketKind {
+    pub fn of(pack_type: &PacketType) -> Self {
+        match pack_type {
+            PacketType::MsgFragment(_) => PacketKind::Fragment,
+            PacketType::Ack(_) => PacketKind::Ack,
+            PacketType::Nack(_) => PacketKind::Nack,
+            PacketType::FloodRequest(_) => PacketKind::FloodRequest,
+            PacketType::FloodResponse(_) => PacketKind::FloodResponse,
+        }
+    }
+}
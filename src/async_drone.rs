@@ -0,0 +1,105 @@
+use crossbeam_channel::{never, Sender};
+use std::collections::HashMap;
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::sleep_until;
+
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+use crate::clock::Clock;
+use crate::drone::RustaceansWitAttitudesDrone;
+use crate::event::AuxEvent;
+use crate::options::DroneOptions;
+use crate::scenario::Scenario;
+use crate::stats::DroneStats;
+
+/// The same drone driven by a tokio task instead of a thread.
+///
+/// Commands and packets are received on tokio channels. Events and packets are still sent on the
+/// crossbeam channels of wg_2024 (`AddSender` carries one), they are unbounded so sending never blocks.
+pub struct RustaceansWitAttitudesDroneAsync {
+    drone: RustaceansWitAttitudesDrone,
+    controller_recv: UnboundedReceiver<DroneCommand>,
+    packet_recv: UnboundedReceiver<Packet>,
+}
+
+impl RustaceansWitAttitudesDroneAsync {
+    pub fn new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: UnboundedReceiver<DroneCommand>,
+        packet_recv: UnboundedReceiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+    ) -> Self {
+        // the inner drone never reads its own channels, the async loop feeds it
+        let drone = RustaceansWitAttitudesDrone::new(id, controller_send, never(), never(), packet_send, pdr);
+        Self { drone, controller_recv, packet_recv }
+    }
+    pub fn with_options(self, options: DroneOptions) -> Self {
+        self.map(|drone| drone.with_options(options))
+    }
+    pub fn with_scenario(self, scenario: Scenario) -> Self {
+        self.map(|drone| drone.with_scenario(scenario))
+    }
+    pub fn with_aux_sender(self, aux_send: Sender<AuxEvent>) -> Self {
+        self.map(|drone| drone.with_aux_sender(aux_send))
+    }
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        self.map(|drone| drone.with_clock(clock))
+    }
+    pub fn stats(&self) -> DroneStats {
+        self.drone.stats()
+    }
+
+    /// Runs until both input channels are closed.
+    pub async fn run(&mut self) {
+        self.drone.start();
+        let mut commands_open = true;
+        let mut packets_open = true;
+        while !self.drone.is_shut_down() && (commands_open || packets_open) {
+            let crashing = self.drone.is_crashing();
+            let accepts_packets = packets_open && self.drone.accepts_packets();
+            // timers don't run while crashing, like in the threaded drone, only the drain timeout does
+            let deadline = self.drone.wake_deadline();
+            if accepts_packets && !crashing && self.drone.packets_first() {
+                if let Ok(packet) = self.packet_recv.try_recv() {
+                    self.drone.dispatch_packet(packet);
+                    self.drone.housekeeping(!self.packet_recv.is_empty());
+                    continue;
+                }
+            }
+            tokio::select! {
+                biased;
+                command = self.controller_recv.recv(), if commands_open => match command {
+                    Some(command) => self.drone.dispatch_command(command),
+                    None => {
+                        commands_open = false;
+                        self.drone.controller_disconnected();
+                    }
+                },
+                packet = self.packet_recv.recv(), if accepts_packets => match packet {
+                    Some(packet) => self.drone.dispatch_packet(packet),
+                    None => {
+                        packets_open = false;
+                        self.drone.packets_disconnected();
+                    }
+                },
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now).into()), if deadline.is_some() => {},
+            }
+            if crashing {
+                self.drone.check_crash_deadline();
+            } else {
+                self.drone.housekeeping(!self.packet_recv.is_empty());
+            }
+        }
+    }
+
+    fn map(mut self, f: impl FnOnce(RustaceansWitAttitudesDrone) -> RustaceansWitAttitudesDrone) -> Self {
+        self.drone = f(self.drone);
+        self
+    }
+}
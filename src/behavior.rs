@@ -0,0 +1,156 @@
+use std::fmt;
+use std::time::Duration;
+
+use rand::{Rng, RngCore};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+/// What the drone does with a packet, see `BehaviorStrategy::on_packet`.
+#[derive(Debug, Clone)]
+pub enum Verdict {
+    /// What the protocol says: drop it with the pdr, forward it, answer the flood...
+    Standard,
+    /// Lose it without a Nack, the controller still gets a PacketDropped.
+    Discard,
+    /// Answer a MsgFragment with a Nack of this type, other packets are discarded.
+    Nack(NackType),
+}
+
+/// What the drone does with a packet whose next hop can't be reached, see `BehaviorStrategy::on_unreachable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unreachable {
+    /// Answer it with a Nack(ErrorInRouting) (protocol behaviour).
+    Nack,
+    /// Keep it and try again after this long.
+    RetryAfter(Duration),
+}
+
+/// Variant of the drone, chosen with `with_behavior`. The drone keeps doing the rest (routing checks, filters,
+/// links, statistics and events) and asks the strategy at the points where variants differ.
+pub trait BehaviorStrategy: Send + fmt::Debug {
+    /// Short name, for the debug output.
+    fn name(&self) -> &'static str;
+
+    /// A packet that passed the routing checks and the ACL, before the drone drops it with the pdr or forwards it.
+    fn on_packet(&mut self, packet: &Packet, rng: &mut dyn RngCore) -> Verdict {
+        let _ = (packet, rng);
+        Verdict::Standard
+    }
+
+    /// The next hop of `packet` has no sender or its link is down, for the `attempt`-th time (from 1).
+    fn on_unreachable(&mut self, packet: &Packet, attempt: u32) -> Unreachable {
+        let _ = (packet, attempt);
+        Unreachable::Nack
+    }
+}
+
+/// The drone of the protocol specification, used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardBehavior;
+
+impl BehaviorStrategy for StandardBehavior {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+}
+
+/// Keeps the packets whose next hop can't be reached and tries again, instead of Nacking them right away,
+/// for links that come and go. The ones still stuck after `max_attempts` are Nacked.
+#[derive(Debug, Clone, Copy)]
+pub struct StoreAndForward {
+    pub retry_interval: Duration,
+    pub max_attempts: u32,
+}
+
+impl Default for StoreAndForward {
+    fn default() -> Self {
+        Self { retry_interval: Duration::from_millis(100), max_attempts: 10 }
+    }
+}
+
+impl BehaviorStrategy for StoreAndForward {
+    fn name(&self) -> &'static str {
+        "store-and-forward"
+    }
+
+    fn on_unreachable(&mut self, _packet: &Packet, attempt: u32) -> Unreachable {
+        if attempt < self.max_attempts {
+            Unreachable::RetryAfter(self.retry_interval)
+        } else {
+            Unreachable::Nack
+        }
+    }
+}
+
+/// A hostile intermediary, to test how clients and servers cope with one: it loses fragments without
+/// telling anyone and makes up Nacks for others. Counted in `DroneStats::misbehaviors`.
+#[derive(Debug, Clone, Default)]
+pub struct Adversarial {
+    /// Probability of discarding a MsgFragment without a Nack.
+    pub blackhole_probability: f64,
+    /// Probability of answering a MsgFragment with a Nack(Dropped) it didn't deserve.
+    pub fake_nack_probability: f64,
+    /// Only the fragments towards these destinations, all of them if empty.
+    pub targets: Vec<NodeId>,
+}
+
+impl BehaviorStrategy for Adversarial {
+    fn name(&self) -> &'static str {
+        "adversarial"
+    }
+
+    fn on_packet(&mut self, packet: &Packet, rng: &mut dyn RngCore) -> Verdict {
+        if !matches!(packet.pack_type, PacketType::MsgFragment(_)) {
+            return Verdict::Standard;
+        }
+        let destination = packet.routing_header.hops.last();
+        if !self.targets.is_empty() && !destination.is_some_and(|destination| self.targets.contains(destination)) {
+            return Verdict::Standard;
+        }
+        if roll(rng, self.blackhole_probability) {
+            Verdict::Discard
+        } else if roll(rng, self.fake_nack_probability) {
+            Verdict::Nack(NackType::Dropped)
+        } else {
+            Verdict::Standard
+        }
+    }
+}
+
+// false for a probability that is NaN or not positive, gen_bool panics outside of [0, 1]
+fn roll(rng: &mut dyn RngCore, probability: f64) -> bool {
+    probability > 0.0 && rng.gen_bool(probability.min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Packet};
+
+    use super::{Adversarial, BehaviorStrategy, Verdict};
+
+    fn adversarial(probability: f64) -> Adversarial {
+        Adversarial { blackhole_probability: probability, fake_nack_probability: probability, targets: Vec::new() }
+    }
+
+    fn fragment() -> Packet {
+        let fragment = Fragment { fragment_index: 0, total_n_fragments: 1, length: 0, data: [0; 128] };
+        Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, 2], 1), 7, fragment)
+    }
+
+    #[test]
+    fn nan_and_negative_probabilities_never_misbehave() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for probability in [f64::NAN, -1.0, 0.0] {
+            assert!(matches!(adversarial(probability).on_packet(&fragment(), &mut rng), Verdict::Standard));
+        }
+    }
+
+    #[test]
+    fn probability_above_one_always_discards() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(matches!(adversarial(2.0).on_packet(&fragment(), &mut rng), Verdict::Discard));
+    }
+}
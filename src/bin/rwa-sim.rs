@@ -0,0 +1,632 @@
+//! Runs a network of our drones from a wg_2024 topology file, with stub clients and servers exchanging messages.
+//!
+//! ```text
+//! rwa-sim <topology.toml> [--messages N] [--fragments N] [--interval-ms MS] [--timeout-secs S] [--profiles FILE] [--flows FILE] [--monitor]
+//! ```
+//!
+//! By default every client sends `--messages` messages of `--fragments` fragments to the servers in turn.
+//! `--flows` replaces them with the flows of a TOML file, and reports the throughput and loss of each one:
+//!
+//! ```toml
+//! # client 1 sends 100 fragments to server 10 at 50 fragments per second
+//! [[flow]]
+//! client = 1
+//! server = 10
+//! fragments = 100
+//! pps = 50        # all at once if missing
+//! start_secs = 2  # after the start of the client, 0 if missing
+//! ```
+//!
+//! `--monitor` (feature `tui`) shows the drones live while the clients run, the summary is printed after quitting it.
+//!
+//! On SIGINT or SIGTERM the clients stop sending and every drone is crashed the way a simulation controller would:
+//! `Crash`, then its senders are removed once the packets queued for the drones have been handled. The statistics
+//! are printed as usual.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::process::ExitCode;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+use crossbeam_channel::{bounded, select, select_biased, unbounded, Receiver, Sender, TryRecvError};
+use rustaceans_wit_attitudes::{spawn, DroneHandle, Profiles, RustaceansWitAttitudesDrone};
+use serde::Deserialize;
+use wg_2024::config::Config;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Ack, Fragment, NackType, Packet, PacketType, FRAGMENT_DSIZE};
+
+const USAGE: &str = "usage: rwa-sim <topology.toml> [--messages N] [--fragments N] [--interval-ms MS] [--timeout-secs S] [--profiles FILE] [--flows FILE] [--monitor]";
+
+// a fragment Nacked with Dropped is sent again at most this many times
+const MAX_RETRIES: u32 = 20;
+// how long crashing drones get to handle the packets already sent to them
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Args {
+    topology: String,
+    messages: u64,
+    fragments: u64,
+    interval: Duration,
+    timeout: Duration,
+    profiles: Option<String>,
+    flows: Option<String>,
+    #[cfg(feature = "tui")]
+    monitor: bool,
+}
+
+// fragments a client sends to a server, in a single session
+#[derive(Debug, Clone)]
+struct Flow {
+    server: NodeId,
+    fragments: u64,
+    // fragments per second, all at once if None
+    pps: Option<f64>,
+    // after the start of the client
+    start: Duration,
+}
+
+#[derive(Debug, Default)]
+struct FlowReport {
+    delivered: bool,
+    unreachable: bool,
+    fragments_sent: u64,
+    fragments_acked: u64,
+    retransmissions: u64,
+    acks: u64,
+    nacks: HashMap<&'static str, u64>,
+    first_sent: Option<Instant>,
+    last_ack: Option<Instant>,
+}
+
+// what woke a client up
+enum Wake {
+    Packet(Packet),
+    Due,
+    Stop,
+}
+
+// [[flow]] of the flows file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FlowFile {
+    client: NodeId,
+    server: NodeId,
+    fragments: u64,
+    pps: Option<f64>,
+    #[serde(default)]
+    start_secs: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FlowsFile {
+    flow: Vec<FlowFile>,
+}
+
+#[derive(Debug, Default)]
+struct EventCounts {
+    sent: u64,
+    dropped: u64,
+    shortcuts: u64,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("rwa-sim: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        topology: String::new(),
+        messages: 10,
+        fragments: 5,
+        interval: Duration::from_millis(10),
+        timeout: Duration::from_secs(30),
+        profiles: None,
+        flows: None,
+        #[cfg(feature = "tui")]
+        monitor: false,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("`{}` needs a value", arg));
+        match arg.as_str() {
+            "--messages" => parsed.messages = number(&value()?)?,
+            "--fragments" => parsed.fragments = number(&value()?)?.max(1),
+            "--interval-ms" => parsed.interval = Duration::from_millis(number(&value()?)?),
+            "--timeout-secs" => parsed.timeout = Duration::from_secs(number(&value()?)?),
+            "--profiles" => parsed.profiles = Some(value()?),
+            "--flows" => parsed.flows = Some(value()?),
+            #[cfg(feature = "tui")]
+            "--monitor" => parsed.monitor = true,
+            "-h" | "--help" => return Err("rwa-sim: run a network of drones".to_string()),
+            other if other.starts_with('-') => return Err(format!("unknown option `{}`", other)),
+            other if parsed.topology.is_empty() => parsed.topology = other.to_string(),
+            other => return Err(format!("unexpected argument `{}`", other)),
+        }
+    }
+    if parsed.topology.is_empty() {
+        return Err("missing topology file".to_string());
+    }
+    Ok(parsed)
+}
+
+fn number(value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("`{}` is not a number", value))
+}
+
+fn run(args: Args) -> Result<bool, String> {
+    let topology = fs::read_to_string(&args.topology).map_err(|e| format!("can't read {}: {}", args.topology, e))?;
+    let config: Config = toml::from_str(&topology).map_err(|e| format!("invalid topology {}: {}", args.topology, e))?;
+    let profiles = match &args.profiles {
+        Some(path) => Profiles::from_toml_file(path).map_err(|e| e.to_string())?,
+        None => Profiles::default(),
+    };
+    let flows = match &args.flows {
+        Some(path) => Some(load_flows(path, &config)?),
+        None => None,
+    };
+
+    // SIGINT and SIGTERM close this channel, whoever waits on it stops
+    let (interrupt_send, interrupt_recv) = bounded::<()>(0);
+    let mut interrupt_send = Some(interrupt_send);
+    ctrlc::set_handler(move || drop(interrupt_send.take())).map_err(|e| format!("can't handle signals: {}", e))?;
+
+    // one packet channel per node, plus its neighbors
+    let mut packet_channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)> = HashMap::new();
+    let mut neighbors: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+    for drone in &config.drone {
+        packet_channels.insert(drone.id, unbounded());
+        neighbors.entry(drone.id).or_default().extend(&drone.connected_node_ids);
+    }
+    let endpoints = config.client.iter().map(|c| (c.id, &c.connected_drone_ids)).chain(config.server.iter().map(|s| (s.id, &s.connected_drone_ids)));
+    for (id, connected_drone_ids) in endpoints {
+        packet_channels.insert(id, unbounded());
+        neighbors.entry(id).or_default().extend(connected_drone_ids);
+    }
+    for (id, ids) in &neighbors {
+        if let Some(unknown) = ids.iter().find(|id| !packet_channels.contains_key(id)) {
+            return Err(format!("node {} is connected to unknown node {}", id, unknown));
+        }
+    }
+    let senders_of = |id: NodeId| -> HashMap<NodeId, Sender<Packet>> {
+        neighbors[&id].iter().map(|neighbor| (*neighbor, packet_channels[neighbor].0.clone())).collect()
+    };
+
+    let (event_send, event_recv) = unbounded();
+    let mut command_sends: Vec<Sender<DroneCommand>> = Vec::new();
+    let mut handles: Vec<DroneHandle> = Vec::new();
+    for drone in &config.drone {
+        let (command_send, command_recv) = unbounded();
+        let profile = profiles.get(drone.id);
+        let node = RustaceansWitAttitudesDrone::new(
+            drone.id,
+            event_send.clone(),
+            command_recv,
+            packet_channels[&drone.id].1.clone(),
+            senders_of(drone.id),
+            drone.pdr,
+        )
+        .with_profile(profile);
+        handles.push(spawn(node).map_err(|e| format!("can't start drone {}: {}", drone.id, e))?);
+        command_sends.push(command_send);
+    }
+    drop(event_send);
+
+    // shortcuts are delivered by the controller, straight to the destination
+    let all_senders: HashMap<NodeId, Sender<Packet>> = packet_channels.iter().map(|(id, (send, _))| (*id, send.clone())).collect();
+    let controller = thread::spawn(move || {
+        let mut counts = EventCounts::default();
+        for event in event_recv.iter() {
+            match event {
+                DroneEvent::PacketSent(_) => counts.sent += 1,
+                DroneEvent::PacketDropped(_) => counts.dropped += 1,
+                DroneEvent::ControllerShortcut(packet) => {
+                    counts.shortcuts += 1;
+                    if let Some(send) = packet.routing_header.hops.last().and_then(|id| all_senders.get(id)) {
+                        let _ = send.send(packet);
+                    }
+                }
+            }
+        }
+        counts
+    });
+
+    let drones: HashSet<NodeId> = config.drone.iter().map(|drone| drone.id).collect();
+    let (stop_send, stop_recv) = unbounded::<()>();
+    let servers: Vec<JoinHandle<()>> = config
+        .server
+        .iter()
+        .map(|server| {
+            let (id, packet_recv, senders, stop_recv) = (server.id, packet_channels[&server.id].1.clone(), senders_of(server.id), stop_recv.clone());
+            thread::spawn(move || run_server(id, packet_recv, senders, stop_recv))
+        })
+        .collect();
+    let server_ids: Vec<NodeId> = config.server.iter().map(|server| server.id).collect();
+    let mut unreachable_servers: HashMap<NodeId, usize> = HashMap::new();
+    let clients: Vec<(NodeId, JoinHandle<Vec<FlowReport>>)> = config
+        .client
+        .iter()
+        .map(|client| {
+            let route_to = |server: NodeId| route(client.id, server, &neighbors, &drones);
+            let client_flows: Vec<(Flow, Option<Vec<NodeId>>)> = match &flows {
+                Some(flows) => flows.iter().filter(|(id, _)| *id == client.id).map(|(_, flow)| (flow.clone(), route_to(flow.server))).collect(),
+                // the messages go to the servers it can reach in turn
+                None => {
+                    let routes: Vec<Vec<NodeId>> = server_ids.iter().filter_map(|server| route_to(*server)).collect();
+                    unreachable_servers.insert(client.id, server_ids.len() - routes.len());
+                    (0..args.messages)
+                        .zip(routes.iter().cycle())
+                        .map(|(message, route)| {
+                            let flow = Flow { server: route[route.len() - 1], fragments: args.fragments, pps: None, start: args.interval * message as u32 };
+                            (flow, Some(route.clone()))
+                        })
+                        .collect()
+                }
+            };
+            let (id, packet_recv, senders, deadline) = (client.id, packet_channels[&client.id].1.clone(), senders_of(client.id), Instant::now() + args.timeout);
+            let interrupt_recv = interrupt_recv.clone();
+            (id, thread::spawn(move || run_client(id, packet_recv, senders, client_flows, deadline, interrupt_recv)))
+        })
+        .collect();
+
+    #[cfg(feature = "tui")]
+    if args.monitor {
+        rustaceans_wit_attitudes::monitor(&handles, Duration::from_millis(250)).map_err(|e| format!("monitor: {}", e))?;
+    }
+
+    let mut reports: HashMap<NodeId, Vec<FlowReport>> = HashMap::new();
+    for (id, client) in clients {
+        reports.insert(id, client.join().map_err(|_| format!("client {} panicked", id))?);
+    }
+    let mut complete = match &flows {
+        Some(flows) => print_flows(flows, reports),
+        None => print_clients(&config.client.iter().map(|client| client.id).collect::<Vec<_>>(), &reports, &unreachable_servers, args.messages),
+    };
+
+    let interrupted = interrupt_recv.try_recv() == Err(TryRecvError::Disconnected);
+    if interrupted {
+        println!("interrupted, crashing the drones");
+        let drones: Vec<(NodeId, &Sender<DroneCommand>)> = config.drone.iter().map(|drone| drone.id).zip(&command_sends).collect();
+        let drained = crash_all(&drones, &neighbors, &packet_channels);
+        if !drained {
+            println!("some packets were still queued after {:?}", DRAIN_TIMEOUT);
+        }
+        complete = false;
+    }
+
+    drop(stop_send);
+    for server in servers {
+        let _ = server.join();
+    }
+    println!("{:>8} {:>10} {:>10} {:>8} {:>8}", "drone", "received", "dropped", "nacks", "filtered");
+    for handle in handles {
+        let stats = handle.stats();
+        println!(
+            "{:>8} {:>10} {:>10} {:>8} {:>8}",
+            handle.id(), stats.packets_received, stats.fragments_dropped, stats.nacks_sent, stats.packets_filtered
+        );
+        let _ = handle.shutdown();
+    }
+    drop(command_sends);
+    drop(packet_channels);
+    let counts = controller.join().map_err(|_| "controller panicked".to_string())?;
+    println!("events: {} sent, {} dropped, {} shortcuts", counts.sent, counts.dropped, counts.shortcuts);
+    Ok(complete)
+}
+
+// flows of the file, with their client, checked against the topology
+fn load_flows(path: &str, config: &Config) -> Result<Vec<(NodeId, Flow)>, String> {
+    let file = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+    let file: FlowsFile = toml::from_str(&file).map_err(|e| format!("invalid flows {}: {}", path, e))?;
+    file.flow
+        .into_iter()
+        .enumerate()
+        .map(|(index, flow)| {
+            let invalid = |reason: &str| format!("invalid flow {} in {}: {}", index, path, reason);
+            if !config.client.iter().any(|client| client.id == flow.client) {
+                return Err(invalid(&format!("{} is not a client", flow.client)));
+            }
+            if !config.server.iter().any(|server| server.id == flow.server) {
+                return Err(invalid(&format!("{} is not a server", flow.server)));
+            }
+            if flow.fragments == 0 {
+                return Err(invalid("`fragments` must be at least 1"));
+            }
+            if flow.pps.is_some_and(|pps| !pps.is_finite() || pps <= 0.0) {
+                return Err(invalid("`pps` must be positive"));
+            }
+            if !flow.start_secs.is_finite() || flow.start_secs < 0.0 {
+                return Err(invalid("`start_secs` must not be negative"));
+            }
+            let start = Duration::from_secs_f64(flow.start_secs);
+            Ok((flow.client, Flow { server: flow.server, fragments: flow.fragments, pps: flow.pps, start }))
+        })
+        .collect()
+}
+
+// one row per client, true if all its messages were delivered
+fn print_clients(clients: &[NodeId], reports: &HashMap<NodeId, Vec<FlowReport>>, unreachable_servers: &HashMap<NodeId, usize>, messages: u64) -> bool {
+    let mut complete = true;
+    println!("{:>8} {:>10} {:>10} {:>8} {:>8} {:>8}", "client", "delivered", "fragments", "retries", "acks", "nacks");
+    for id in clients {
+        let flows = &reports[id];
+        let delivered = flows.iter().filter(|flow| flow.delivered).count() as u64;
+        let mut nacks: HashMap<&'static str, u64> = HashMap::new();
+        for (kind, count) in flows.iter().flat_map(|flow| &flow.nacks) {
+            *nacks.entry(kind).or_default() += count;
+        }
+        println!(
+            "{:>8} {:>10} {:>10} {:>8} {:>8} {:>8} {:?}",
+            id,
+            delivered,
+            flows.iter().map(|flow| flow.fragments_sent).sum::<u64>(),
+            flows.iter().map(|flow| flow.retransmissions).sum::<u64>(),
+            flows.iter().map(|flow| flow.acks).sum::<u64>(),
+            nacks.values().sum::<u64>(),
+            nacks
+        );
+        let unreachable = unreachable_servers.get(id).copied().unwrap_or_default();
+        if unreachable > 0 {
+            println!("{:>8} can't reach {} server(s)", id, unreachable);
+        }
+        complete &= delivered == messages && unreachable == 0;
+    }
+    complete
+}
+
+// one row per flow in the order of the file and the whole network, true if every flow was delivered
+fn print_flows(flows: &[(NodeId, Flow)], reports: HashMap<NodeId, Vec<FlowReport>>) -> bool {
+    // the reports of a client are in the order of its flows
+    let mut reports: HashMap<NodeId, _> = reports.into_iter().map(|(id, flows)| (id, flows.into_iter())).collect();
+    let mut complete = true;
+    let (mut sent, mut acked, mut dropped) = (0, 0, 0);
+    let (mut first_sent, mut last_ack): (Option<Instant>, Option<Instant>) = (None, None);
+    println!(
+        "{:>5} {:>7} {:>7} {:>10} {:>7} {:>8} {:>7} {:>8}  status",
+        "flow", "client", "server", "fragments", "acked", "retries", "loss%", "pps"
+    );
+    for (index, (client, flow)) in flows.iter().enumerate() {
+        let Some(report) = reports.get_mut(client).and_then(Iterator::next) else {
+            continue;
+        };
+        let flow_dropped = report.nacks.get("Dropped").copied().unwrap_or_default();
+        let status = if report.unreachable {
+            "unreachable"
+        } else if report.delivered {
+            "delivered"
+        } else {
+            "incomplete"
+        };
+        println!(
+            "{:>5} {:>7} {:>7} {:>10} {:>7} {:>8} {:>7.2} {:>8.1}  {}",
+            index,
+            client,
+            flow.server,
+            flow.fragments,
+            report.fragments_acked,
+            report.retransmissions,
+            percent(flow_dropped, report.fragments_sent),
+            rate(report.fragments_acked, report.first_sent, report.last_ack),
+            status
+        );
+        complete &= report.delivered;
+        sent += report.fragments_sent;
+        acked += report.fragments_acked;
+        dropped += flow_dropped;
+        first_sent = first_sent.into_iter().chain(report.first_sent).min();
+        last_ack = last_ack.into_iter().chain(report.last_ack).max();
+    }
+    println!(
+        "network: {} fragments acked of {} sent, {:.1} pps, {:.2}% lost",
+        acked,
+        sent,
+        rate(acked, first_sent, last_ack),
+        percent(dropped, sent)
+    );
+    complete
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+// fragments acked per second, from the first one sent to the last Ack
+fn rate(acked: u64, first_sent: Option<Instant>, last_ack: Option<Instant>) -> f64 {
+    match (first_sent, last_ack) {
+        (Some(first_sent), Some(last_ack)) if last_ack > first_sent => acked as f64 / (last_ack - first_sent).as_secs_f64(),
+        _ => 0.0,
+    }
+}
+
+// Crash, then the senders of every drone are removed once their queues are empty (or after DRAIN_TIMEOUT),
+// which completes the crash. False if packets were still queued.
+fn crash_all(drones: &[(NodeId, &Sender<DroneCommand>)], neighbors: &HashMap<NodeId, Vec<NodeId>>, packet_channels: &HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)>) -> bool {
+    for (_, command_send) in drones {
+        let _ = command_send.send(DroneCommand::Crash);
+    }
+    // crashing drones still forward Acks and Nacks, and Nack fragments, which can queue packets on another drone
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    let queued = || drones.iter().any(|(id, _)| !packet_channels[id].1.is_empty());
+    while queued() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    let drained = !queued();
+    for (id, command_send) in drones {
+        for neighbor in &neighbors[id] {
+            let _ = command_send.send(DroneCommand::RemoveSender(*neighbor));
+        }
+    }
+    drained
+}
+
+// shortest path from `from` to `to` through drones only
+fn route(from: NodeId, to: NodeId, neighbors: &HashMap<NodeId, Vec<NodeId>>, drones: &HashSet<NodeId>) -> Option<Vec<NodeId>> {
+    let mut previous: HashMap<NodeId, NodeId> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut hops = vec![to];
+            while let Some(hop) = previous.get(hops.last()?) {
+                hops.push(*hop);
+            }
+            hops.reverse();
+            return Some(hops);
+        }
+        if node != from && !drones.contains(&node) {
+            continue;
+        }
+        for neighbor in neighbors.get(&node).into_iter().flatten() {
+            if *neighbor != from && !previous.contains_key(neighbor) {
+                previous.insert(*neighbor, node);
+                queue.push_back(*neighbor);
+            }
+        }
+    }
+    None
+}
+
+// sends the fragments of every flow when they are due, resends the ones Nacked with Dropped, and waits until all
+// of them are acked
+fn run_client(
+    id: NodeId,
+    packet_recv: Receiver<Packet>,
+    senders: HashMap<NodeId, Sender<Packet>>,
+    flows: Vec<(Flow, Option<Vec<NodeId>>)>,
+    deadline: Instant,
+    interrupt_recv: Receiver<()>,
+) -> Vec<FlowReport> {
+    let started = Instant::now();
+    let mut reports: Vec<FlowReport> = flows.iter().map(|(_, route)| FlowReport { unreachable: route.is_none(), ..Default::default() }).collect();
+    // per flow, the next fragment to send and the fragments not acked yet
+    let mut next: Vec<u64> = flows.iter().map(|(flow, route)| if route.is_some() { 0 } else { flow.fragments }).collect();
+    let mut pending: Vec<HashSet<u64>> = flows.iter().map(|(flow, route)| match route {
+        Some(_) => (0..flow.fragments).collect(),
+        None => HashSet::new(),
+    }).collect();
+    let mut retries: HashMap<(usize, u64), u32> = HashMap::new();
+    // the session of a flow is its index among the flows of the client
+    let session_id = |index: usize| (u64::from(id) << 32) | index as u64;
+    let send = |report: &mut FlowReport, index: usize, fragment_index: u64| {
+        let (flow, Some(hops)) = &flows[index] else {
+            return;
+        };
+        let fragment = Fragment { fragment_index, total_n_fragments: flow.fragments, length: FRAGMENT_DSIZE as u8, data: [id; FRAGMENT_DSIZE] };
+        let packet = Packet::new_fragment(SourceRoutingHeader::new(hops.clone(), 1), session_id(index), fragment);
+        report.fragments_sent += 1;
+        report.first_sent.get_or_insert_with(Instant::now);
+        if let Some(send) = senders.get(&hops[1]) {
+            let _ = send.send(packet);
+        }
+    };
+    let due_at = |index: usize, fragment_index: u64| {
+        let flow = &flows[index].0;
+        started + flow.start + flow.pps.map_or(Duration::ZERO, |pps| Duration::from_secs_f64(fragment_index as f64 / pps))
+    };
+    loop {
+        let now = Instant::now();
+        for index in 0..flows.len() {
+            while next[index] < flows[index].0.fragments && due_at(index, next[index]) <= now {
+                send(&mut reports[index], index, next[index]);
+                next[index] += 1;
+            }
+        }
+        if pending.iter().all(HashSet::is_empty) || now >= deadline {
+            break;
+        }
+        let wake_at = (0..flows.len())
+            .filter(|index| next[*index] < flows[*index].0.fragments)
+            .map(|index| due_at(index, next[index]))
+            .fold(deadline, Instant::min);
+        // once interrupted, only the packets already there
+        let wake = select_biased! {
+            recv(packet_recv) -> packet => packet.map_or(Wake::Stop, Wake::Packet),
+            recv(interrupt_recv) -> _ => Wake::Stop,
+            default(wake_at.saturating_duration_since(now)) => Wake::Due,
+        };
+        let packet = match wake {
+            Wake::Packet(packet) => packet,
+            Wake::Due => continue,
+            Wake::Stop => break,
+        };
+        let index = (packet.session_id & u64::from(u32::MAX)) as usize;
+        if packet.session_id >> 32 != u64::from(id) || index >= flows.len() {
+            continue;
+        }
+        let report = &mut reports[index];
+        match packet.pack_type {
+            PacketType::Ack(Ack { fragment_index }) => {
+                report.acks += 1;
+                report.last_ack = Some(Instant::now());
+                if pending[index].remove(&fragment_index) {
+                    report.fragments_acked += 1;
+                    report.delivered = pending[index].is_empty();
+                }
+            }
+            PacketType::Nack(nack) => {
+                let kind = match nack.nack_type {
+                    NackType::ErrorInRouting(_) => "ErrorInRouting",
+                    NackType::DestinationIsDrone => "DestinationIsDrone",
+                    NackType::Dropped => "Dropped",
+                    NackType::UnexpectedRecipient(_) => "UnexpectedRecipient",
+                };
+                *report.nacks.entry(kind).or_default() += 1;
+                let retry = retries.entry((index, nack.fragment_index)).or_default();
+                if matches!(nack.nack_type, NackType::Dropped) && *retry < MAX_RETRIES {
+                    *retry += 1;
+                    report.retransmissions += 1;
+                    send(report, index, nack.fragment_index);
+                }
+            }
+            _ => {}
+        }
+    }
+    reports
+}
+
+fn run_server(id: NodeId, packet_recv: Receiver<Packet>, senders: HashMap<NodeId, Sender<Packet>>, stop_recv: Receiver<()>) {
+    let mut running = true;
+    while running {
+        select! {
+            recv(packet_recv) -> packet => match packet {
+                Ok(packet) => acknowledge(id, packet, &senders),
+                Err(_) => running = false,
+            },
+            recv(stop_recv) -> _ => running = false,
+        }
+    }
+}
+
+// acks a fragment back along the route it came from
+fn acknowledge(id: NodeId, packet: Packet, senders: &HashMap<NodeId, Sender<Packet>>) {
+    let PacketType::MsgFragment(fragment) = &packet.pack_type else {
+        return;
+    };
+    let hops: Vec<NodeId> = packet.routing_header.hops.iter().rev().copied().collect();
+    if hops.first() != Some(&id) || hops.len() < 2 {
+        return;
+    }
+    let ack = Packet::new_ack(SourceRoutingHeader::new(hops, 1), packet.session_id, fragment.fragment_index);
+    if let Some(send) = senders.get(&ack.routing_header.hops[1]) {
+        let _ = send.send(ack);
+    }
+}
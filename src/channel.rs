@@ -0,0 +1,137 @@
+use crossbeam_channel::{unbounded, Receiver, SendTimeoutError, Sender, TrySendError};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Sending half of a channel the drone can send events or packets on:
+/// crossbeam (what wg_2024 uses), `std::sync::mpsc` or, with the `tokio` feature, tokio's unbounded channel.
+pub trait ChannelSender<T>: Send + private::Sealed {
+    /// Gives the value back if the receiver is gone.
+    fn send(&self, value: T) -> Result<(), T>;
+    /// Like `send`, but gives up if the channel is still full after `timeout`.
+    /// Only bounded channels can be full.
+    fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendFailure<T>> {
+        let _ = timeout;
+        self.send(value).map_err(SendFailure::Disconnected)
+    }
+    /// Values waiting in the channel, `None` if the backend doesn't tell.
+    fn queued(&self) -> Option<usize> {
+        None
+    }
+    /// Whether the channel is full, `None` if the backend doesn't tell.
+    fn is_full(&self) -> Option<bool> {
+        None
+    }
+    /// Whether the receiver is still there, `None` if the backend can't tell without sending.
+    fn is_connected(&self) -> Option<bool> {
+        None
+    }
+}
+
+/// Why a value couldn't be sent, the value is given back.
+#[derive(Debug)]
+pub enum SendFailure<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+/// Receiving half of a channel the drone can receive commands or packets from, see `ChannelSender`.
+pub trait ChannelReceiver<T>: Send + private::Sealed {
+    #[doc(hidden)]
+    fn into_crossbeam(self, name: String) -> Receiver<T>;
+}
+
+impl<T: Send> ChannelSender<T> for Sender<T> {
+    fn send(&self, value: T) -> Result<(), T> {
+        Sender::send(self, value).map_err(|e| e.0)
+    }
+    fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendFailure<T>> {
+        // without reading the time, which isn't available everywhere (wasm32)
+        if timeout.is_zero() {
+            return Sender::try_send(self, value).map_err(|e| match e {
+                TrySendError::Full(value) => SendFailure::Full(value),
+                TrySendError::Disconnected(value) => SendFailure::Disconnected(value),
+            });
+        }
+        Sender::send_timeout(self, value, timeout).map_err(|e| match e {
+            SendTimeoutError::Timeout(value) => SendFailure::Full(value),
+            SendTimeoutError::Disconnected(value) => SendFailure::Disconnected(value),
+        })
+    }
+    fn queued(&self) -> Option<usize> {
+        Some(Sender::len(self))
+    }
+    fn is_full(&self) -> Option<bool> {
+        Some(Sender::is_full(self))
+    }
+}
+
+impl<T: Send> ChannelSender<T> for mpsc::Sender<T> {
+    fn send(&self, value: T) -> Result<(), T> {
+        mpsc::Sender::send(self, value).map_err(|e| e.0)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: Send> ChannelSender<T> for tokio::sync::mpsc::UnboundedSender<T> {
+    fn send(&self, value: T) -> Result<(), T> {
+        tokio::sync::mpsc::UnboundedSender::send(self, value).map_err(|e| e.0)
+    }
+    fn is_connected(&self) -> Option<bool> {
+        Some(!self.is_closed())
+    }
+}
+
+impl<T: Send> ChannelReceiver<T> for Receiver<T> {
+    fn into_crossbeam(self, _name: String) -> Receiver<T> {
+        self
+    }
+}
+
+// the run loop selects over crossbeam channels, other receivers are forwarded by a thread
+// that ends when the channel is closed
+
+impl<T: Send + 'static> ChannelReceiver<T> for mpsc::Receiver<T> {
+    fn into_crossbeam(self, name: String) -> Receiver<T> {
+        bridge(name, move || self.recv().ok())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: Send + 'static> ChannelReceiver<T> for tokio::sync::mpsc::UnboundedReceiver<T> {
+    fn into_crossbeam(mut self, name: String) -> Receiver<T> {
+        bridge(name, move || self.blocking_recv())
+    }
+}
+
+fn bridge<T: Send + 'static>(name: String, mut recv: impl FnMut() -> Option<T> + Send + 'static) -> Receiver<T> {
+    let (send, bridged) = unbounded();
+    let spawned = thread::Builder::new().name(name).spawn(move || {
+        while let Some(value) = recv() {
+            if send.send(value).is_err() {
+                break;
+            }
+        }
+    });
+    // without a thread nothing can be received
+    if spawned.is_err() {
+        return crossbeam_channel::never();
+    }
+    bridged
+}
+
+mod private {
+    use super::*;
+
+    pub trait Sealed {}
+    impl<T> Sealed for Sender<T> {}
+    impl<T> Sealed for Receiver<T> {}
+    impl<T> Sealed for mpsc::Sender<T> {}
+    impl<T> Sealed for mpsc::Receiver<T> {}
+    impl Sealed for crate::outgoing::OutgoingWorker {}
+    impl<T> Sealed for crate::slow::SlowSender<T> {}
+    #[cfg(feature = "tokio")]
+    impl<T> Sealed for tokio::sync::mpsc::UnboundedSender<T> {}
+    #[cfg(feature = "tokio")]
+    impl<T> Sealed for tokio::sync::mpsc::UnboundedReceiver<T> {}
+}
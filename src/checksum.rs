@@ -0,0 +1,43 @@
+//! Checksums of fragment payloads, for clients and servers that want their drones to catch corrupted fragments
+//! (see `DroneOptions::verify_checksums`).
+//!
+//! The checksum is a Fletcher-16 of `data[..length]`, stored big-endian in the two bytes right after the payload.
+//! Fragments longer than `MAX_SEALED_LENGTH` have no room for it and are never checked, senders following the
+//! convention split their messages in `MAX_SEALED_LENGTH` bytes.
+
+use wg_2024::packet::{Fragment, FRAGMENT_DSIZE};
+
+/// Longest payload that leaves room for the checksum.
+pub const MAX_SEALED_LENGTH: usize = FRAGMENT_DSIZE - 2;
+
+/// Fletcher-16 of `payload`.
+pub fn of(payload: &[u8]) -> u16 {
+    let (mut low, mut high) = (0u16, 0u16);
+    for byte in payload {
+        low = (low + u16::from(*byte)) % 255;
+        high = (high + low) % 255;
+    }
+    (high << 8) | low
+}
+
+/// Write the checksum after the payload, false if the fragment is too long to carry one.
+pub fn seal(fragment: &mut Fragment) -> bool {
+    let length = fragment.length as usize;
+    if length > MAX_SEALED_LENGTH {
+        return false;
+    }
+    let checksum = of(&fragment.data[..length]);
+    fragment.data[length..length + 2].copy_from_slice(&checksum.to_be_bytes());
+    true
+}
+
+/// Checksum carried by the fragment, `None` if it's too long to carry one.
+pub fn carried(fragment: &Fragment) -> Option<u16> {
+    let length = fragment.length as usize;
+    (length <= MAX_SEALED_LENGTH).then(|| u16::from_be_bytes([fragment.data[length], fragment.data[length + 1]]))
+}
+
+/// Whether the payload still matches its checksum, `None` if the fragment is too long to carry one.
+pub fn verify(fragment: &Fragment) -> Option<bool> {
+    carried(fragment).map(|checksum| checksum == of(&fragment.data[..fragment.length as usize]))
+}
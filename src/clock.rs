@@ -0,0 +1,98 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use web_time::Instant;
+
+/// Source of time of every time-based feature (delays, scenario steps, timeouts, link outages).
+pub trait Clock: Send + fmt::Debug {
+    fn now(&self) -> Instant;
+
+    /// How long `duration` of this clock lasts in real time, for the timers of the drone.
+    fn real_duration(&self, duration: Duration) -> Duration {
+        duration
+    }
+}
+
+/// The real time, used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// The real time `factor` times faster: delays, scenario steps, timeouts and link outages all take
+/// `1 / factor` of their real duration. Clones share the same start, give one to each drone of a network.
+#[derive(Debug, Clone, Copy)]
+pub struct ScaledClock {
+    start: Instant,
+    factor: f64,
+}
+
+impl ScaledClock {
+    /// `factor` 100.0 runs 100 times faster, 0.5 twice as slow. Factors that aren't positive are taken as 1.
+    pub fn new(factor: f64) -> Self {
+        let factor = if factor.is_finite() && factor > 0.0 { factor } else { 1.0 };
+        Self { start: Instant::now(), factor }
+    }
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+}
+
+impl Clock for ScaledClock {
+    fn now(&self) -> Instant {
+        self.start + self.start.elapsed().mul_f64(self.factor)
+    }
+
+    fn real_duration(&self, duration: Duration) -> Duration {
+        duration.div_f64(self.factor)
+    }
+}
+
+/// A clock that only moves when told to, for instant and deterministic tests.
+/// Clones share the same time, keep one to advance the clock given to the drone.
+///
+/// The drone doesn't wake up when the clock is advanced, the work that became due
+/// (delayed packets, scenario steps) is done after the next command or packet.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now(), elapsed: Arc::new(Mutex::new(Duration::ZERO)) }
+    }
+    pub fn advance(&self, by: Duration) {
+        *self.lock() += by;
+    }
+    /// Move to `elapsed` after the creation of the clock, never backwards.
+    pub fn advance_to(&self, elapsed: Duration) {
+        let mut current = self.lock();
+        *current = (*current).max(elapsed);
+    }
+    pub fn elapsed(&self) -> Duration {
+        *self.lock()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Duration> {
+        self.elapsed.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+}
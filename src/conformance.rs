@@ -0,0 +1,206 @@
+//! Conformance checker (`conformance` feature): every packet the drone handles is compared with the rules of the
+//! WG 2024 protocol, from what the drone did with it as its `DroneEvent`s tell.
+//!
+//! A packet handled in a way no rule allows is reported as `AuxEventKind::ConformanceViolation` and counted in
+//! `DroneStats::conformance_violations`. Behaviour beyond the protocol (chaos, delays, pauses, filters...) is
+//! reported too: with the default options there should be none.
+
+use std::fmt;
+use std::mem;
+
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+use crate::stats::DroneState;
+use crate::validation;
+
+/// What the drone did with a packet.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Sent to the next hop of its route.
+    Forwarded,
+    /// Answered with a Nack of this type, sent back or to the controller.
+    Nacked(NackType),
+    /// Reported to the controller with `PacketDropped`.
+    Dropped,
+    /// Sent to the controller with `ControllerShortcut`.
+    Shortcut,
+    /// A FloodRequest sent to one neighbor.
+    FloodForwarded,
+    /// A FloodRequest answered with a FloodResponse, sent back or to the controller.
+    FloodAnswered,
+}
+
+/// A packet handled in a way the protocol doesn't allow.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// The rule that applied to the packet.
+    pub rule: &'static str,
+    /// What the rule allows.
+    pub expected: &'static str,
+    /// What the drone did, empty if nothing.
+    pub actions: Vec<Action>,
+    pub packet: Packet,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: expected {}, got {:?} for {:?}", self.rule, self.expected, self.actions, self.packet.pack_type)
+    }
+}
+
+// What the rules look at, taken before the drone handles the packet.
+struct Input {
+    packet: Packet,
+    id: NodeId,
+    state: DroneState,
+    // whether the drone has a sender to the next hop of the route
+    next_hop_known: bool,
+}
+
+// A rule of the protocol: the first one that applies to the input decides.
+struct Rule {
+    name: &'static str,
+    expected: &'static str,
+    applies: fn(&Input) -> bool,
+    allows: fn(&[Action]) -> bool,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        name: "a crashed drone handles nothing",
+        expected: "nothing",
+        applies: |input| input.state == DroneState::Crashed,
+        allows: <[Action]>::is_empty,
+    },
+    Rule {
+        name: "a crashing drone loses FloodRequests",
+        expected: "nothing",
+        applies: |input| input.state == DroneState::Crashing && is_flood_request(input),
+        allows: <[Action]>::is_empty,
+    },
+    Rule {
+        name: "a FloodRequest is forwarded to the other neighbors, or answered",
+        expected: "FloodForwarded to each other neighbor, or one FloodAnswered",
+        applies: is_flood_request,
+        allows: |actions| match actions {
+            [Action::FloodAnswered] => true,
+            [] => false,
+            actions => actions.iter().all(|action| matches!(action, Action::FloodForwarded)),
+        },
+    },
+    // the protocol doesn't say what to do with a route that can't be followed
+    Rule {
+        name: "a packet with an unusable routing header is lost or shortcut",
+        expected: "nothing, or Shortcut for Acks, Nacks and FloodResponses",
+        applies: |input| validation::check_routing_header(&input.packet.routing_header).is_err(),
+        allows: |actions| matches!(actions, [] | [Action::Shortcut]),
+    },
+    Rule {
+        name: "a fragment sent to a crashing drone is Nacked with ErrorInRouting",
+        expected: "Nacked(ErrorInRouting)",
+        applies: |input| input.state == DroneState::Crashing && is_fragment(input),
+        allows: |actions| matches!(actions, [Action::Nacked(NackType::ErrorInRouting(_))]),
+    },
+    Rule {
+        name: "a fragment for another node is Nacked with UnexpectedRecipient",
+        expected: "Nacked(UnexpectedRecipient)",
+        applies: |input| is_fragment(input) && input.packet.routing_header.current_hop() != Some(input.id),
+        allows: |actions| matches!(actions, [Action::Nacked(NackType::UnexpectedRecipient(_))]),
+    },
+    Rule {
+        name: "a fragment whose destination is the drone is Nacked with DestinationIsDrone",
+        expected: "Nacked(DestinationIsDrone)",
+        applies: |input| is_fragment(input) && input.packet.routing_header.next_hop().is_none(),
+        allows: |actions| matches!(actions, [Action::Nacked(NackType::DestinationIsDrone)]),
+    },
+    Rule {
+        name: "a fragment for a node that isn't a neighbor is Nacked with ErrorInRouting",
+        expected: "Nacked(ErrorInRouting)",
+        applies: |input| is_fragment(input) && !input.next_hop_known,
+        allows: |actions| matches!(actions, [Action::Nacked(NackType::ErrorInRouting(_))]),
+    },
+    Rule {
+        name: "a fragment is forwarded, or dropped and Nacked with Dropped",
+        expected: "Forwarded, or Dropped and Nacked(Dropped)",
+        applies: is_fragment,
+        allows: |actions| {
+            matches!(
+                actions,
+                [Action::Forwarded]
+                    | [Action::Dropped, Action::Nacked(NackType::Dropped)]
+                    | [Action::Nacked(NackType::Dropped), Action::Dropped]
+            )
+        },
+    },
+    Rule {
+        name: "Acks, Nacks and FloodResponses are forwarded, or shortcut when they can't be",
+        expected: "Forwarded or Shortcut",
+        applies: |_| true,
+        allows: |actions| matches!(actions, [Action::Forwarded] | [Action::Shortcut]),
+    },
+];
+
+fn is_fragment(input: &Input) -> bool {
+    matches!(input.packet.pack_type, PacketType::MsgFragment(_))
+}
+
+fn is_flood_request(input: &Input) -> bool {
+    matches!(input.packet.pack_type, PacketType::FloodRequest(_))
+}
+
+// The packet being handled and what the drone did with it so far.
+#[derive(Default)]
+pub(crate) struct Checker {
+    input: Option<Input>,
+    actions: Vec<Action>,
+}
+
+impl Checker {
+    pub(crate) fn begin(&mut self, packet: &Packet, id: NodeId, state: DroneState, next_hop_known: bool) {
+        self.input = Some(Input { packet: packet.clone(), id, state, next_hop_known });
+        self.actions.clear();
+    }
+
+    // events sent while no packet is being handled (timers, commands) aren't checked
+    pub(crate) fn observe(&mut self, event: &DroneEvent) {
+        let Some(input) = &self.input else {
+            return;
+        };
+        let action = match event {
+            DroneEvent::PacketDropped(_) => Action::Dropped,
+            DroneEvent::PacketSent(packet) => match &packet.pack_type {
+                PacketType::FloodRequest(_) => Action::FloodForwarded,
+                _ if is_forwarded(packet, &input.packet) => Action::Forwarded,
+                PacketType::Nack(nack) => Action::Nacked(nack.nack_type.clone()),
+                PacketType::FloodResponse(_) => Action::FloodAnswered,
+                _ => Action::Forwarded,
+            },
+            DroneEvent::ControllerShortcut(packet) => match &packet.pack_type {
+                _ if is_forwarded(packet, &input.packet) => Action::Shortcut,
+                PacketType::Nack(nack) => Action::Nacked(nack.nack_type.clone()),
+                PacketType::FloodResponse(_) => Action::FloodAnswered,
+                _ => Action::Shortcut,
+            },
+        };
+        self.actions.push(action);
+    }
+
+    // the packet is handled, the violation if the rule that applies doesn't allow what was done
+    pub(crate) fn finish(&mut self) -> Option<Violation> {
+        let input = self.input.take()?;
+        let actions = mem::take(&mut self.actions);
+        let rule = RULES.iter().find(|rule| (rule.applies)(&input))?;
+        if (rule.allows)(&actions) {
+            return None;
+        }
+        Some(Violation { rule: rule.name, expected: rule.expected, actions, packet: input.packet })
+    }
+}
+
+// the packet handled, on its way: a Nack or FloodResponse the drone created has a route of its own
+fn is_forwarded(packet: &Packet, input: &Packet) -> bool {
+    mem::discriminant(&packet.pack_type) == mem::discriminant(&input.pack_type)
+        && packet.routing_header.hops == input.routing_header.hops
+}
@@ -1,6 +1,16 @@
 #[cfg(feature = "debug")]
 macro_rules! debug {
-    ($($arg:tt)*) => { println!("[DEBUG] {}", format!($($arg)*)) }
+    ($($arg:tt)*) => {
+        if DEBUG_ENABLED.with(|enabled| enabled.get()) {
+            println!("[DEBUG] {}", format!($($arg)*))
+        }
+    }
+}
+
+// each drone runs on its own thread, so the DroneHandle can toggle the output per drone
+#[cfg(feature = "debug")]
+thread_local! {
+    static DEBUG_ENABLED: std::cell::Cell<bool> = const { std::cell::Cell::new(true) };
 }
 
 #[cfg(not(feature = "debug"))]
@@ -8,24 +18,146 @@ macro_rules! debug {
     ($($arg:tt)*) => {}
 }
 
-use crossbeam_channel::{select_biased, Receiver, SendError, Sender};
-use rand::Rng;
-use std::collections::HashMap;
+use crossbeam_channel::{at, never, select_biased, unbounded, Receiver, Select, Sender, TryRecvError};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::path::Path;
+use std::time::Duration;
+
+use web_time::Instant;
 
 use wg_2024::controller::{DroneCommand, DroneEvent};
 use wg_2024::drone::Drone;
-use wg_2024::network::NodeId;
-use wg_2024::packet::Nack;
-use wg_2024::packet::{FloodRequest, NackType, NodeType, Packet, PacketType};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, FloodResponse, NackType, NodeType, Packet, PacketType, FRAGMENT_DSIZE};
+
+use crate::behavior::{BehaviorStrategy, StandardBehavior, Unreachable, Verdict};
+use crate::channel::{ChannelReceiver, ChannelSender, SendFailure};
+use crate::clock::{Clock, ScaledClock, SystemClock};
+#[cfg(feature = "conformance")]
+use crate::conformance::Checker;
+#[cfg(feature = "otel")]
+use crate::otel::{OtlpExporter, Tracer};
+#[cfg(feature = "test-internals")]
+use crate::acl::PacketKind;
+#[cfg(feature = "test-internals")]
+use crate::internals::InjectedFailure;
+#[cfg(feature = "record")]
+use crate::clock::ManualClock;
+use crate::env::EnvDefaults;
+use crate::flood_store::FloodStore;
+use crate::handle::HandleCommand;
+use crate::acl::{Acl, AclAction};
+use crate::link::{LinkDirection, LinkDown, LinkOutage, LinkPdr, LinkProfile};
+use crate::nack;
+use crate::event_log;
+use crate::event::{self, AppliedCommand, AuxEvent, AuxEventKind, Diagnostic, DropAudit, Queue, ShortcutReason};
+#[cfg(feature = "toml")]
+use crate::profile::{self, DroneProfile};
+use crate::options::{BrokenHeaderPolicy, ChaosOptions, ControllerLossPolicy, CrashFloodPolicy, DroneOptions, DropMode, DuplicateFloodPolicy, EventOverflowPolicy, Fairness, OptionsError, OverflowPolicy, PausePolicy, UnexpectedRecipientPolicy};
+use crate::outgoing::OutgoingWorker;
+use crate::sessions::SessionTable;
+use crate::shortcut::{ShortcutLimiter, Suppressed};
+use crate::seed;
+use crate::scenario::{Scenario, ScenarioAction, ScenarioStep, ScenarioTrigger};
+use crate::stats::{self, DroneState, DroneStats, FloodRecord, MemoryUsage, NeighborStats, SessionStats};
+use crate::tap::Tap;
+use crate::timer_wheel::TimerWheel;
+#[cfg(feature = "record")]
+use crate::record::{RecordedCommand, RecordedEntry, RecordedInput, Recorder, Recording, RecordingHeader, ReplayOutput, ReplayStep};
+#[cfg(feature = "snapshot")]
+use crate::snapshot::{DroneSnapshot, SnapshotError};
+use crate::validation;
+use crate::violation::{ProtocolViolation, ViolationSink};
+use crate::watchdog::{Busy, Watchdog};
+
+// How often buffered events are sent again while the controller's channel is full.
+const EVENT_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+// Why forward_packet didn't send a packet.
+enum ForwardError {
+    // no usable link to the next hop, the packet is given back (boxed, to keep the Result small)
+    NoLink(Box<Packet>),
+    // the next hop's channel is full, DroneOptions::overflow_policy was applied
+    Overflow,
+    // held back by a link profile, sent (and reported) once it's through the link
+    Delayed,
+    // the route ends at this drone, handle_broken_header already reported it
+    BrokenHeader,
+}
 
 pub struct RustaceansWitAttitudesDrone {
     id: NodeId,
-    controller_send: Sender<DroneEvent>,        // send to sc
-    controller_recv: Receiver<DroneCommand>,    // receive from sc
+    controller_send: Box<dyn ChannelSender<DroneEvent>>,   // send to sc
+    controller_recv: Receiver<DroneCommand>,    // receive from sc (never() once it disconnected)
     packet_recv: Receiver<Packet>,              // receive to neighbor nodes
+    ingress: Vec<Receiver<Packet>>,             // more receivers read in turn with packet_recv (never() once disconnected)
+    next_ingress: usize,                        // receiver to try first, 0 is packet_recv
     pdr: f32,
-    packet_send: HashMap<NodeId, Sender<Packet>>,   // send to neighbor nodes
-    flood_initiators: HashMap<u64, NodeId>,
+    packet_send: HashMap<NodeId, Box<dyn ChannelSender<Packet>>>,   // send to neighbor nodes
+    flood_store: FloodStore,                    // floods already forwarded, maybe shared with other drones
+    answered_floods: HashSet<(u64, NodeId)>,   // duplicates already answered (RespondOncePerInitiator)
+    own_floods: HashMap<u64, Sender<FloodResponse>>,   // floods we initiated -> where their responses go
+    options: DroneOptions,
+    aux_send: Option<Sender<AuxEvent>>,         // send extra events (optional)
+    stats: Arc<Mutex<DroneStats>>,
+    sessions: SessionTable,                     // sessions seen recently, with the Nacks sent for them
+    handle_recv: Receiver<HandleCommand>,       // receive from the DroneHandle (never() without one)
+    paused: bool,
+    paused_backlog: VecDeque<Packet>,           // packets received while paused (PausePolicy::Nack)
+    over_memory_budget: bool,                   // DroneOptions::memory_budget exceeded, reported once
+    congested: HashSet<Queue>,                  // queues over DroneOptions::congestion_threshold
+    packets_since_command: u32,                 // for DroneOptions::fairness
+    shutdown: bool,
+    state: DroneState,
+    crash_deadline: Option<Instant>,            // DroneOptions::crash_drain_timeout, in clock time
+    controller_lost: bool,
+    pending_events: VecDeque<DroneEvent>,       // waiting for room in controller_send (EventOverflowPolicy::Buffer)
+    event_retry_at: Option<Instant>,
+    started_at: Instant,
+    clock: Box<dyn Clock>,
+    scenario: Vec<ScenarioStep>,                // steps not executed yet
+    packets_received: u64,                      // for the scenario, the stats can be reset
+    next_stats_reset: Option<Instant>,          // DroneOptions::stats_reset_interval
+    next_neighbor_probe: Option<Instant>,       // DroneOptions::neighbor_probe_interval
+    disconnected_neighbors: HashSet<NodeId>,    // neighbors whose receiver is gone
+    links_down: HashMap<NodeId, LinkDown>,      // neighbors we keep the sender of but don't use
+    link_queues: HashMap<NodeId, VecDeque<Packet>>,   // fragments waiting for a link to come back up
+    shortcut_limiter: ShortcutLimiter,          // DroneOptions::shortcut_rate_limit
+    acl: Acl,
+    link_profiles: HashMap<NodeId, LinkProfile>,    // per-direction behaviour of some links
+    links_busy_until: HashMap<(NodeId, bool), Instant>,   // (neighbor, egress) -> end of the last transmission
+    in_flight: TimerWheel<(NodeId, Packet)>,    // packets held back by a link profile, by arrival time
+    energy_remaining: Option<u64>,
+    rng: StdRng,
+    seed: u64,                                  // seed of rng, kept to make recordings replayable
+    fragments_sampled: u64,                     // fragments the drop mode was applied to
+    scripted_drops: HashSet<(u64, u64)>,        // (session, fragment) already dropped (DropMode::FragmentIndices)
+    delayed_packets: TimerWheel<Packet>,        // packets to forward later, by release time
+    stored_packets: Vec<(Instant, u32, Packet)>,    // next hop unreachable: retry time, attempts so far
+    behavior: Box<dyn BehaviorStrategy>,
+    unacked_fragments: HashMap<(u64, u64), (Instant, u32, Packet)>,   // (session, fragment) -> Ack due by, resends, packet as received
+    held_fragments: Vec<(u32, Packet)>,         // fragments to forward after this many others (reorder)
+    taps: Vec<Tap>,                             // mirror of every packet sent to a neighbor
+    drop_log: Option<LineWriter<File>>,         // one line per dropped fragment
+    violation_sinks: Vec<Box<dyn ViolationSink>>,   // told about every malformed packet received
+    watchdog: Option<Arc<Watchdog>>,            // started by start() if there is a watchdog deadline
+    env: EnvDefaults,                           // RWA_* environment variables read by new()
+    #[cfg(feature = "record")]
+    recorder: Option<Recorder>,
+    #[cfg(feature = "conformance")]
+    conformance: Checker,                       // what was done with the packet being handled
+    #[cfg(feature = "otel")]
+    tracer: Option<Tracer>,                     // span of the packet being handled
+    #[cfg(feature = "test-internals")]
+    failing_sends: Vec<(Option<NodeId>, Option<PacketKind>, InjectedFailure)>,    // sends failed on purpose, see internals::fail_sends
 }
 
 impl Drone for RustaceansWitAttitudesDrone {
@@ -37,36 +169,350 @@ impl Drone for RustaceansWitAttitudesDrone {
         packet_send: HashMap<NodeId, Sender<Packet>>,
         pdr: f32,
     ) -> Self {
+        let env = EnvDefaults::read();
+        let seed = env.seed.map_or_else(rand::random, |simulation_seed| seed::derive_seed(simulation_seed, id));
+        let pdr = env.pdr.unwrap_or(pdr);
+        let clock: Box<dyn Clock> = match env.time_scale {
+            Some(factor) => Box::new(ScaledClock::new(factor)),
+            None => Box::new(SystemClock),
+        };
         Self {
             id,
-            controller_send,
+            controller_send: Box::new(controller_send),
             controller_recv,
             packet_recv,
-            packet_send,
+            ingress: Vec::new(),
+            next_ingress: 0,
+            packet_send: packet_send.into_iter().map(|(id, sender)| (id, Box::new(sender) as Box<dyn ChannelSender<Packet>>)).collect(),
             pdr,
-            flood_initiators: HashMap::new()
+            flood_store: FloodStore::new(),
+            answered_floods: HashSet::new(),
+            own_floods: HashMap::new(),
+            options: DroneOptions { aux_event_filter: env.event_filter.clone(), ..DroneOptions::default() },
+            env,
+            aux_send: None,
+            stats: Arc::new(Mutex::new(DroneStats { pdr, ..DroneStats::default() })),
+            sessions: SessionTable::default(),
+            handle_recv: never(),
+            paused: false,
+            paused_backlog: VecDeque::new(),
+            over_memory_budget: false,
+            congested: HashSet::new(),
+            packets_since_command: 0,
+            shutdown: false,
+            state: DroneState::Running,
+            crash_deadline: None,
+            controller_lost: false,
+            pending_events: VecDeque::new(),
+            event_retry_at: None,
+            started_at: clock.now(),
+            clock,
+            scenario: Vec::new(),
+            packets_received: 0,
+            next_stats_reset: None,
+            next_neighbor_probe: None,
+            disconnected_neighbors: HashSet::new(),
+            links_down: HashMap::new(),
+            link_queues: HashMap::new(),
+            shortcut_limiter: ShortcutLimiter::default(),
+            acl: Acl::default(),
+            link_profiles: HashMap::new(),
+            links_busy_until: HashMap::new(),
+            in_flight: TimerWheel::default(),
+            energy_remaining: None,
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+            fragments_sampled: 0,
+            scripted_drops: HashSet::new(),
+            delayed_packets: TimerWheel::default(),
+            stored_packets: Vec::new(),
+            unacked_fragments: HashMap::new(),
+            behavior: Box::new(StandardBehavior),
+            held_fragments: Vec::new(),
+            taps: Vec::new(),
+            drop_log: None,
+            violation_sinks: Vec::new(),
+            watchdog: None,
+            #[cfg(feature = "record")]
+            recorder: None,
+            #[cfg(feature = "conformance")]
+            conformance: Checker::default(),
+            #[cfg(feature = "otel")]
+            tracer: None,
+            #[cfg(feature = "test-internals")]
+            failing_sends: Vec::new(),
         }
     }
 
     fn run(&mut self) {
-        loop {
-            select_biased! {
-                recv(self.controller_recv) -> command => {
-                    if let Ok(command) = command {
-                        self.handle_command(command);
-                    }
-                },
-                recv(self.packet_recv) -> packet => {
-                    if let Ok(packet) = packet {
-                        self.handle_packet(packet);
-                    }
-                },
+        self.start();
+        if !self.ingress.is_empty() {
+            self.run_ingress();
+            return;
+        }
+        while !self.is_shut_down() {
+            let packet_recv = if self.accepts_packets() {
+                self.packet_recv.clone()
+            } else {
+                never()
+            };
+            // wake up for the next timed scenario step
+            let timer = match self.timer_deadline() {
+                Some(deadline) => at(deadline),
+                None => never(),
+            };
+            if !self.try_packet_first() {
+                select_biased! {
+                    recv(self.controller_recv) -> command => {
+                        match command {
+                            Ok(command) => self.dispatch_command(command),
+                            Err(_) => self.controller_disconnected(),
+                        }
+                    },
+                    recv(self.handle_recv) -> command => {
+                        match command {
+                            Ok(command) => self.handle_handle_command(command),
+                            // the DroneHandle was dropped
+                            Err(_) => self.handle_recv = never(),
+                        }
+                    },
+                    recv(packet_recv) -> packet => {
+                        match packet {
+                            Ok(packet) => self.dispatch_packet(packet),
+                            Err(_) => self.packets_disconnected(),
+                        }
+                    },
+                    recv(timer) -> _ => {},
+                }
+            }
+            self.housekeeping(self.packets_pending());
+            if self.is_crashing() {
+                self.run_crashing();
             }
         }
     }
 }
 
 impl RustaceansWitAttitudesDrone {
+    /// Like `Drone::new`, over any supported channel backend instead of crossbeam only.
+    /// `AddSender` commands still carry crossbeam senders.
+    pub fn with_channels(
+        id: NodeId,
+        controller_send: impl ChannelSender<DroneEvent> + 'static,
+        controller_recv: impl ChannelReceiver<DroneCommand>,
+        packet_recv: impl ChannelReceiver<Packet>,
+        packet_send: HashMap<NodeId, Box<dyn ChannelSender<Packet>>>,
+        pdr: f32,
+    ) -> Self {
+        let controller_recv = controller_recv.into_crossbeam(format!("rwa-drone-{}-commands", id));
+        let packet_recv = packet_recv.into_crossbeam(format!("rwa-drone-{}-packets", id));
+        let mut drone = Self::new(id, unbounded().0, controller_recv, packet_recv, HashMap::new(), pdr);
+        drone.controller_send = Box::new(controller_send);
+        drone.packet_send = packet_send;
+        drone
+    }
+    pub fn with_options(mut self, mut options: DroneOptions) -> Self {
+        self.energy_remaining = options.energy.map(|energy| energy.budget);
+        self.stats_mut().energy_remaining = self.energy_remaining;
+        let derived = options.simulation_seed.map(|simulation_seed| seed::derive_seed(simulation_seed, self.id));
+        if let Some(seed) = options.seed.or(derived) {
+            self.rng = StdRng::seed_from_u64(seed);
+            self.seed = seed;
+        }
+        if options.aux_event_filter.is_none() {
+            options.aux_event_filter = self.env.event_filter.clone();
+        }
+        self.options = options;
+        self
+    }
+    /// Like `with_options`, but the options are checked first, see `DroneOptions::validate`.
+    pub fn try_with_options(self, options: DroneOptions) -> Result<Self, OptionsError> {
+        options.validate()?;
+        Ok(self.with_options(options))
+    }
+    pub fn with_scenario(mut self, scenario: Scenario) -> Self {
+        self.scenario = scenario.steps;
+        self
+    }
+    /// Apply the pdr, options, link profiles and fault schedule of a profile loaded with `Profiles`.
+    #[cfg(feature = "toml")]
+    pub fn with_profile(mut self, profile: &DroneProfile) -> Self {
+        if let Some(pdr) = profile.pdr {
+            self.pdr = pdr;
+        }
+        for (node_id, link_profile) in &profile.links {
+            self.set_link_profile(*node_id, *link_profile);
+        }
+        self.with_options(profile.options.clone()).with_scenario(profile.scenario.clone())
+    }
+    /// Use `clock` instead of the real time, see `ManualClock`.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.started_at = clock.now();
+        self.clock = Box::new(clock);
+        self
+    }
+    /// Run the variant of the drone `behavior` stands for, see `BehaviorStrategy`.
+    pub fn with_behavior(mut self, behavior: impl BehaviorStrategy + 'static) -> Self {
+        debug!("Drone: {:?} behaves as {:?}", self.id, behavior.name());
+        self.behavior = Box::new(behavior);
+        self
+    }
+    /// Remember the floods in `store`, shared with other drones of the process, see `FloodStore`.
+    pub fn with_flood_store(mut self, store: FloodStore) -> Self {
+        self.flood_store = store;
+        self
+    }
+    /// Filter the packets the drone handles, see `Acl`.
+    pub fn with_acl(mut self, acl: Acl) -> Self {
+        self.acl = acl;
+        self
+    }
+    /// Delay, limit and drop the packets from and to `node_id`.
+    pub fn with_link_profile(mut self, node_id: NodeId, profile: LinkProfile) -> Self {
+        self.set_link_profile(node_id, profile);
+        self
+    }
+    /// Drop packets from and to `node_id` with these probabilities too, the rest of its profile is kept.
+    pub fn with_link_pdr(mut self, node_id: NodeId, link_pdr: LinkPdr) -> Self {
+        self.set_link_pdr(node_id, link_pdr);
+        self
+    }
+    /// Also receive packets on these channels, e.g. one per neighbor. They are read in turn with
+    /// the `packet_recv` of `new()`, one packet each, so a busy neighbor can't starve the others.
+    /// `RustaceansWitAttitudesDroneAsync` only reads its own packet channel.
+    pub fn with_ingress(mut self, receivers: impl IntoIterator<Item = Receiver<Packet>>) -> Self {
+        self.ingress.extend(receivers);
+        self
+    }
+    pub fn with_aux_sender(mut self, aux_send: Sender<AuxEvent>) -> Self {
+        self.aux_send = Some(aux_send);
+        self
+    }
+    /// Mirror every packet sent to a neighbor on `tap`, without changing the routing.
+    pub fn with_tap(mut self, tap: Sender<Packet>) -> Self {
+        self.taps.push(Tap::Channel(tap));
+        self
+    }
+    /// Write every packet sent to a neighbor to a text file, one line per packet.
+    pub fn with_capture_file(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        self.taps.push(Tap::capture(File::create(path)?));
+        Ok(self)
+    }
+    /// Write why every fragment was dropped to a text file, one `DropAudit` per line.
+    pub fn with_drop_log(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        self.drop_log = Some(LineWriter::new(File::create(path)?));
+        Ok(self)
+    }
+    /// Report every malformed packet received (broken header, wrong recipient, invalid fragment...) to `sink`,
+    /// with the node it comes from. Sinks add up.
+    pub fn with_violation_sink(mut self, sink: impl ViolationSink + 'static) -> Self {
+        self.violation_sinks.push(Box::new(sink));
+        self
+    }
+    /// Export a span for every packet handled to the collector of `exporter`, see `OtlpExporter`.
+    #[cfg(feature = "otel")]
+    pub fn with_otlp_exporter(mut self, exporter: &OtlpExporter) -> Self {
+        self.tracer = Some(exporter.tracer());
+        self
+    }
+    /// Record the commands and packets received while running to `path`, see `Recording`.
+    #[cfg(feature = "record")]
+    pub fn with_recording(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        self.recorder = Some(Recorder::new(File::create(path)?));
+        Ok(self)
+    }
+    pub fn stats(&self) -> DroneStats {
+        self.stats_mut().clone()
+    }
+    /// Zero the counters, see `DroneStats::reset`.
+    pub fn reset_stats(&self) {
+        self.stats_mut().reset();
+    }
+    /// Current state of the drone, see `DroneSnapshot`.
+    #[cfg(feature = "snapshot")]
+    pub fn snapshot(&self) -> DroneSnapshot {
+        DroneSnapshot {
+            drone_id: self.id,
+            pdr: self.pdr,
+            neighbors: self.neighbor_ids(),
+            floods: self.flood_store.floods_of(self.id),
+            answered_floods: self.answered_floods.iter().copied().collect(),
+            stats: self.stats(),
+            crashing: self.is_crashing(),
+            paused: self.paused,
+            seed: self.seed,
+        }
+    }
+    /// Resume from `snapshot`, taken from a drone with the same id and neighbors.
+    /// Call it after `with_options`, which resets the energy left.
+    #[cfg(feature = "snapshot")]
+    pub fn with_snapshot(mut self, snapshot: DroneSnapshot) -> Result<Self, SnapshotError> {
+        if snapshot.drone_id != self.id {
+            return Err(SnapshotError::WrongDrone { expected: snapshot.drone_id, found: self.id });
+        }
+        let neighbors = self.neighbor_ids();
+        if snapshot.neighbors != neighbors {
+            return Err(SnapshotError::NeighborsMismatch { expected: snapshot.neighbors, found: neighbors });
+        }
+        self.pdr = snapshot.pdr;
+        for (flood_id, initiator_id) in snapshot.floods {
+            self.remember_flood(flood_id, initiator_id);
+        }
+        self.answered_floods = snapshot.answered_floods.into_iter().collect();
+        // restored sessions are idle from now on
+        let now = self.clock.now();
+        self.sessions.restore(snapshot.stats.sessions.keys().copied(), now);
+        self.energy_remaining = snapshot.stats.energy_remaining;
+        *self.stats_mut() = snapshot.stats;
+        if snapshot.crashing {
+            self.set_state(DroneState::Crashing);
+        }
+        self.paused = snapshot.paused;
+        self.rng = StdRng::seed_from_u64(snapshot.seed);
+        self.seed = snapshot.seed;
+        Ok(self)
+    }
+    /// Seed of the random decisions, pass it as `DroneOptions::seed` to repeat them.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+    /// Current packet drop rate, after `RWA_PDR` and the `SetPacketDropRate` commands.
+    pub fn pdr(&self) -> f32 {
+        self.pdr
+    }
+    /// Nodes the drone has a sender to, sorted.
+    pub fn neighbor_ids(&self) -> Vec<NodeId> {
+        let mut neighbor_ids: Vec<NodeId> = self.packet_send.keys().copied().collect();
+        neighbor_ids.sort_unstable();
+        neighbor_ids
+    }
+    pub(crate) fn shared_stats(&self) -> Arc<Mutex<DroneStats>> {
+        self.stats.clone()
+    }
+    pub(crate) fn receivers(&self) -> (Receiver<DroneCommand>, Receiver<HandleCommand>) {
+        (self.controller_recv.clone(), self.handle_recv.clone())
+    }
+    // the ingress channels still read, packet_recv first
+    pub(crate) fn ingress(&self) -> Vec<Receiver<Packet>> {
+        self.ingress_receivers().cloned().collect()
+    }
+    fn ingress_receivers(&self) -> impl Iterator<Item = &Receiver<Packet>> {
+        std::iter::once(&self.packet_recv).chain(&self.ingress)
+    }
+    // packets waiting on all the ingress channels
+    fn ingress_len(&self) -> usize {
+        self.ingress_receivers().map(Receiver::len).sum()
+    }
+    pub(crate) fn packets_pending(&self) -> bool {
+        self.ingress_receivers().any(|packet_recv| !packet_recv.is_empty())
+    }
+    pub(crate) fn attach_handle(&mut self, handle_recv: Receiver<HandleCommand>) {
+        self.handle_recv = handle_recv;
+    }
+
     // <editor-fold desc="Simulation controller commands">
     fn handle_command(&mut self, command: DroneCommand) {
         match command {
@@ -74,7 +520,8 @@ impl RustaceansWitAttitudesDrone {
                 debug!("Drone: {:?} received command SetPacketDropRate", self.id);
                 debug!("Drone: {:?} changed pdf from {:?} to {:?}", self.id, self.pdr, _pdr);
                 debug!("Drone: {:?} neighbours: {:?} ", self.id, self.packet_send.keys());
-                self.pdr = _pdr
+                self.pdr = _pdr;
+                self.send_aux_event(AuxEventKind::CommandApplied(AppliedCommand::SetPacketDropRate(_pdr)));
             },
             DroneCommand::Crash => {
                 debug!("Drone: {:?} received command Crash", self.id);
@@ -90,98 +537,1025 @@ impl RustaceansWitAttitudesDrone {
             },
         }
     }
+    pub(crate) fn handle_handle_command(&mut self, command: HandleCommand) {
+        let _busy = self.busy();
+        self.packets_since_command = 0;
+        debug!("Drone: {:?} received handle command {:?}", self.id, command);
+        if self.is_crashing() {
+            self.handle_handle_command_while_crashing(command);
+            return;
+        }
+        match command {
+            HandleCommand::Pause => self.paused = true,
+            HandleCommand::Resume => {
+                self.paused = false;
+                while let Some(packet) = self.paused_backlog.pop_front() {
+                    self.handle_packet_isolated(packet);
+                }
+            }
+            HandleCommand::SetPdr(pdr) => self.dispatch_command(DroneCommand::SetPacketDropRate(pdr)),
+            HandleCommand::Crash => self.dispatch_command(DroneCommand::Crash),
+            HandleCommand::SetDebug(_enabled) => {
+                #[cfg(feature = "debug")]
+                DEBUG_ENABLED.with(|enabled| enabled.set(_enabled));
+            }
+            HandleCommand::LinkDown(node_id, outage) => self.link_down(node_id, outage),
+            HandleCommand::LinkUp(node_id) => self.link_up(node_id),
+            HandleCommand::SetLinkPdr(node_id, link_pdr) => self.set_link_pdr(node_id, link_pdr),
+            HandleCommand::SetLinkProfile(node_id, profile) => self.set_link_profile(node_id, profile),
+            HandleCommand::SetAcl(acl) => self.acl = acl,
+            #[cfg(feature = "toml")]
+            HandleCommand::ReloadProfile(profile) => self.reload_profile(*profile),
+            #[cfg(feature = "toml")]
+            HandleCommand::ProfileReloadFailed(reason) => self.send_aux_event(AuxEventKind::ProfileReloadFailed { reason }),
+            HandleCommand::InitiateFlood(response_send) => self.initiate_flood(response_send),
+            #[cfg(feature = "snapshot")]
+            HandleCommand::Snapshot(snapshot_send) => {
+                // the handle may have given up waiting
+                let _ = snapshot_send.send(self.snapshot());
+            }
+            HandleCommand::Shutdown => self.shutdown = true,
+        }
+    }
+    pub(crate) fn start(&mut self) {
+        self.started_at = self.clock.now();
+        self.update_neighbor_ids();
+        #[cfg(feature = "debug")]
+        if let Some(debug) = self.env.debug {
+            DEBUG_ENABLED.with(|enabled| enabled.set(debug));
+        }
+        #[cfg(feature = "record")]
+        self.start_recording();
+        self.start_watchdog();
+        self.start_outgoing_workers();
+        self.next_stats_reset = self.options.stats_reset_interval
+            .filter(|interval| !interval.is_zero())
+            .map(|interval| self.started_at + interval);
+        self.next_neighbor_probe = self.options.neighbor_probe_interval
+            .filter(|interval| !interval.is_zero())
+            .map(|interval| self.started_at + interval);
+    }
+    // shut down by the handle or crashed
+    pub(crate) fn is_shut_down(&self) -> bool {
+        self.shutdown || self.state == DroneState::Crashed
+    }
+    pub(crate) fn is_crashing(&self) -> bool {
+        self.state == DroneState::Crashing
+    }
+    fn set_state(&mut self, state: DroneState) {
+        debug!("Drone: {:?} {:?} -> {:?}", self.id, self.state, state);
+        self.state = state;
+        self.stats_mut().state = state;
+    }
+    // while paused with PausePolicy::Buffer, or a full backlog and OverflowPolicy::Block, packets are left in the channel
+    pub(crate) fn accepts_packets(&self) -> bool {
+        let backlog_full = self.paused_backlog.len() >= self.options.pause_backlog_capacity
+            && self.options.overflow_policy == OverflowPolicy::Block;
+        self.is_crashing() || !(self.paused && (self.options.pause_policy == PausePolicy::Buffer || backlog_full))
+    }
+    // deadlines are in clock time, the timer waits in real time
+    pub(crate) fn timer_deadline(&self) -> Option<std::time::Instant> {
+        self.next_deadline()
+            .map(|deadline| real_time(self.clock.real_duration(deadline.saturating_duration_since(self.clock.now()))))
+    }
+    pub(crate) fn dispatch_command(&mut self, command: DroneCommand) {
+        let _busy = self.busy();
+        self.packets_since_command = 0;
+        self.record_command(&command);
+        match self.state {
+            DroneState::Running => self.handle_command(command),
+            DroneState::Crashing => self.handle_command_while_crashing(command),
+            // nothing reads the channels of a crashed drone but a replay
+            DroneState::Crashed => {
+                debug!("Drone: {:?} crashed, ignored {:?}", self.id, command);
+            }
+        }
+    }
+    pub(crate) fn dispatch_packet(&mut self, packet: Packet) {
+        let _busy = self.busy();
+        self.packets_since_command = self.packets_since_command.saturating_add(1);
+        self.record_packet(&packet);
+        #[cfg(feature = "conformance")]
+        {
+            let next_hop_known = packet.routing_header.next_hop().is_some_and(|next_hop| self.packet_send.contains_key(&next_hop));
+            self.conformance.begin(&packet, self.id, self.state, next_hop_known);
+        }
+        #[cfg(feature = "otel")]
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.begin(&packet);
+        }
+        if self.state == DroneState::Crashed {
+            debug!("Drone: {:?} crashed, lost {:?}", self.id, packet.pack_type);
+        } else if self.is_crashing() {
+            self.handle_packet_while_crashing(packet);
+        } else if self.paused {
+            self.handle_packet_while_paused(packet);
+        } else {
+            self.handle_packet_isolated(packet);
+        }
+        #[cfg(feature = "conformance")]
+        self.check_conformance();
+        #[cfg(feature = "otel")]
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.finish(self.id);
+        }
+    }
+    #[cfg(feature = "conformance")]
+    fn check_conformance(&mut self) {
+        if let Some(violation) = self.conformance.finish() {
+            debug!("Drone: {:?} violated the protocol: {}", self.id, violation);
+            self.stats_mut().conformance_violations += 1;
+            self.send_aux_event(AuxEventKind::ConformanceViolation(violation));
+        }
+    }
+    // The simulation controller is gone, stop reading its channel (it would be ready forever).
+    pub(crate) fn controller_disconnected(&mut self) {
+        if self.controller_lost {
+            return;
+        }
+        debug!("Drone: {:?} lost the simulation controller", self.id);
+        self.controller_lost = true;
+        self.controller_recv = never();
+        if self.options.controller_loss_policy == ControllerLossPolicy::Crash {
+            self.crash();
+        }
+    }
+    // Every neighbor dropped its sender to us. Without a controller no RemoveSender will
+    // complete a crash, so it completes now.
+    pub(crate) fn packets_disconnected(&mut self) {
+        if self.is_crashing() && self.controller_lost {
+            debug!("Drone: {:?} completed the crash without a controller", self.id);
+            self.complete_crash();
+        }
+    }
+    // Whether a waiting packet goes before the waiting commands (DroneOptions::fairness).
+    pub(crate) fn packets_first(&self) -> bool {
+        match self.options.fairness {
+            Fairness::Biased => false,
+            Fairness::RoundRobin => self.packets_since_command < 1,
+            Fairness::PacketsPerCommandCheck(packets) => self.packets_since_command < packets,
+        }
+    }
+    // Handle a waiting packet if it's its turn, false if the commands come first.
+    fn try_packet_first(&mut self) -> bool {
+        // a crash is always handled in order
+        if self.is_crashing() || !self.packets_first() {
+            return false;
+        }
+        match self.try_recv_packet() {
+            Some(packet) => {
+                self.dispatch_packet(packet);
+                true
+            }
+            None => false,
+        }
+    }
+    // Handle at most one pending input without blocking, false if there was nothing to do.
+    // Same priorities as run(): controller, then handle, then packets, unless it's the packets' turn.
+    pub(crate) fn step(&mut self) -> bool {
+        let crashing = self.is_crashing();
+        let handled = if self.try_packet_first() {
+            true
+        } else if let Some(command) = self.try_recv_command() {
+            self.dispatch_command(command);
+            true
+        } else if let Some(command) = self.try_recv_handle_command() {
+            self.handle_handle_command(command);
+            true
+        } else if let Some(packet) = self.try_recv_packet() {
+            self.dispatch_packet(packet);
+            true
+        } else {
+            false
+        };
+        // timers don't run while crashing, only the drain timeout does
+        if crashing {
+            let due = self.crash_deadline.is_some_and(|deadline| deadline <= self.clock.now());
+            self.check_crash_deadline();
+            return handled || due;
+        }
+        let due = self.next_deadline().is_some_and(|deadline| deadline <= self.clock.now());
+        if handled || due {
+            self.housekeeping(self.packets_pending());
+        }
+        handled || due
+    }
+    fn try_recv_command(&mut self) -> Option<DroneCommand> {
+        match self.controller_recv.try_recv() {
+            Ok(command) => Some(command),
+            Err(TryRecvError::Disconnected) => {
+                self.controller_disconnected();
+                None
+            }
+            Err(TryRecvError::Empty) => None,
+        }
+    }
+    fn try_recv_handle_command(&mut self) -> Option<HandleCommand> {
+        match self.handle_recv.try_recv() {
+            Ok(command) => Some(command),
+            // the DroneHandle was dropped
+            Err(TryRecvError::Disconnected) => {
+                self.handle_recv = never();
+                None
+            }
+            Err(TryRecvError::Empty) => None,
+        }
+    }
+    pub(crate) fn try_recv_packet(&mut self) -> Option<Packet> {
+        if !self.accepts_packets() {
+            return None;
+        }
+        // one packet per ingress channel in turn, starting after the last one read
+        let count = self.ingress.len() + 1;
+        for offset in 0..count {
+            let index = (self.next_ingress + offset) % count;
+            let packet_recv = if index == 0 { &self.packet_recv } else { &self.ingress[index - 1] };
+            match packet_recv.try_recv() {
+                Ok(packet) => {
+                    self.next_ingress = (index + 1) % count;
+                    return Some(packet);
+                }
+                Err(TryRecvError::Disconnected) if index == 0 => self.packets_disconnected(),
+                // a neighbor's own channel is gone, the others still work
+                Err(TryRecvError::Disconnected) => self.ingress[index - 1] = never(),
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        None
+    }
+    // Register the channels step() would read from.
+    pub(crate) fn watch<'a>(&'a self, select: &mut Select<'a>) {
+        select.recv(&self.controller_recv);
+        select.recv(&self.handle_recv);
+        if self.accepts_packets() {
+            for packet_recv in self.ingress_receivers() {
+                select.recv(packet_recv);
+            }
+        }
+    }
+    // work due after every input, except while crashing: timers, scenario steps, energy
+    pub(crate) fn housekeeping(&mut self, packets_pending: bool) {
+        let _busy = self.busy();
+        self.release_delayed_packets();
+        self.retry_stored_packets();
+        self.resend_unacked_fragments();
+        self.release_link_queues();
+        self.expire_shortcut_windows();
+        self.flush_pending_events();
+        self.release_in_flight();
+        // don't hold fragments back when nothing else is coming
+        if !packets_pending {
+            self.release_held_fragments(true);
+        }
+        self.run_scenario();
+        self.reset_stats_if_due();
+        self.update_memory_usage();
+        {
+            let (pdr, sessions_tracked) = (self.pdr, self.sessions.len());
+            let mut stats = self.stats_mut();
+            stats.pdr = pdr;
+            stats.sessions_tracked = sessions_tracked;
+        }
+        self.sample_channels();
+        self.check_congestion();
+        self.probe_neighbors_if_due();
+        if self.energy_remaining == Some(0) {
+            debug!("Drone: {:?} ran out of energy", self.id);
+            self.energy_remaining = None;
+            self.send_aux_event(AuxEventKind::EnergyDepleted);
+            self.crash();
+        }
+    }
+    // occupancy of the channels, to match drops and delays with queues building up
+    fn sample_channels(&self) {
+        let queue_depth = self.ingress_len();
+        let ingress_full = self.ingress_receivers().any(Receiver::is_full);
+        let mut stats = self.stats_mut();
+        stats.queue_depth = queue_depth;
+        stats.ingress_full = ingress_full;
+        for (node_id, sender) in &self.packet_send {
+            let neighbor = stats.neighbors.entry(*node_id).or_default();
+            neighbor.queued = sender.queued();
+            neighbor.full = sender.is_full();
+        }
+    }
+    fn memory_usage(&self) -> MemoryUsage {
+        // std's LineWriter buffer
+        const LINE_WRITER_CAPACITY: usize = 1024;
+        // one entry in the table and one in the order of the drone
+        let flood_entry = 2 * mem::size_of::<(u64, NodeId)>() + mem::size_of::<NodeId>();
+        let flood_table = self.flood_store.len_of(self.id) * flood_entry
+            + stats::table_bytes::<(u64, NodeId)>(self.answered_floods.capacity())
+            + stats::table_bytes::<(u64, Sender<FloodResponse>)>(self.own_floods.capacity());
+
+        let held = self.paused_backlog.iter()
+            .chain(self.delayed_packets.iter().map(|(_, packet)| packet))
+            .chain(self.stored_packets.iter().map(|(_, _, packet)| packet))
+            .chain(self.unacked_fragments.values().map(|(_, _, packet)| packet))
+            .chain(self.link_queues.values().flatten())
+            .chain(self.in_flight.iter().map(|(_, (_, packet))| packet))
+            .chain(self.held_fragments.iter().map(|(_, packet)| packet));
+        let queued_packets = self.ingress_len() * mem::size_of::<Packet>() + held.map(stats::packet_bytes).sum::<usize>();
+
+        let sessions = self.sessions.bytes()
+            + stats::table_bytes::<(u64, u64)>(self.scripted_drops.capacity());
+
+        let captures = self.taps.iter().filter(|tap| matches!(tap, Tap::Capture(_))).count() + usize::from(self.drop_log.is_some());
+        #[cfg(feature = "record")]
+        let captures = captures + usize::from(self.recorder.is_some());
+
+        let stats = self.stats_mut();
+        let statistics = stats::table_bytes::<(u64, SessionStats)>(stats.sessions.capacity())
+            + stats::table_bytes::<(NodeId, NeighborStats)>(stats.neighbors.capacity())
+            + stats::table_bytes::<FloodRecord>(stats.floods.capacity())
+            + stats::table_bytes::<u64>(stats.flood_fan_out.buckets.capacity() + stats.flood_response_latency.buckets.capacity());
+
+        MemoryUsage { flood_table, queued_packets, sessions, capture_buffers: captures * LINE_WRITER_CAPACITY, statistics }
+    }
+    fn update_memory_usage(&mut self) {
+        let memory = self.memory_usage();
+        self.stats_mut().memory = memory;
+        let Some(budget) = self.options.memory_budget else {
+            return;
+        };
+        let over_budget = memory.total() > budget;
+        if over_budget && !self.over_memory_budget {
+            debug!("Drone: {:?} uses about {:?} bytes, more than its budget", self.id, memory.total());
+            self.send_aux_event(AuxEventKind::MemoryBudgetExceeded { memory, budget });
+        }
+        self.over_memory_budget = over_budget;
+    }
+    fn start_watchdog(&mut self) {
+        let (Some(deadline), Some(aux_send)) = (self.options.watchdog_deadline, &self.aux_send) else {
+            return;
+        };
+        // the watchdog only sends Stalled and Recovered
+        if !self.aux_event_allowed("Stalled") && !self.aux_event_allowed("Recovered") {
+            return;
+        }
+        match Watchdog::spawn(self.id, deadline, aux_send.clone()) {
+            Ok(watchdog) => self.watchdog = Some(watchdog),
+            // the drone works the same without it
+            Err(_e) => {
+                debug!("Drone: {:?} can't start its watchdog: {:?}", self.id, _e);
+            }
+        }
+    }
+    // DroneOptions::outgoing_queue, for the senders given before the drone starts
+    fn start_outgoing_workers(&mut self) {
+        if self.options.outgoing_queue.is_none() {
+            return;
+        }
+        let packet_send = mem::take(&mut self.packet_send);
+        self.packet_send = packet_send.into_iter().map(|(node_id, sender)| (node_id, self.outgoing_sender(node_id, sender))).collect();
+    }
+    fn outgoing_sender(&self, node_id: NodeId, sender: Box<dyn ChannelSender<Packet>>) -> Box<dyn ChannelSender<Packet>> {
+        let Some(capacity) = self.options.outgoing_queue else {
+            return sender;
+        };
+        match OutgoingWorker::spawn(self.id, node_id, capacity, sender) {
+            Ok(worker) => Box::new(worker),
+            // without threads (wasm32) the drone sends to the neighbor itself
+            Err(sender) => {
+                debug!("Drone: {:?} can't start the outgoing worker of {:?}", self.id, node_id);
+                sender
+            }
+        }
+    }
+    // the watchdog reports a stall if this lives too long
+    fn busy(&self) -> Option<Busy> {
+        self.watchdog.as_ref().and_then(Watchdog::busy)
+    }
+    fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_duration_since(self.started_at)
+    }
+    fn next_deadline(&self) -> Option<Instant> {
+        let scenario = self.scenario.iter().filter_map(|step| match step.trigger {
+            ScenarioTrigger::At(time) => Some(self.started_at + time),
+            ScenarioTrigger::AfterPackets(_) => None,
+        });
+        let delayed = self.delayed_packets.next_deadline().into_iter()
+            .chain(self.stored_packets.iter().map(|(retry_at, _, _)| *retry_at))
+            .chain(self.unacked_fragments.values().map(|(due_by, _, _)| *due_by))
+            .chain(self.link_queues.keys().filter_map(|id| match self.links_down.get(id) {
+                Some(LinkDown::Until(until)) => Some(*until),
+                _ => None,
+            }))
+            .chain(self.in_flight.next_deadline());
+        scenario.chain(delayed).chain(self.shortcut_limiter.next_deadline()).chain(self.event_retry_at).chain(self.next_stats_reset).chain(self.next_neighbor_probe).min()
+    }
+    fn probe_neighbors_if_due(&mut self) {
+        let (Some(next), Some(interval)) = (self.next_neighbor_probe, self.options.neighbor_probe_interval) else {
+            return;
+        };
+        let now = self.clock.now();
+        if next > now {
+            return;
+        }
+        let probed: Vec<(NodeId, bool)> = self.packet_send.iter()
+            .filter_map(|(node_id, sender)| sender.is_connected().map(|connected| (*node_id, connected)))
+            .collect();
+        for (node_id, connected) in probed {
+            if connected {
+                self.neighbor_reconnected(node_id);
+            } else {
+                self.neighbor_disconnected(node_id);
+            }
+        }
+        let missed = (now - next).as_nanos() / interval.as_nanos().max(1);
+        self.next_neighbor_probe = Some(next + interval * (missed as u32 + 1));
+    }
+    // also called when a send finds the receiver gone
+    fn neighbor_disconnected(&mut self, node_id: NodeId) {
+        // no sender or a simulated outage, the receiver may be fine
+        if self.next_neighbor_probe.is_none() || !self.packet_send.contains_key(&node_id) || self.links_down.contains_key(&node_id) {
+            return;
+        }
+        if self.disconnected_neighbors.insert(node_id) {
+            debug!("Drone: {:?} neighbor {:?} disconnected", self.id, node_id);
+            self.send_aux_event(AuxEventKind::NeighborDisconnected(node_id));
+        }
+    }
+    fn neighbor_reconnected(&mut self, node_id: NodeId) {
+        if self.disconnected_neighbors.remove(&node_id) {
+            debug!("Drone: {:?} neighbor {:?} reconnected", self.id, node_id);
+            self.send_aux_event(AuxEventKind::NeighborReconnected(node_id));
+        }
+    }
+    fn check_congestion(&mut self) {
+        let Some(threshold) = self.options.congestion_threshold else {
+            return;
+        };
+        // forget the neighbors that were removed
+        let packet_send = &self.packet_send;
+        self.congested.retain(|queue| match queue {
+            Queue::Ingress => true,
+            Queue::Neighbor(node_id) => packet_send.contains_key(node_id),
+        });
+        let mut depths = vec![(Queue::Ingress, Some(self.ingress_len()))];
+        depths.extend(self.packet_send.iter().map(|(node_id, sender)| (Queue::Neighbor(*node_id), sender.queued())));
+        for (queue, depth) in depths {
+            let Some(depth) = depth else {
+                continue;
+            };
+            if depth > threshold && self.congested.insert(queue) {
+                debug!("Drone: {:?} {:?} congested with {:?} packets", self.id, queue, depth);
+                self.send_aux_event(AuxEventKind::Congested { queue, depth });
+            } else if depth <= threshold / 2 && self.congested.remove(&queue) {
+                debug!("Drone: {:?} {:?} drained", self.id, queue);
+                self.send_aux_event(AuxEventKind::Drained { queue });
+            }
+        }
+    }
+    fn reset_stats_if_due(&mut self) {
+        let (Some(next), Some(interval)) = (self.next_stats_reset, self.options.stats_reset_interval) else {
+            return;
+        };
+        let now = self.clock.now();
+        if next > now {
+            return;
+        }
+        debug!("Drone: {:?} resets its statistics", self.id);
+        self.reset_stats();
+        // skip the resets missed while busy, the next one stays on the interval
+        let missed = (now - next).as_nanos() / interval.as_nanos().max(1);
+        self.next_stats_reset = Some(next + interval * (missed as u32 + 1));
+    }
+    fn run_scenario(&mut self) {
+        if self.scenario.is_empty() {
+            return;
+        }
+        let elapsed = self.elapsed();
+        let received = self.packets_received;
+        let (due, pending): (Vec<ScenarioStep>, Vec<ScenarioStep>) =
+            mem::take(&mut self.scenario).into_iter().partition(|step| match step.trigger {
+                ScenarioTrigger::AfterPackets(packets) => received >= packets,
+                ScenarioTrigger::At(time) => elapsed >= time,
+            });
+        self.scenario = pending;
+        for step in due {
+            debug!("Drone: {:?} runs scenario step {:?}", self.id, step);
+            match step.action {
+                ScenarioAction::SetPdr(pdr) => self.handle_command(DroneCommand::SetPacketDropRate(pdr)),
+                ScenarioAction::Crash => self.handle_command(DroneCommand::Crash),
+                ScenarioAction::LinkDown(node_id) => self.link_down(node_id, LinkOutage::Indefinite),
+                ScenarioAction::LinkUp(node_id) => self.link_up(node_id),
+                ScenarioAction::Pause => self.handle_handle_command(HandleCommand::Pause),
+                ScenarioAction::Resume => self.handle_handle_command(HandleCommand::Resume),
+            }
+        }
+    }
+    // Running -> Crashing, a drone crashes once
     fn crash(&mut self){
+        if self.state != DroneState::Running {
+            debug!("Drone: {:?} already {:?}, ignored the crash", self.id, self.state);
+            return;
+        }
         debug!("Drone: {:?} is in crashing state", self.id);
-        loop {
+        self.set_state(DroneState::Crashing);
+        // the links won't come back up for us
+        for packet in mem::take(&mut self.link_queues).into_values().flatten() {
+            self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+        }
+        // nothing resumes a crashing drone, what it kept while paused is handled like the rest of its input
+        self.paused = false;
+        for packet in mem::take(&mut self.paused_backlog) {
+            self.handle_packet_while_crashing(packet);
+        }
+        self.crash_deadline = self.options.crash_drain_timeout.map(|timeout| self.clock.now() + timeout);
+    }
+    // when step() has something to do even without input, in real time
+    pub(crate) fn wake_deadline(&self) -> Option<std::time::Instant> {
+        self.time_to_wake().map(|after| real_time(self.clock.real_duration(after)))
+    }
+    // how long until step() has something to do even without input, in clock time
+    pub(crate) fn time_to_wake(&self) -> Option<Duration> {
+        let deadline = if self.is_crashing() { self.crash_deadline } else { self.next_deadline() };
+        deadline.map(|deadline| deadline.saturating_duration_since(self.clock.now()))
+    }
+    // real time, like timer_deadline
+    pub(crate) fn crash_deadline(&self) -> Option<std::time::Instant> {
+        self.crash_deadline
+            .map(|deadline| real_time(self.clock.real_duration(deadline.saturating_duration_since(self.clock.now()))))
+    }
+    // Complete the crash if the senders weren't removed in time.
+    pub(crate) fn check_crash_deadline(&mut self) {
+        if self.is_crashing() && self.crash_deadline.is_some_and(|deadline| deadline <= self.clock.now()) {
+            debug!("Drone: {:?} completed the crash after the drain timeout", self.id);
+            self.complete_crash();
+        }
+    }
+    // Crashing -> Crashed, the drone stops
+    fn complete_crash(&mut self) {
+        // what was sent before the last sender was removed is still handled as by a crashing drone,
+        // with no neighbor left it goes to the controller
+        for _ in 0..self.ingress_len() {
+            let Some(packet) = self.try_recv_packet() else {
+                break;
+            };
+            self.dispatch_packet(packet);
+        }
+        self.set_state(DroneState::Crashed);
+        self.crash_deadline = None;
+    }
+    // run() with several ingress channels: select_biased! can't wait on a list of them, step through
+    // the inputs like the Scheduler does instead
+    fn run_ingress(&mut self) {
+        while !self.is_shut_down() {
+            if self.step() {
+                continue;
+            }
+            let deadline = self.wake_deadline();
+            let mut select = Select::new();
+            self.watch(&mut select);
+            match deadline {
+                Some(deadline) => {
+                    let _ = select.ready_deadline(deadline);
+                }
+                None => {
+                    select.ready();
+                }
+            }
+        }
+    }
+    fn run_crashing(&mut self) {
+        while self.is_crashing() && !self.shutdown {
+            let timeout = self.crash_deadline().map_or_else(never, at);
             select_biased! {
                 recv(self.controller_recv) -> command => {
-                    if let Ok(command) = command {
-                        match command {
-                            // If no senders are left, the drone can exit the crashing state and be considered as crashed
-                            DroneCommand::RemoveSender(_node_id) => {
-                                self.remove_sender(_node_id);
-                                if self.packet_send.is_empty() {
-                                    debug!("Drone: {:?} completed the crash", self.id);
-                                    return;
-                                }
-                            }
-
-                            // Ignore other commands while crashing
-                            _ => {}
-                        }
+                    match command {
+                        Ok(command) => self.dispatch_command(command),
+                        Err(_) => self.controller_disconnected(),
+                    }
+                }
+                recv(self.handle_recv) -> command => {
+                    match command {
+                        Ok(command) => self.handle_handle_command(command),
+                        // the DroneHandle was dropped
+                        Err(_) => self.handle_recv = never(),
                     }
                 }
                 recv(self.packet_recv) -> packet => {
-                    if let Ok(mut packet) = packet {
-                        debug!("Drone: {:?} received packet {:?} while in crashing state", self.id, packet.pack_type);
-                        match packet.pack_type.clone() {
-                            // Lose FloodRequest
-                            PacketType::FloodRequest(_) => {
-                                // Do nothing
-                            }
+                    match packet {
+                        Ok(packet) => self.dispatch_packet(packet),
+                        Err(_) => self.packets_disconnected(),
+                    }
+                }
+                recv(timeout) -> _ => {}
+            }
+            self.check_crash_deadline();
+        }
+    }
+    // other commands are ignored while crashing, a second Crash included
+    fn handle_command_while_crashing(&mut self, command: DroneCommand) {
+        // If no senders are left, the drone can exit the crashing state and be considered as crashed
+        if let DroneCommand::RemoveSender(_node_id) = command {
+            self.remove_sender(_node_id);
+            if self.packet_send.is_empty() {
+                debug!("Drone: {:?} completed the crash", self.id);
+                self.complete_crash();
+            }
+        }
+    }
+    // the handle still gets its snapshot and can stop the drone, the rest is ignored like the controller's commands
+    fn handle_handle_command_while_crashing(&mut self, command: HandleCommand) {
+        match command {
+            #[cfg(feature = "snapshot")]
+            HandleCommand::Snapshot(snapshot_send) => {
+                let _ = snapshot_send.send(self.snapshot());
+            }
+            HandleCommand::Shutdown => self.shutdown = true,
+            _command => {
+                debug!("Drone: {:?} is crashing, ignored {:?}", self.id, _command);
+            }
+        }
+    }
+    fn handle_packet_while_crashing(&mut self, packet: Packet) {
+        debug!("Drone: {:?} received packet {:?} while in crashing state", self.id, packet.pack_type);
+        if !matches!(packet.pack_type, PacketType::FloodRequest(_)) {
+            if let Err(diagnostic) = validation::check_routing_header(&packet.routing_header) {
+                self.handle_broken_header(diagnostic, packet);
+                return;
+            }
+        }
+        match packet.pack_type.clone() {
+            // Lose FloodRequest, unless the crash flood policy says otherwise
+            PacketType::FloodRequest(mut _flood_request) => {
+                match self.options.crash_flood_policy {
+                    CrashFloodPolicy::Drop => {}
+                    CrashFloodPolicy::Respond => {
+                        _flood_request.increment(self.id, NodeType::Drone);
+                        let flood_response_packet = _flood_request.generate_response(packet.session_id);
+                        let p = self.forward_packet(flood_response_packet);
+                        self.report_forward(p);
+                    }
+                    CrashFloodPolicy::Forward => self.handle_packet(packet),
+                }
+            }
 
-                            // Forward Ack, Nack, and FloodResponse
-                            PacketType::Ack(_) => {
-                                let p = self.forward_packet(packet);
-                                match p{
-                                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                                    _ => {}
-                                }
-                            } 
-                            PacketType::Nack(_) => {
-                                let p = self.forward_packet(packet);
-                                match p{
-                                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                                    _ => {}
-                                }
-                            }
-                            PacketType::FloodResponse(_) => {
-                                let p = self.forward_packet(packet);
-                                match p{
-                                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                                    _ => {}
-                                }
-                            }
+            // Forward Ack, Nack, and FloodResponse
+            PacketType::Ack(_) => {
+                let p = self.forward_packet(packet);
+                self.report_forward(p);
+            } 
+            PacketType::Nack(_) => {
+                let p = self.forward_packet(packet);
+                self.report_forward(p);
+            }
+            PacketType::FloodResponse(_) => {
+                let p = self.forward_packet(packet);
+                self.report_forward(p);
+            }
 
-                            // Send Nack(ErrorInRouting) for other packet types
-                            PacketType::MsgFragment(_) => {
-                                packet.routing_header.reverse();
-                                let new_packet = Packet::new_nack(
-                                    packet.routing_header.clone(),
-                                    packet.session_id,
-                                    Nack{
-                                        fragment_index: packet.get_fragment_index(),
-                                        nack_type: NackType::ErrorInRouting(self.id)
-                                    }
-                                );
-                                let p = self.forward_packet(new_packet);
-                                match p{
-                                    Ok(_p) => {self.send_sent_to_sc(_p)}
-                                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                                }
-                            }
-                        }
+            // Send Nack(ErrorInRouting) for other packet types
+            PacketType::MsgFragment(_) => {
+                self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+            }
+        }
+    }
+    fn initiate_flood(&mut self, response_send: Sender<FloodResponse>) {
+        let flood_id = self.rng.gen();
+        debug!("Drone: {:?} initiates flood {:?}", self.id, flood_id);
+        self.own_floods.insert(flood_id, response_send);
+        let flood_request = FloodRequest { flood_id, initiator_id: self.id, path_trace: vec![(self.id, NodeType::Drone)] };
+        let packet = Packet::new_flood_request(SourceRoutingHeader { hop_index: 0, hops: Vec::new() }, self.rng.gen(), flood_request);
+        let mut sent = 0;
+        for node_id in self.packet_send.keys().cloned().collect::<Vec<NodeId>>() {
+            sent += u64::from(self.try_send_flood_request(packet.clone(), node_id));
+        }
+        if let Some(energy) = self.options.energy {
+            self.consume_energy(sent * energy.per_forward);
+        }
+    }
+    fn add_sender(&mut self, id: NodeId, sender: Sender<Packet>) {
+        debug!("Drone: {:?} add sender {:?}", self.id, id);
+        // replacing the sender of a neighbor is always fine
+        if let Some(max_neighbors) = self.options.max_neighbors {
+            if !self.packet_send.contains_key(&id) && self.packet_send.len() >= max_neighbors {
+                debug!("Drone: {:?} refused sender {:?}, already {:?} neighbors", self.id, id, self.packet_send.len());
+                self.send_aux_event(AuxEventKind::SenderRefused { node_id: id, max_neighbors });
+                return;
+            }
+        }
+        let sender = self.outgoing_sender(id, Box::new(sender));
+        self.packet_send.insert(id, sender);
+        self.update_neighbor_ids();
+        self.neighbor_reconnected(id);
+        self.send_aux_event(AuxEventKind::CommandApplied(AppliedCommand::AddSender(id)));
+    }
+    fn remove_sender(&mut self, id: NodeId) {
+        debug!("Drone: {:?} remove sender {:?}", self.id, id);
+        self.packet_send.remove(&id);
+        self.disconnected_neighbors.remove(&id);
+        self.update_neighbor_ids();
+        self.send_aux_event(AuxEventKind::CommandApplied(AppliedCommand::RemoveSender(id)));
+    }
+    fn update_neighbor_ids(&self) {
+        self.stats_mut().neighbor_ids = self.neighbor_ids();
+    }
+    fn link_down(&mut self, id: NodeId, outage: LinkOutage) {
+        debug!("Drone: {:?} link to {:?} down {:?}", self.id, id, outage);
+        self.links_down.insert(id, LinkDown::new(outage, self.clock.now()));
+    }
+    fn expire_link_outages(&mut self) {
+        if !self.links_down.is_empty() {
+            let now = self.clock.now();
+            self.links_down.retain(|_, down| !down.is_over(now));
+        }
+    }
+    fn link_up(&mut self, id: NodeId) {
+        debug!("Drone: {:?} link to {:?} up", self.id, id);
+        self.links_down.remove(&id);
+    }
+    fn set_link_pdr(&mut self, id: NodeId, link_pdr: LinkPdr) {
+        let mut profile = self.link_profile(id).copied().unwrap_or_default();
+        profile.ingress.pdr = link_pdr.ingress;
+        profile.egress.pdr = link_pdr.egress;
+        self.set_link_profile(id, profile);
+    }
+    // the profile of the link to `id`, DroneOptions::default_link if it has none of its own
+    fn link_profile(&self, id: NodeId) -> Option<&LinkProfile> {
+        self.link_profiles.get(&id).or(self.options.default_link.as_ref())
+    }
+    // packets already in flight keep their arrival time
+    fn set_link_profile(&mut self, id: NodeId, profile: LinkProfile) {
+        debug!("Drone: {:?} link to {:?} profile {:?}", self.id, id, profile);
+        // kept to override DroneOptions::default_link
+        if profile == LinkProfile::default() && self.options.default_link.is_none() {
+            self.link_profiles.remove(&id);
+        } else {
+            self.link_profiles.insert(id, profile);
+        }
+    }
+    // the seed, energy and watchdog deadline keep their value, as does the fault schedule
+    #[cfg(feature = "toml")]
+    fn reload_profile(&mut self, profile: DroneProfile) {
+        let mut changed = Vec::new();
+        if let Some(pdr) = profile.pdr.filter(|pdr| *pdr != self.pdr) {
+            self.pdr = pdr;
+            changed.push("pdr".to_string());
+        }
+        let mut links: Vec<NodeId> = self.link_profiles.keys().chain(profile.links.keys()).copied().collect();
+        links.sort_unstable();
+        links.dedup();
+        for id in links {
+            let link_profile = profile.links.get(&id).copied().unwrap_or_default();
+            if self.link_profiles.get(&id).copied().unwrap_or_default() != link_profile {
+                self.set_link_profile(id, link_profile);
+                changed.push(format!("links.{}", id));
+            }
+        }
+        let options = DroneOptions {
+            seed: self.options.seed,
+            simulation_seed: self.options.simulation_seed,
+            energy: self.options.energy,
+            watchdog_deadline: self.options.watchdog_deadline,
+            ..profile.options
+        };
+        let changed_options = profile::changed_options(&self.options, &options);
+        if !changed_options.is_empty() {
+            let stats_reset_changed = options.stats_reset_interval != self.options.stats_reset_interval;
+            let neighbor_probe_changed = options.neighbor_probe_interval != self.options.neighbor_probe_interval;
+            self.options = options;
+            let now = self.clock.now();
+            if stats_reset_changed {
+                self.next_stats_reset = self.options.stats_reset_interval.filter(|interval| !interval.is_zero()).map(|interval| now + interval);
+            }
+            if neighbor_probe_changed {
+                self.next_neighbor_probe = self.options.neighbor_probe_interval.filter(|interval| !interval.is_zero()).map(|interval| now + interval);
+            }
+            changed.extend(changed_options.into_iter().map(|option| format!("options.{}", option)));
+        }
+        debug!("Drone: {:?} reloaded its profile, changed {:?}", self.id, changed);
+        if !changed.is_empty() {
+            self.send_aux_event(AuxEventKind::ProfileReloaded { changed });
+        }
+    }
+    fn send_dropped_to_sc(&mut self, packet: Packet){
+        self.send_event(DroneEvent::PacketDropped(packet));
+    }
+    fn send_sent_to_sc(&mut self, packet: Packet){
+        self.send_event(DroneEvent::PacketSent(packet));
+    }
+    // the event for what forward_packet did with a packet
+    fn report_forward(&mut self, result: Result<Packet, ForwardError>) {
+        match result {
+            Ok(p) => self.send_sent_to_sc(p),
+            Err(ForwardError::NoLink(p)) => self.send_shortcut_to_sc(*p),
+            Err(ForwardError::Overflow | ForwardError::Delayed | ForwardError::BrokenHeader) => {}
+        }
+    }
+    // the header of `packet` points to the next hop it couldn't be sent to
+    fn send_shortcut_to_sc(&mut self, packet: Packet){
+        let reason = match packet.routing_header.current_hop() {
+            Some(next_hop) if !self.packet_send.contains_key(&next_hop) => ShortcutReason::NoSender,
+            Some(next_hop) if self.links_down.contains_key(&next_hop) => ShortcutReason::LinkDown,
+            _ => ShortcutReason::Disconnected,
+        };
+        self.send_shortcut_with_reason(packet, reason);
+    }
+    fn send_shortcut_with_reason(&mut self, packet: Packet, reason: ShortcutReason){
+        let next_hop = match reason {
+            ShortcutReason::BrokenHeader => None,
+            _ => packet.routing_header.current_hop(),
+        };
+        if let Some(limit) = self.options.shortcut_rate_limit {
+            let (allowed, summary) = self.shortcut_limiter.allow(limit, next_hop, packet.session_id, self.clock.now());
+            if let Some(summary) = summary {
+                self.send_shortcuts_suppressed(summary);
+            }
+            if !allowed {
+                debug!("Drone: {:?} suppressed shortcut {:?} towards {:?}", self.id, packet.pack_type, next_hop);
+                self.stats_mut().shortcuts_suppressed += 1;
+                return;
+            }
+        }
+        // the packet is only cloned for someone listening
+        if self.aux_send.is_some() && self.aux_event_allowed("Shortcut") {
+            self.send_aux_event(AuxEventKind::Shortcut { reason, next_hop, packet: packet.clone() });
+        }
+        self.send_event(DroneEvent::ControllerShortcut(packet));
+    }
+    fn send_event(&mut self, event: DroneEvent) {
+        #[cfg(feature = "conformance")]
+        self.conformance.observe(&event);
+        #[cfg(feature = "otel")]
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.observe(&event);
+        }
+        event_log::append(self.id, &event);
+        let sent = match self.options.event_overflow_policy {
+            EventOverflowPolicy::Block => self.controller_send.send(event).map_err(SendFailure::Disconnected),
+            EventOverflowPolicy::Drop => self.controller_send.send_timeout(event, Duration::ZERO),
+            EventOverflowPolicy::Buffer(capacity) => {
+                // behind the ones already waiting, to keep the order
+                self.flush_pending_events();
+                let sent = if self.pending_events.is_empty() {
+                    self.controller_send.send_timeout(event, Duration::ZERO)
+                } else {
+                    Err(SendFailure::Full(event))
+                };
+                match sent {
+                    Err(SendFailure::Full(event)) if self.pending_events.len() < capacity => {
+                        self.pending_events.push_back(event);
+                        self.event_retry_at.get_or_insert(self.clock.now() + EVENT_RETRY_INTERVAL);
+                        Ok(())
                     }
+                    sent => sent,
                 }
             }
+        };
+        if let Err(_failure) = sent {
+            debug!("Drone: {:?} lost event {:?}", self.id, _failure);
+            self.stats_mut().events_lost += 1;
+        }
+    }
+    // send the buffered events while the controller's channel has room
+    fn flush_pending_events(&mut self) {
+        while let Some(event) = self.pending_events.pop_front() {
+            match self.controller_send.send_timeout(event, Duration::ZERO) {
+                Ok(()) => {}
+                Err(SendFailure::Full(event)) => {
+                    self.pending_events.push_front(event);
+                    break;
+                }
+                Err(SendFailure::Disconnected(_)) => self.stats_mut().events_lost += 1,
+            }
+        }
+        self.event_retry_at = (!self.pending_events.is_empty()).then(|| self.clock.now() + EVENT_RETRY_INTERVAL);
+    }
+    fn send_shortcuts_suppressed(&self, summary: Suppressed) {
+        let Suppressed { next_hop, session_id, count, over } = summary;
+        self.send_aux_event(AuxEventKind::ShortcutsSuppressed { next_hop, session_id, count, over });
+    }
+    // summaries of the rate-limited shortcuts whose window is over
+    fn expire_shortcut_windows(&mut self) {
+        let Some(limit) = self.options.shortcut_rate_limit else {
+            return;
+        };
+        for summary in self.shortcut_limiter.expire(limit, self.clock.now()) {
+            self.send_shortcuts_suppressed(summary);
+        }
+    }
+    fn stats_mut(&self) -> MutexGuard<'_, DroneStats> {
+        self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    fn track_session(&mut self, session_id: u64, update: impl FnOnce(&mut SessionStats)) {
+        let Some(first_seen) = self.touch_session(session_id) else {
+            return;
+        };
+        let (first_seen, last_seen) = (first_seen.saturating_duration_since(self.started_at), self.elapsed());
+        let mut stats = self.stats_mut();
+        let session = stats.sessions.entry(session_id).or_insert_with(|| SessionStats { first_seen, ..Default::default() });
+        session.last_seen = last_seen;
+        update(session);
+    }
+    // the session was seen now, returns when it was first seen
+    fn touch_session(&mut self, session_id: u64) -> Option<Instant> {
+        let now = self.clock.now();
+        let evicted = self.sessions.touch(session_id, now, self.options.session_idle_timeout, self.options.session_capacity);
+        if !evicted.is_empty() {
+            let mut stats = self.stats_mut();
+            stats.sessions_evicted += evicted.len() as u64;
+            for session_id in evicted {
+                stats.sessions.remove(&session_id);
+            }
+        }
+        self.sessions.get_mut(session_id).map(|session| session.first_seen)
+    }
+    // only known neighbors get an entry, whatever the packets claim
+    fn track_neighbor(&self, node_id: NodeId, update: impl FnOnce(&mut NeighborStats)) {
+        if !self.packet_send.contains_key(&node_id) {
+            return;
+        }
+        let at = self.elapsed();
+        let mut stats = self.stats_mut();
+        let neighbor = stats.neighbors.entry(node_id).or_default();
+        neighbor.last_activity = Some(at);
+        update(neighbor);
+    }
+    // false if the flood was seen already
+    fn remember_flood(&mut self, flood_id: u64, initiator_id: NodeId) -> bool {
+        self.flood_store.remember(self.id, flood_id, initiator_id, self.options.flood_dedup_capacity)
+    }
+    fn log_flood(&self, flood_request: &FloodRequest) {
+        let capacity = self.options.flood_log_capacity;
+        if capacity == 0 {
+            return;
+        }
+        let record = FloodRecord {
+            flood_id: flood_request.flood_id,
+            initiator_id: flood_request.initiator_id,
+            first_seen: self.elapsed(),
+            from: flood_request.path_trace.last().map(|(node_id, _)| *node_id),
+            forwarded_to: 0,
+            duplicates: 0,
+            responses: 0,
+            first_response: None,
+        };
+        let mut stats = self.stats_mut();
+        stats.floods.push_back(record);
+        while stats.floods.len() > capacity {
+            stats.floods.pop_front();
+        }
+    }
+    // floods that left the log are not tracked anymore
+    fn track_flood(&self, flood_request: &FloodRequest, update: impl FnOnce(&mut FloodRecord)) {
+        let mut stats = self.stats_mut();
+        let flood = stats.floods.iter_mut().rev()
+            .find(|flood| flood.flood_id == flood_request.flood_id && flood.initiator_id == flood_request.initiator_id);
+        if let Some(flood) = flood {
+            update(flood);
         }
     }
-    fn add_sender(&mut self, id: NodeId, sender: Sender<Packet>) {
-        debug!("Drone: {:?} add sender {:?}", self.id, id);
-        self.packet_send.insert(id, sender);
+    // the first FloodResponse of a logged flood going back
+    fn track_flood_response(&self, flood_response: &FloodResponse) {
+        let Some((initiator_id, _)) = flood_response.path_trace.first() else {
+            return;
+        };
+        let now = self.elapsed();
+        let mut stats = self.stats_mut();
+        let stats = &mut *stats;
+        let flood = stats.floods.iter_mut().rev()
+            .find(|flood| flood.flood_id == flood_response.flood_id && flood.initiator_id == *initiator_id);
+        if let Some(flood) = flood.filter(|flood| flood.first_response.is_none()) {
+            let latency = now.saturating_sub(flood.first_seen);
+            flood.first_response = Some(latency);
+            stats.flood_response_latency.record(latency.as_micros() as u64);
+        }
     }
-    fn remove_sender(&mut self, id: NodeId) {
-        debug!("Drone: {:?} remove sender {:?}", self.id, id);
-        self.packet_send.remove(&id);
+    fn consume_energy(&mut self, amount: u64) {
+        if let Some(remaining) = self.energy_remaining.as_mut() {
+            *remaining = remaining.saturating_sub(amount);
+            self.stats_mut().energy_remaining = self.energy_remaining;
+        }
     }
-    fn send_dropped_to_sc(&mut self, packet: Packet){
-        self.controller_send.send(DroneEvent::PacketDropped(packet));
+    fn aux_event_allowed(&self, name: &str) -> bool {
+        self.options.aux_event_filter.as_ref().is_none_or(|names| names.iter().any(|allowed| allowed == name))
     }
-    fn send_sent_to_sc(&mut self, packet: Packet){
-        self.controller_send.send(DroneEvent::PacketSent(packet));
+    fn send_aux_event(&self, kind: AuxEventKind) {
+        if !self.aux_event_allowed(kind.name()) {
+            return;
+        }
+        if let Some(aux_send) = &self.aux_send {
+            let _ = aux_send.send(AuxEvent { drone_id: self.id, kind });
+        }
     }
-    fn send_shortcut_to_sc(&mut self, packet: Packet){
-        self.controller_send.send(DroneEvent::ControllerShortcut(packet));
+    fn send_diagnostic(&mut self, diagnostic: Diagnostic, packet: Packet) {
+        debug!("Drone: {:?} diagnostic {:?}", self.id, diagnostic);
+        if !self.violation_sinks.is_empty() {
+            let violation = ProtocolViolation {
+                drone_id: self.id,
+                at: self.elapsed(),
+                from: validation::sender_of(&packet),
+                diagnostic,
+                packet: packet.clone(),
+            };
+            for sink in &mut self.violation_sinks {
+                sink.report(&violation);
+            }
+        }
+        self.send_aux_event(AuxEventKind::Diagnostic { diagnostic, packet });
     }
     // </editor-fold>
 
@@ -189,190 +1563,527 @@ impl RustaceansWitAttitudesDrone {
     // <editor-fold desc="Packets">
     fn handle_packet(&mut self, mut packet: Packet) {
         debug!("Drone: {:?} received packet {:?}", self.id, packet.pack_type);
+        self.packets_received += 1;
+        self.stats_mut().packets_received += 1;
+        if let Some(previous_hop) = validation::sender_of(&packet) {
+            self.track_neighbor(previous_hop, |neighbor| neighbor.packets_received += 1);
+        }
+        self.expire_link_outages();
+        if let Some(energy) = self.options.energy {
+            self.consume_energy(energy.per_packet);
+        }
 
         // first thing first check if it's a FloodRequest
         // if so, hop_index and hops will be ignored
         if !matches!(packet.pack_type, PacketType::FloodRequest(_)){
 
+            // sanity check the routing header, a broken one can't be forwarded nor Nacked back
+            if let Err(diagnostic) = validation::check_routing_header(&packet.routing_header) {
+                self.handle_broken_header(diagnostic, packet);
+                return;
+            }
+            if let Some(hop) = validation::find_duplicate_hop(&packet.routing_header.hops[..=packet.routing_header.hop_index]) {
+                self.send_diagnostic(Diagnostic::DuplicateHop(hop), packet.clone());
+            }
+
             // check for UnexpectedRecipient (will send the package backwards)
             match packet.routing_header.current_hop() {
                 None => {
-                    debug!("*surprised quack*, Drone: {:?} panicked, routing_header.current_hop() is None", self.id);
-                    panic!("*surprised quack*")
+                    let (hop_index, hops_len) = (packet.routing_header.hop_index, packet.routing_header.hops.len());
+                    self.handle_broken_header(Diagnostic::HopIndexOutOfBounds { hop_index, hops_len }, packet);
+                    return;
                 }
                 Some(current_hop) => {
                     if self.id != current_hop{
-                        debug!("Drone: {:?} got UnexpectedRecipient error", self.id);
-                        packet.routing_header.reverse();
-                        let new_packet = Packet::new_nack(
-                            packet.routing_header.clone(),
-                            packet.session_id,
-                            Nack{
-                                fragment_index: packet.get_fragment_index(),
-                                nack_type: NackType::UnexpectedRecipient(self.id)
+                        self.send_diagnostic(Diagnostic::UnexpectedRecipient(current_hop), packet.clone());
+                        match self.options.unexpected_recipient_policy {
+                            UnexpectedRecipientPolicy::Nack => {
+                                debug!("Drone: {:?} got UnexpectedRecipient error", self.id);
+                                self.send_nack(&packet, NackType::UnexpectedRecipient(self.id));
+                                return;
+                            }
+                            UnexpectedRecipientPolicy::Drop => {
+                                debug!("Drone: {:?} dropped {:?} meant for {:?}", self.id, packet.pack_type, current_hop);
+                                return;
+                            }
+                            UnexpectedRecipientPolicy::FixAndForward => {
+                                debug!("Drone: {:?} takes {:?} meant for {:?}", self.id, packet.pack_type, current_hop);
+                                let header = &mut packet.routing_header;
+                                match header.hops.iter().position(|hop| *hop == self.id) {
+                                    Some(position) => header.hop_index = position,
+                                    None => header.hops[header.hop_index] = self.id,
+                                }
                             }
-                        );
-                        let p = self.forward_packet(new_packet);
-                        match p{
-                            Ok(_p) => {self.send_sent_to_sc(_p)}
-                            Err(_p) => {self.send_shortcut_to_sc(_p.0)}
                         }
-                        return;
                     }
                 }
             }
 
 
-            // check for DestinationIsDrone (will send the package backwards)
-            if packet.routing_header.hops.len() == packet.routing_header.hop_index {
-                debug!("Drone: {:?} got DestinationIsDrone error", self.id);
-                packet.routing_header.reverse();
-                let new_packet = Packet::new_nack(
-                    packet.routing_header.clone(),
-                    packet.session_id,
-                    Nack{
-                        fragment_index: packet.get_fragment_index(),
-                        nack_type: NackType::DestinationIsDrone
+            // a response to a flood we initiated
+            if packet.routing_header.hop_index + 1 == packet.routing_header.hops.len() {
+                if let PacketType::FloodResponse(flood_response) = &packet.pack_type {
+                    if let Some(response_send) = self.own_floods.get(&flood_response.flood_id) {
+                        debug!("Drone: {:?} received a response to its flood {:?}", self.id, flood_response.flood_id);
+                        if response_send.send(flood_response.clone()).is_err() {
+                            self.own_floods.remove(&flood_response.flood_id);
+                        }
+                        return;
                     }
-                );
-                let p = self.forward_packet(new_packet);
-                match p{
-                    Ok(_p) => {self.send_sent_to_sc(_p)}
-                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
                 }
+            }
+
+            // check for DestinationIsDrone (will send the package backwards)
+            if packet.routing_header.hop_index + 1 == packet.routing_header.hops.len() {
+                debug!("Drone: {:?} got DestinationIsDrone error", self.id);
+                self.send_nack(&packet, NackType::DestinationIsDrone);
                 return;
             }
 
+            // check for loops in the remaining route (optionally sends the package backwards)
+            if let Some(hop) = validation::find_routing_loop(self.id, &packet.routing_header.hops[packet.routing_header.hop_index + 1..]) {
+                self.send_diagnostic(Diagnostic::RoutingLoop(hop), packet.clone());
+                if self.options.nack_routing_loops {
+                    debug!("Drone: {:?} got a routing loop through {:?}", self.id, hop);
+                    self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+                    return;
+                }
+            }
+
             // check for ErrorInRouting (will send the package backwards)
-            if !self.packet_send.contains_key(&packet.routing_header.hops[packet.routing_header.hop_index + 1]) {
-                debug!("Drone: {:?} got ErrorInRouting error", self.id);
-                packet.routing_header.reverse();
-                let new_packet = Packet::new_nack(
-                    packet.routing_header.clone(),
-                    packet.session_id,
-                    Nack{
-                        fragment_index: packet.get_fragment_index(),
-                        nack_type: NackType::ErrorInRouting(self.id)
-                    }
-                );
-                let p = self.forward_packet(new_packet);
-                match p{
-                    Ok(_p) => {self.send_sent_to_sc(_p)}
-                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
+            let next_hop = packet.routing_header.hops[packet.routing_header.hop_index + 1];
+            if let Some(LinkDown::Packets(packets)) = self.links_down.get_mut(&next_hop) {
+                *packets = packets.saturating_sub(1);
+            }
+            if !self.is_link_up(next_hop) {
+                self.handle_link_down(next_hop, packet);
+                return;
+            }
+        }
+        self.handle_routed_packet(packet);
+    }
+    // fragments wait for the link if there is room, see LinkProfile::queue_while_down
+    fn handle_link_down(&mut self, next_hop: NodeId, packet: Packet) {
+        let capacity = self.link_profile(next_hop).map_or(0, |profile| profile.queue_while_down);
+        let queued = self.link_queues.get(&next_hop).map_or(0, VecDeque::len);
+        if !matches!(packet.pack_type, PacketType::MsgFragment(_)) || !self.links_down.contains_key(&next_hop) || queued >= capacity {
+            self.handle_unreachable(packet, 1);
+            return;
+        }
+        debug!("Drone: {:?} queues {:?} until the link to {:?} is up", self.id, packet.pack_type, next_hop);
+        self.stats_mut().fragments_queued += 1;
+        self.link_queues.entry(next_hop).or_default().push_back(packet);
+    }
+    // forward the fragments of the links back up, Nack the ones of the neighbors that are gone
+    fn release_link_queues(&mut self) {
+        if self.link_queues.is_empty() {
+            return;
+        }
+        self.expire_link_outages();
+        let released: Vec<NodeId> = self.link_queues.keys().copied().filter(|id| !self.links_down.contains_key(id)).collect();
+        for id in released {
+            for packet in self.link_queues.remove(&id).unwrap_or_default() {
+                if self.is_link_up(id) {
+                    self.handle_routed_packet(packet);
+                } else {
+                    self.handle_unreachable(packet, 1);
                 }
+            }
+        }
+    }
+    // see DroneOptions::broken_header_policy
+    fn handle_broken_header(&mut self, diagnostic: Diagnostic, packet: Packet) {
+        debug!("Drone: {:?} can't route {:?}: {:?}", self.id, packet.pack_type, diagnostic);
+        self.stats_mut().broken_headers += 1;
+        self.send_diagnostic(diagnostic, packet.clone());
+        let shortcut = self.options.broken_header_policy == BrokenHeaderPolicy::Shortcut
+            && !packet.routing_header.hops.is_empty()
+            && matches!(packet.pack_type, PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_));
+        if shortcut {
+            self.send_shortcut_with_reason(packet, ShortcutReason::BrokenHeader);
+        }
+    }
+    // Nack of `packet` from the hop its header points to
+    pub(crate) fn send_nack(&mut self, packet: &Packet, nack_type: NackType) {
+        self.send_nack_from_hop(packet, packet.routing_header.hop_index, nack_type);
+    }
+    // a route that can't be followed back from `hops[hop_index]` is a broken header, the Nack is lost
+    fn send_nack_from_hop(&mut self, packet: &Packet, hop_index: usize, nack_type: NackType) {
+        match nack::from_hop(packet, hop_index, nack_type) {
+            Some(nack) => self.forward_nack(nack),
+            None if hop_index == 0 => self.handle_broken_header(Diagnostic::HopIndexNotIncremented, packet.clone()),
+            None => {
+                let hops_len = packet.routing_header.hops.len();
+                self.handle_broken_header(Diagnostic::HopIndexOutOfBounds { hop_index, hops_len }, packet.clone());
+            }
+        }
+    }
+    // the next hop of `packet` can't be reached for the `attempt`-th time
+    fn handle_unreachable(&mut self, packet: Packet, attempt: u32) {
+        match self.behavior.on_unreachable(&packet, attempt) {
+            Unreachable::RetryAfter(after) => {
+                debug!("Drone: {:?} stores {:?} for {:?}", self.id, packet.pack_type, after);
+                self.stats_mut().packets_stored += 1;
+                self.stored_packets.push((self.clock.now() + after, attempt, packet));
+            }
+            Unreachable::Nack if matches!(packet.pack_type, PacketType::MsgFragment(_)) => {
+                debug!("Drone: {:?} got ErrorInRouting error", self.id);
+                self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+            }
+            // Acks, Nacks and FloodResponses can't be lost, the controller delivers them
+            Unreachable::Nack => {
+                let mut packet = packet;
+                packet.routing_header.increase_hop_index();
+                self.send_shortcut_to_sc(packet);
+            }
+        }
+    }
+    fn retry_stored_packets(&mut self) {
+        if self.stored_packets.is_empty() {
+            return;
+        }
+        let now = self.clock.now();
+        let (due, stored): (Vec<_>, Vec<_>) =
+            mem::take(&mut self.stored_packets).into_iter().partition(|(retry_at, _, _)| *retry_at <= now);
+        self.stored_packets = stored;
+        self.expire_link_outages();
+        for (_, attempts, packet) in due {
+            if self.is_link_up(packet.routing_header.hops[packet.routing_header.hop_index + 1]) {
+                self.handle_routed_packet(packet);
+            } else {
+                self.handle_unreachable(packet, attempts + 1);
+            }
+        }
+    }
+    // a packet whose route checked out, or a FloodRequest
+    fn handle_routed_packet(&mut self, packet: Packet) {
+        // filtering rules
+        match self.acl.decide(&packet) {
+            AclAction::Allow => {}
+            AclAction::Nack if matches!(packet.pack_type, PacketType::MsgFragment(_)) => {
+                debug!("Drone: {:?} filtered {:?} with a nack", self.id, packet.pack_type);
+                self.stats_mut().packets_filtered += 1;
+                self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+                return;
+            }
+            AclAction::Drop | AclAction::Nack => {
+                debug!("Drone: {:?} filtered {:?}", self.id, packet.pack_type);
+                self.stats_mut().packets_filtered += 1;
+                self.send_dropped_to_sc(packet);
+                return;
+            }
+        }
+
+        // drop the other packet types too (opt-in testing mode)
+        if self.is_dropped_by_type(&packet) {
+            debug!("Drone: {:?} dropped {:?}", self.id, packet.pack_type);
+            self.stats_mut().control_packets_dropped += 1;
+            self.send_dropped_to_sc(packet);
+            return;
+        }
+
+        // the variant of the drone, see BehaviorStrategy
+        match self.behavior.on_packet(&packet, &mut self.rng) {
+            Verdict::Standard => {}
+            Verdict::Nack(nack_type) if matches!(packet.pack_type, PacketType::MsgFragment(_)) => {
+                debug!("Drone: {:?} nacks {:?} on purpose", self.id, packet.pack_type);
+                self.stats_mut().misbehaviors += 1;
+                self.send_nack(&packet, nack_type);
+                return;
+            }
+            Verdict::Discard | Verdict::Nack(_) => {
+                debug!("Drone: {:?} discards {:?} on purpose", self.id, packet.pack_type);
+                self.stats_mut().misbehaviors += 1;
+                self.send_dropped_to_sc(packet);
                 return;
             }
         }
 
         // match with all Packet Types
         match packet.clone().pack_type {
-            PacketType::Nack(_) => {
+            PacketType::Nack(_nack) => {
+                // the next hop answered, the source takes it from here (hop_reliability)
+                self.unacked_fragments.remove(&(packet.session_id, _nack.fragment_index));
                 let p = self.forward_packet(packet);
-                match p{
-                    Ok(_p) => {self.send_sent_to_sc(_p)}
-                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                }
-                return;
+                self.report_forward(p);
             }
-            PacketType::Ack(_) => {
-                let p = self.forward_packet(packet);
-                match p{
-                    Ok(_p) => {self.send_sent_to_sc(_p)}
-                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
+            PacketType::Ack(_ack) => {
+                self.unacked_fragments.remove(&(packet.session_id, _ack.fragment_index));
+                if self.misbehave(|chaos| chaos.delay_acks) {
+                    let delay = self.options.chaos.map(|chaos| chaos.ack_delay).unwrap_or_default();
+                    debug!("Drone: {:?} delays ack by {:?}", self.id, delay);
+                    self.delayed_packets.insert(self.clock.now() + delay, packet);
+                    return;
                 }
-                return;
+                let p = self.forward_packet(packet);
+                self.report_forward(p);
             }
-            PacketType::MsgFragment(_) => {
+            PacketType::MsgFragment(_fragment) => {
+                self.track_session(packet.session_id, |session| session.fragments_seen += 1);
+
+                // check the fragment payload (will send the package backwards)
+                if self.options.validate_fragments {
+                    if let Err(diagnostic) = validation::check_fragment(&_fragment) {
+                        self.send_diagnostic(diagnostic, packet.clone());
+                        self.send_nack(&packet, NackType::Dropped);
+                        return;
+                    }
+                }
+                if self.options.verify_checksums {
+                    if let Err(diagnostic) = validation::check_checksum(&_fragment) {
+                        debug!("Drone: {:?} got a corrupted fragment {:?}", self.id, _fragment.fragment_index);
+                        self.stats_mut().checksum_failures += 1;
+                        self.send_diagnostic(diagnostic, packet.clone());
+                        self.send_nack(&packet, NackType::Dropped);
+                        return;
+                    }
+                }
+
                 // check if it's Dropped
-                let mut rng = rand::thread_rng();
-                if rng.gen_range(0.0..=1.0) < self.pdr {
+                let dropped = if self.options.droppable.fragments {
+                    let pdr = self.effective_pdr(&packet);
+                    self.drop_decision(pdr, packet.session_id, _fragment.fragment_index)
+                } else {
+                    None
+                };
+                if let Some(audit) = dropped {
+                    self.audit_drop(audit);
                     // forward Dropped
-                    let return_route = packet.routing_header
-                        .sub_route(0..=packet.routing_header.hop_index)
-                        .unwrap_or_else(|| {
-                            debug!("*surprised quack*, Drone: {:?} failed to get sub-route", self.id);
-                            panic!("Failed to get sub-route for NACK")
-                        });
-                    let reversed_route = return_route.get_reversed();
-                    let new_packet = Packet::new_nack(
-                        reversed_route,
-                        packet.session_id,
-                        Nack{
-                            fragment_index: packet.get_fragment_index(),
-                            nack_type: NackType::Dropped
-                        }
-                    );
-                    let p = self.forward_packet(new_packet);
-                    match p{
-                        Ok(_p) => {self.send_sent_to_sc(_p)}
-                        Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                    }
-                    return;
+                    self.stats_mut().fragments_dropped += 1;
+                    self.track_session(packet.session_id, |session| session.fragments_dropped += 1);
+                    self.send_dropped_to_sc(packet.clone());
+                    self.send_nack(&packet, NackType::Dropped);
                 } else {
-                    // forward fragment
-                    let p = self.forward_packet(packet);
-                    match p{
-                        Ok(_p) => {self.send_sent_to_sc(_p)}
-                        Err(_p) => {
-                            debug!("*surprised quack*, Drone: {:?} panicked", self.id);
-                            panic!("*surprised quack*")
+                    // hold it back for a few fragments (fault mode)
+                    if let Some(reorder) = self.options.reorder {
+                        if reorder.max_displacement > 0 && self.roll(reorder.probability) {
+                            let displacement = self.rng.gen_range(1..=reorder.max_displacement);
+                            debug!("Drone: {:?} holds fragment back for {:?} fragments", self.id, displacement);
+                            self.held_fragments.push((displacement, packet));
+                            return;
                         }
                     }
-                    return;
+
+                    self.forward_fragment(packet);
+                    self.release_held_fragments(false);
                 }
             }
             PacketType::FloodRequest(mut _flood_request) => {
+                // our own flood came back
+                if _flood_request.initiator_id == self.id {
+                    return;
+                }
                 // is it the first time the node receives this flood request?
-                let current_flood: Option<&NodeId> = self.flood_initiators.get(&_flood_request.flood_id);
-                let is_new_flood = match current_flood {
-                    None => true,
-                    Some(initiator) => initiator != &_flood_request.initiator_id
-                };
-                if is_new_flood{
-                    // yes: send a flood request to all neighbors and add it to the flood_initiators hashmap
-                    self.flood_initiators.insert(_flood_request.flood_id, _flood_request.initiator_id);
-                    let p = self.forward_flood_request(packet, _flood_request);
-                    match p{
-                        Ok(_p) => {self.send_sent_to_sc(_p)}
-                        Err(_p) => {
-                            debug!("*surprised quack*, Drone: {:?} panicked", self.id);
-                            panic!("*surprised quack*")
+                if self.remember_flood(_flood_request.flood_id, _flood_request.initiator_id) {
+                    // yes: send a flood request to all neighbors
+                    self.log_flood(&_flood_request);
+                    // PacketSent is sent for each neighbor
+                    self.forward_flood_request(packet, _flood_request);
+                } else {
+                    self.track_flood(&_flood_request, |flood| flood.duplicates += 1);
+                    // no: check the duplicate flood policy
+                    match self.options.duplicate_flood_policy {
+                        DuplicateFloodPolicy::Respond => {}
+                        DuplicateFloodPolicy::Drop => {
+                            debug!("Drone: {:?} dropped duplicate flood {:?}", self.id, _flood_request.flood_id);
+                            return;
+                        }
+                        DuplicateFloodPolicy::RespondOncePerInitiator => {
+                            if !self.answered_floods.insert((_flood_request.flood_id, _flood_request.initiator_id)) {
+                                debug!("Drone: {:?} already answered flood {:?}", self.id, _flood_request.flood_id);
+                                return;
+                            }
                         }
                     }
-                    return;
-                } else {
-                    // no: send a flood response
+
+                    // send a flood response
                     // add node to the path trace
                     _flood_request.increment(self.id, NodeType::Drone);
                     // generate a flood response
                     let flood_response_packet = _flood_request.generate_response(packet.session_id);
                     debug!("Drone: {:?} is generating a flood_request: {:?}", self.id, flood_response_packet);
+                    self.track_flood(&_flood_request, |flood| flood.responses += 1);
                     let p = self.forward_packet(flood_response_packet);
-                    match p {
-                        Ok(_p) => {self.send_sent_to_sc(_p)}
-                        Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                    }
-                    return;
+                    self.report_forward(p);
                 }
             },
-            PacketType::FloodResponse(_) => {
+            PacketType::FloodResponse(_flood_response) => {
+                self.track_flood_response(&_flood_response);
                 let p = self.forward_packet(packet);
-                match p{
-                    Ok(_p) => {self.send_sent_to_sc(_p)}
-                    Err(_p) => {self.send_shortcut_to_sc(_p.0)}
-                }
-                return;
+                self.report_forward(p);
             },
         }
     }
-    fn forward_flood_request(&mut self, mut packet: Packet, mut flood_request: FloodRequest) ->Result<(Packet), SendError<Packet>>{
+    // handle_packet, turning a panic into an event if DroneOptions::catch_panics is set
+    fn handle_packet_isolated(&mut self, packet: Packet) {
+        if !self.options.catch_panics {
+            self.handle_packet(packet);
+            return;
+        }
+        let copy = packet.clone();
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| self.handle_packet(packet))) {
+            let message = event::panic_message(payload.as_ref());
+            debug!("Drone: {:?} panicked on {:?}: {}", self.id, copy.pack_type, message);
+            self.send_aux_event(AuxEventKind::Panicked { message, packet: copy });
+        }
+    }
+    fn handle_packet_while_paused(&mut self, packet: Packet) {
+        debug!("Drone: {:?} received packet {:?} while paused", self.id, packet.pack_type);
+        match packet.pack_type {
+            // Send Nack(ErrorInRouting) for fragments
+            PacketType::MsgFragment(_) => {
+                if let Err(diagnostic) = validation::check_routing_header(&packet.routing_header) {
+                    self.handle_broken_header(diagnostic, packet);
+                    return;
+                }
+                self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+            }
+            // Keep the other packets until the drone is resumed
+            _ if self.paused_backlog.len() < self.options.pause_backlog_capacity => self.paused_backlog.push_back(packet),
+            _ => self.handle_backlog_overflow(packet),
+        }
+    }
+    // a packet that doesn't fit in the paused backlog, OverflowPolicy::Block never gets here
+    fn handle_backlog_overflow(&mut self, mut packet: Packet) {
+        debug!("Drone: {:?} backlog is full, {:?} for {:?}", self.id, self.options.overflow_policy, packet.pack_type);
+        self.stats_mut().packets_overflowed += 1;
+        match (&packet.pack_type, self.options.overflow_policy) {
+            (PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_), OverflowPolicy::Shortcut) => {
+                // a shortcut points to the hop it couldn't be sent to
+                packet.routing_header.increase_hop_index();
+                self.send_shortcut_with_reason(packet, ShortcutReason::BacklogFull);
+            }
+            _ => self.send_dropped_to_sc(packet),
+        }
+    }
+    fn forward_fragment(&mut self, mut packet: Packet) {
+        // flip a payload bit, never the routing data (fault mode)
+        if let PacketType::MsgFragment(fragment) = &mut packet.pack_type {
+            let length = (fragment.length as usize).min(FRAGMENT_DSIZE);
+            if length > 0 && self.roll(self.options.corrupt_payload_probability) {
+                let index = self.rng.gen_range(0..length);
+                fragment.data[index] ^= 1u8 << self.rng.gen_range(0..8u8);
+                debug!("Drone: {:?} corrupted byte {:?} of fragment {:?}", self.id, index, fragment.fragment_index);
+                self.stats_mut().fragments_corrupted += 1;
+            }
+        }
+
+        // keep it until its Ack comes back (hop_reliability)
+        let session_id = packet.session_id;
+        let unacked = match (self.options.hop_reliability, &packet.pack_type) {
+            (Some(hop_reliability), PacketType::MsgFragment(fragment)) => {
+                let due_by = self.clock.now() + hop_reliability.ack_timeout;
+                self.unacked_fragments.insert((session_id, fragment.fragment_index), (due_by, 0, packet.clone()));
+                Some((session_id, fragment.fragment_index))
+            }
+            _ => None,
+        };
+
+        // forward fragment
+        let p = self.forward_packet(packet);
+        match p{
+            Ok(_p) => {
+                self.track_session(session_id, |session| session.fragments_forwarded += 1);
+                self.send_sent_to_sc(_p.clone());
+
+                // send it twice (fault mode)
+                if self.roll(self.options.duplicate_fragment_probability) {
+                    if let Some(next_hop) = _p.routing_header.current_hop() {
+                        if let Ok(duplicate) = self.try_send_packet(_p, next_hop) {
+                            debug!("Drone: {:?} duplicated fragment to {:?}", self.id, next_hop);
+                            self.stats_mut().fragments_duplicated += 1;
+                            self.send_sent_to_sc(duplicate);
+                        }
+                    }
+                }
+            }
+            // the next hop went away, the Nack goes back from this drone
+            Err(ForwardError::NoLink(_p)) => {
+                if let Some(key) = unacked {
+                    self.unacked_fragments.remove(&key);
+                }
+                let next_hop = _p.routing_header.hops[_p.routing_header.hop_index];
+                self.send_nack_from_hop(&_p, _p.routing_header.hop_index - 1, NackType::ErrorInRouting(next_hop));
+            }
+            Err(ForwardError::Delayed) => {
+                self.track_session(session_id, |session| session.fragments_forwarded += 1);
+            }
+            // already answered if the overflow policy Nacks it, a broken header is already reported
+            Err(ForwardError::Overflow | ForwardError::BrokenHeader) => {
+                if let Some(key) = unacked {
+                    self.unacked_fragments.remove(&key);
+                }
+            }
+        }
+    }
+    // forwarded fragments whose Ack is late go to the next hop again, see DroneOptions::hop_reliability
+    fn resend_unacked_fragments(&mut self) {
+        let Some(hop_reliability) = self.options.hop_reliability else {
+            self.unacked_fragments.clear();
+            return;
+        };
+        let now = self.clock.now();
+        let due: Vec<(u64, u64)> =
+            self.unacked_fragments.iter().filter(|(_, (due_by, _, _))| *due_by <= now).map(|(key, _)| *key).collect();
+        for key in due {
+            let Some((_, resends, packet)) = self.unacked_fragments.remove(&key) else {
+                continue;
+            };
+            if resends >= hop_reliability.max_retransmissions {
+                debug!("Drone: {:?} gives up on fragment {:?} of session {:?}", self.id, key.1, key.0);
+                self.stats_mut().hop_acks_missed += 1;
+                continue;
+            }
+            // the link may have gone away since, the Nack of the first attempt is enough
+            if !self.is_link_up(packet.routing_header.hops[packet.routing_header.hop_index + 1]) {
+                continue;
+            }
+            debug!("Drone: {:?} resends fragment {:?} of session {:?}", self.id, key.1, key.0);
+            self.stats_mut().hop_retransmissions += 1;
+            self.unacked_fragments.insert(key, (now + hop_reliability.ack_timeout, resends + 1, packet.clone()));
+            match self.forward_packet(packet) {
+                Ok(p) => self.send_sent_to_sc(p),
+                Err(ForwardError::NoLink(_) | ForwardError::BrokenHeader) => {
+                    self.unacked_fragments.remove(&key);
+                }
+                Err(ForwardError::Overflow | ForwardError::Delayed) => {}
+            }
+        }
+    }
+    fn release_held_fragments(&mut self, all: bool) {
+        if self.held_fragments.is_empty() {
+            return;
+        }
+        for (remaining, _) in self.held_fragments.iter_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+        let (due, held) =
+            mem::take(&mut self.held_fragments).into_iter().partition::<Vec<_>, _>(|(remaining, _)| all || *remaining == 0);
+        self.held_fragments = held;
+        for (_, packet) in due {
+            // the link may have gone away while the fragment was held
+            if self.is_link_up(packet.routing_header.hops[packet.routing_header.hop_index + 1]) {
+                self.forward_fragment(packet);
+            } else {
+                self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+            }
+        }
+    }
+    fn forward_flood_request(&mut self, mut packet: Packet, mut flood_request: FloodRequest) {
         packet.routing_header.increase_hop_index();
 
         // add node to the hops
         packet.routing_header.append_hop(self.id);
 
+        // the node it came from is the last of the path trace, the routing header of a FloodRequest is meaningless
+        let previous = flood_request.path_trace.last().map(|(node_id, _)| *node_id);
+
         // add node to the path trace
         flood_request.increment(self.id, NodeType::Drone);
+        if self.misbehave(|chaos| chaos.corrupt_path_traces) {
+            let index = self.rng.gen_range(0..flood_request.path_trace.len());
+            flood_request.path_trace[index].0 = self.rng.gen();
+        }
 
         // generate new packet
         let p = Packet::new_flood_request(
@@ -381,50 +2092,476 @@ impl RustaceansWitAttitudesDrone {
             flood_request,
         );
 
-        // send packet to neighbors (except for the previous drone)
-        match packet.routing_header.previous_hop() {
-            Some(prev) => {
-                for node_id in self.packet_send.keys().cloned() {
-                    if node_id != prev {
-                        let _ = self.try_send_packet(p.clone(), node_id);
-                    }
-                }
+        // send packet to neighbors (except for the previous node)
+        let mut sent = 0;
+        for node_id in self.packet_send.keys().cloned().collect::<Vec<NodeId>>() {
+            if Some(node_id) != previous {
+                sent += u64::from(self.try_send_flood_request(p.clone(), node_id));
             }
-            None => {
-                // Caso iniziale del flooding: inoltra a tutti i vicini
-                for node_id in self.packet_send.keys().cloned() {
-                    let _ = self.try_send_packet(p.clone(), node_id);
-                }
+        }
+        if let PacketType::FloodRequest(flood_request) = &p.pack_type {
+            self.track_flood(flood_request, |flood| flood.forwarded_to = sent);
+        }
+        self.stats_mut().flood_fan_out.record(sent);
+        if let Some(energy) = self.options.energy {
+            self.consume_energy(sent * energy.per_forward);
+        }
+    }
+    // FloodRequests that don't fit in a full channel are lost, whatever the overflow policy
+    // one event per neighbor, so that the controller counts what was actually sent
+    fn try_send_flood_request(&mut self, p: Packet, node_id: NodeId) -> bool {
+        match self.try_send_packet(p, node_id) {
+            Ok(p) => {
+                self.send_sent_to_sc(p);
+                true
+            }
+            Err(SendFailure::Full(p)) => {
+                self.stats_mut().packets_overflowed += 1;
+                self.send_aux_event(AuxEventKind::FloodSendFailed { to: node_id, packet: *p });
+                false
+            }
+            Err(SendFailure::Disconnected(p)) => {
+                self.neighbor_disconnected(node_id);
+                self.send_aux_event(AuxEventKind::FloodSendFailed { to: node_id, packet: *p });
+                false
             }
         }
-        Ok(p)
     }
-    fn forward_packet(&mut self, mut packet: Packet) ->Result<(Packet), SendError<Packet>>{
+    // the packet that wasn't sent is given back, like crossbeam's SendError
+    fn forward_packet(&mut self, mut packet: Packet) -> Result<Packet, ForwardError> {
+        let previous_hop = validation::sender_of(&packet);
         packet.routing_header.increase_hop_index();
 
+        // swap two hops after the next one (chaos)
+        let first = packet.routing_header.hop_index + 1;
+        if first + 1 < packet.routing_header.hops.len() && self.misbehave(|chaos| chaos.reorder_hops) {
+            let a = self.rng.gen_range(first..packet.routing_header.hops.len());
+            let b = self.rng.gen_range(first..packet.routing_header.hops.len());
+            packet.routing_header.hops.swap(a, b);
+        }
+
         // Try to send packet
         match packet.routing_header.current_hop() {
             None => {
-                debug!("*surprised quack*, Drone: {:?} pack: {:?}", self.id, packet);
-                panic!("*surprised quack*, Drone: {:?} pack: {:?}", self.id, packet)
+                let (hop_index, hops_len) = (packet.routing_header.hop_index, packet.routing_header.hops.len());
+                self.handle_broken_header(Diagnostic::HopIndexOutOfBounds { hop_index, hops_len }, packet);
+                Err(ForwardError::BrokenHeader)
+            }
+            Some(_next_node_id) => {
+                // a missing link is found out right away
+                let arrival = if self.is_link_up(_next_node_id) { self.link_arrival(previous_hop, _next_node_id) } else { None };
+                if let Some(arrival) = arrival {
+                    debug!("Drone: {:?} holds {:?} for {:?} until {:?}", self.id, packet.pack_type, _next_node_id, arrival);
+                    self.in_flight.insert(arrival, (_next_node_id, packet));
+                    if let Some(energy) = self.options.energy {
+                        self.consume_energy(energy.per_forward);
+                    }
+                    return Err(ForwardError::Delayed);
+                }
+                match self.try_send_packet(packet, _next_node_id) {
+                    Ok(p) => {
+                        if let Some(energy) = self.options.energy {
+                            self.consume_energy(energy.per_forward);
+                        }
+                        Ok(p)
+                    }
+                    Err(SendFailure::Full(p)) => {
+                        self.handle_overflow(*p);
+                        Err(ForwardError::Overflow)
+                    }
+                    Err(SendFailure::Disconnected(p)) => {
+                        self.neighbor_disconnected(_next_node_id);
+                        Err(ForwardError::NoLink(p))
+                    }
+                }
+            }
+        }
+    }
+    pub(crate) fn forward_nack(&mut self, mut packet: Packet) {
+        if self.is_duplicate_nack(&packet) {
+            debug!("Drone: {:?} suppressed duplicate nack {:?}", self.id, packet.pack_type);
+            self.stats_mut().nacks_suppressed += 1;
+            return;
+        }
+        self.stats_mut().nacks_sent += 1;
+        if let PacketType::Nack(nack) = &mut packet.pack_type {
+            if self.misbehave(|chaos| chaos.misreport_nacks) {
+                let mut wrong_types = vec![
+                    NackType::ErrorInRouting(self.id),
+                    NackType::DestinationIsDrone,
+                    NackType::Dropped,
+                    NackType::UnexpectedRecipient(self.id),
+                ];
+                wrong_types.retain(|nack_type| mem::discriminant(nack_type) != mem::discriminant(&nack.nack_type));
+                nack.nack_type = wrong_types.swap_remove(self.rng.gen_range(0..wrong_types.len()));
+            }
+            self.stats_mut().nacks_by_type.record(&nack.nack_type);
+        }
+        let p = self.forward_packet(packet);
+        self.report_forward(p);
+    }
+    fn is_duplicate_nack(&mut self, packet: &Packet) -> bool {
+        let (Some(window), PacketType::Nack(nack)) = (self.options.nack_dedup_window, &packet.pack_type) else {
+            return false;
+        };
+        let now = self.clock.now();
+        self.touch_session(packet.session_id);
+        let Some(session) = self.sessions.get_mut(packet.session_id) else {
+            return false;
+        };
+        session.recent_nacks.retain(|_, sent_at| now.duration_since(*sent_at) < window);
+        let key = (nack.fragment_index, mem::discriminant(&nack.nack_type));
+        if session.recent_nacks.contains_key(&key) {
+            return true;
+        }
+        session.recent_nacks.insert(key, now);
+        false
+    }
+    fn release_delayed_packets(&mut self) {
+        if self.delayed_packets.is_empty() {
+            return;
+        }
+        let mut due = self.delayed_packets.expire(self.clock.now());
+        due.sort_by_key(|(release_at, _)| *release_at);
+        for (_, packet) in due {
+            let p = self.forward_packet(packet);
+            self.report_forward(p);
+        }
+    }
+    // when a packet from `from` to `to` gets through their links, None if right away
+    fn link_arrival(&mut self, from: Option<NodeId>, to: NodeId) -> Option<Instant> {
+        if self.link_profiles.is_empty() && self.options.default_link.is_none() {
+            return None;
+        }
+        let now = self.clock.now();
+        let mut arrival = now;
+        let ingress = from.and_then(|from| self.link_profile(from).map(|profile| (from, false, profile.ingress)));
+        let egress = self.link_profile(to).map(|profile| (to, true, profile.egress));
+        for (node_id, is_egress, direction) in ingress.into_iter().chain(egress) {
+            arrival = self.through_link(arrival, (node_id, is_egress), direction);
+        }
+        (arrival > now).then_some(arrival)
+    }
+    // queue behind the packets on the link, then add the delay
+    fn through_link(&mut self, at: Instant, link: (NodeId, bool), direction: LinkDirection) -> Instant {
+        let transmission_time = direction.transmission_time();
+        let mut sent_at = at;
+        if !transmission_time.is_zero() {
+            let busy_until = self.links_busy_until.get(&link).copied().unwrap_or(at);
+            sent_at = busy_until.max(at) + transmission_time;
+            self.links_busy_until.insert(link, sent_at);
+        }
+        let jitter = Duration::from_nanos(self.rng.gen_range(0..=direction.jitter.as_nanos() as u64));
+        sent_at + direction.delay + jitter
+    }
+    fn release_in_flight(&mut self) {
+        if self.in_flight.is_empty() {
+            return;
+        }
+        let mut due = self.in_flight.expire(self.clock.now());
+        // in arrival order, jitter may have changed it
+        due.sort_by_key(|(arrival, _)| *arrival);
+        for (_, (next_node_id, packet)) in due {
+            match self.try_send_packet(packet, next_node_id) {
+                Ok(p) => self.send_sent_to_sc(p),
+                Err(SendFailure::Full(p)) => self.handle_overflow(*p),
+                Err(SendFailure::Disconnected(p)) => {
+                    self.neighbor_disconnected(next_node_id);
+                    if matches!(p.pack_type, PacketType::MsgFragment(_)) {
+                        // the link went away while the fragment was on it
+                        self.send_nack_from_hop(&p, p.routing_header.hop_index - 1, NackType::ErrorInRouting(next_node_id));
+                    } else {
+                        self.send_shortcut_to_sc(*p);
+                    }
+                }
+            }
+        }
+    }
+    fn handle_overflow(&mut self, packet: Packet) {
+        debug!("Drone: {:?} next hop is full, {:?} for {:?}", self.id, self.options.overflow_policy, packet.pack_type);
+        self.stats_mut().packets_overflowed += 1;
+        match (&packet.pack_type, self.options.overflow_policy) {
+            (PacketType::MsgFragment(_), _) => {
+                // the packet already points to the next hop
+                self.send_nack_from_hop(&packet, packet.routing_header.hop_index - 1, NackType::Dropped);
+            }
+            (PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_), OverflowPolicy::Shortcut) => {
+                self.send_shortcut_with_reason(packet, ShortcutReason::ChannelFull);
+            }
+            _ => self.send_dropped_to_sc(packet),
+        }
+    }
+    fn is_dropped_by_type(&mut self, packet: &Packet) -> bool {
+        let droppable = match &packet.pack_type {
+            // fragments have their own handling (Nack Dropped)
+            PacketType::MsgFragment(_) => false,
+            PacketType::Ack(_) => self.options.droppable.acks,
+            PacketType::Nack(_) => self.options.droppable.nacks,
+            PacketType::FloodRequest(_) => self.options.droppable.flood_requests,
+            PacketType::FloodResponse(_) => self.options.droppable.flood_responses,
+        };
+        droppable && self.rng.gen_range(0.0..=1.0) < self.effective_pdr(packet)
+    }
+    // the drone's pdr combined with the ingress pdr of the previous hop and the egress pdr of the next
+    // one, FloodRequests have no next hop
+    fn effective_pdr(&self, packet: &Packet) -> f32 {
+        if self.link_profiles.is_empty() && self.options.default_link.is_none() {
+            return self.pdr;
+        }
+        let ingress = validation::sender_of(packet).and_then(|node_id| self.link_profile(node_id)).map_or(0.0, |link| link.ingress.pdr);
+        let egress = match packet.pack_type {
+            PacketType::FloodRequest(_) => None,
+            _ => packet.routing_header.hops.get(packet.routing_header.hop_index + 1),
+        }
+        .and_then(|node_id| self.link_profile(*node_id))
+        .map_or(0.0, |link| link.egress.pdr);
+        let delivered = [self.pdr, ingress, egress].iter().map(|pdr| 1.0 - pdr.clamp(0.0, 1.0)).product::<f32>();
+        1.0 - delivered
+    }
+    // Some if the fragment is dropped
+    fn drop_decision(&mut self, pdr: f32, session_id: u64, fragment_index: u64) -> Option<DropAudit> {
+        let position = self.fragments_sampled;
+        self.fragments_sampled += 1;
+        let mut sample = None;
+        let dropped = match &self.options.drop_mode {
+            DropMode::Random => {
+                let value = self.rng.gen_range(0.0..=1.0);
+                sample = Some(value);
+                value < pdr
+            }
+            // the k-th fragment of the window is dropped when ⌊k·pdr⌋ goes up, ⌊window·pdr⌋ times in all
+            // in millionths, an f32 pdr of 0.7 is slightly less than 0.7
+            DropMode::ExactRate { window } => {
+                let k = position % u64::from((*window).max(1));
+                let pdr = (f64::from(pdr.clamp(0.0, 1.0)) * 1e6).round() as u64;
+                (k + 1) * pdr / 1_000_000 > k * pdr / 1_000_000
             }
-            Some(_next_node_id) => {self.try_send_packet(packet, _next_node_id)}
+            DropMode::EveryNth(n) => (position + 1).is_multiple_of(*n),
+            DropMode::FragmentIndices(indices) => {
+                indices.contains(&fragment_index) && self.scripted_drops.insert((session_id, fragment_index))
+            }
+        };
+        dropped.then(|| DropAudit { at: self.elapsed(), session_id, fragment_index, pdr, sample, position })
+    }
+    fn audit_drop(&mut self, audit: DropAudit) {
+        debug!("Drone: {:?} {}", self.id, audit);
+        if let Some(drop_log) = self.drop_log.as_mut() {
+            // the log never changes what the drone does, failures are ignored
+            let _ = writeln!(drop_log, "{} {}", self.id, audit);
+        }
+        if self.options.audit_drops {
+            self.send_aux_event(AuxEventKind::FragmentDropped(audit));
+        }
+    }
+    fn roll(&mut self, probability: f64) -> bool {
+        probability > 0.0 && self.rng.gen_bool(probability.min(1.0))
+    }
+    fn misbehave(&mut self, enabled: fn(&ChaosOptions) -> bool) -> bool {
+        let Some(chaos) = self.options.chaos else {
+            return false;
+        };
+        if !enabled(&chaos) || !self.roll(chaos.probability) {
+            return false;
         }
+        self.stats_mut().misbehaviors += 1;
+        true
     }
-    fn try_send_packet(&self, p: Packet, next_node_id: NodeId) -> Result<Packet, SendError<Packet>> {
-        if let Some(sender) = self.packet_send.get(&next_node_id) {
-            // send packet
-            match sender.send(p.clone()) {
-                Ok(_) => {
+    fn is_link_up(&self, node_id: NodeId) -> bool {
+        self.packet_send.contains_key(&node_id) && !self.links_down.contains_key(&node_id)
+    }
+    // the packet that wasn't sent is given back boxed, like crossbeam's SendError but without a large Err
+    fn try_send_packet(&self, p: Packet, next_node_id: NodeId) -> Result<Packet, SendFailure<Box<Packet>>> {
+        #[cfg(feature = "test-internals")]
+        if let Some(failure) = self.injected_send_failure(&p, next_node_id) {
+            debug!("Drone: {:?} fails to send {:?} to {:?} on purpose", self.id, p.pack_type, next_node_id);
+            self.track_neighbor(next_node_id, |neighbor| neighbor.send_failures += 1);
+            return Err(match failure {
+                InjectedFailure::Full => SendFailure::Full(Box::new(p)),
+                InjectedFailure::Disconnected => SendFailure::Disconnected(Box::new(p)),
+            });
+        }
+        if let Some(sender) = self.packet_send.get(&next_node_id).filter(|_| !self.links_down.contains_key(&next_node_id)) {
+            // send packet, without blocking on a full channel unless the overflow policy says so
+            let sent = match self.options.overflow_policy {
+                OverflowPolicy::Block => sender.send(p.clone()).map_err(SendFailure::Disconnected),
+                _ => sender.send_timeout(p.clone(), self.options.overflow_timeout),
+            };
+            match sent {
+                Ok(()) => {
                     debug!("Drone: {:?} sent packet {:?} to {:?}", self.id, p.pack_type, next_node_id);
+                    for tap in &self.taps {
+                        tap.mirror(self.elapsed(), self.id, next_node_id, &p);
+                    }
+                    let is_nack = matches!(p.pack_type, PacketType::Nack(_));
+                    self.track_neighbor(next_node_id, |neighbor| {
+                        neighbor.packets_forwarded += 1;
+                        neighbor.nacks_sent += u64::from(is_nack);
+                    });
                     Ok(p)
                 },
-                Err(e) => Err(e),
+                Err(e) => {
+                    self.track_neighbor(next_node_id, |neighbor| neighbor.send_failures += 1);
+                    Err(match e {
+                        SendFailure::Full(p) => SendFailure::Full(Box::new(p)),
+                        SendFailure::Disconnected(p) => SendFailure::Disconnected(Box::new(p)),
+                    })
+                }
             }
         } else {
             debug!("ERROR, Sender not found, Drone: {:?} cannot send Packet to: {:?}\nPacket: {:?}", self.id, next_node_id, p);
-            Err(SendError(p))
+            self.track_neighbor(next_node_id, |neighbor| neighbor.send_failures += 1);
+            Err(SendFailure::Disconnected(Box::new(p)))
+        }
+    }
+    #[cfg(feature = "test-internals")]
+    pub(crate) fn inject_send_failure(&mut self, neighbor: Option<NodeId>, kind: Option<PacketKind>, failure: InjectedFailure) {
+        self.failing_sends.push((neighbor, kind, failure));
+    }
+    #[cfg(feature = "test-internals")]
+    pub(crate) fn clear_send_failures(&mut self) {
+        self.failing_sends.clear();
+    }
+    #[cfg(feature = "test-internals")]
+    pub(crate) fn set_options(&mut self, options: DroneOptions) {
+        self.options = options;
+    }
+    // the first injected failure that matches
+    #[cfg(feature = "test-internals")]
+    fn injected_send_failure(&self, packet: &Packet, next_node_id: NodeId) -> Option<InjectedFailure> {
+        let kind = PacketKind::of(&packet.pack_type);
+        self.failing_sends.iter()
+            .find(|(neighbor, kinds, _)| neighbor.is_none_or(|id| id == next_node_id) && kinds.is_none_or(|k| k == kind))
+            .map(|(_, _, failure)| *failure)
+    }
+    // </editor-fold>
+
+
+    // <editor-fold desc="Record and replay">
+    fn record_command(&mut self, _command: &DroneCommand) {
+        #[cfg(feature = "record")]
+        self.record(RecordedInput::Command(RecordedCommand::from(_command)));
+    }
+    fn record_packet(&mut self, _packet: &Packet) {
+        #[cfg(feature = "record")]
+        self.record(RecordedInput::Packet(_packet.clone()));
+    }
+    #[cfg(feature = "record")]
+    fn record(&mut self, input: RecordedInput) {
+        let at = self.elapsed();
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.write(&RecordedEntry { at, input });
+        }
+    }
+    #[cfg(feature = "record")]
+    fn start_recording(&mut self) {
+        let header = RecordingHeader { drone_id: self.id, pdr: self.pdr, neighbors: self.neighbor_ids(), seed: self.seed };
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.write(&header);
+        }
+    }
+    #[cfg(feature = "record")]
+    pub(crate) fn replay(recording: &Recording, options: DroneOptions) -> ReplayOutput {
+        let header = &recording.header;
+        let (controller_send, events) = unbounded();
+        let (_, controller_recv) = unbounded();
+        let (_, packet_recv) = unbounded();
+        // the neighbors' receivers are kept so that sending to them succeeds
+        let mut neighbors = Vec::new();
+        let mut packet_send = HashMap::new();
+        for &node_id in &header.neighbors {
+            let (send, recv) = unbounded();
+            packet_send.insert(node_id, send);
+            neighbors.push(recv);
+        }
+        // recorded times are replayed on a clock of their own
+        let clock = ManualClock::new();
+        let mut drone = Self::new(header.drone_id, controller_send, controller_recv, packet_recv, packet_send, header.pdr)
+            .with_options(DroneOptions { seed: Some(header.seed), ..options })
+            .with_clock(clock.clone());
+        let (sent_send, sent) = unbounded();
+        drone.taps.push(Tap::Routed(sent_send));
+
+        // same dispatch as run(), without waiting between inputs
+        let mut steps = Vec::new();
+        for (index, entry) in recording.entries.iter().enumerate() {
+            clock.advance_to(entry.at);
+            let crashing = drone.is_crashing();
+            match entry.input.clone() {
+                RecordedInput::Command(command) => {
+                    let command = match command {
+                        RecordedCommand::AddSender(node_id) => {
+                            let (send, recv) = unbounded();
+                            neighbors.push(recv);
+                            DroneCommand::AddSender(node_id, send)
+                        }
+                        RecordedCommand::RemoveSender(node_id) => DroneCommand::RemoveSender(node_id),
+                        RecordedCommand::SetPacketDropRate(pdr) => DroneCommand::SetPacketDropRate(pdr),
+                        RecordedCommand::Crash => DroneCommand::Crash,
+                    };
+                    drone.dispatch_command(command);
+                }
+                RecordedInput::Packet(packet) => drone.dispatch_packet(packet),
+            }
+            if !crashing {
+                drone.housekeeping(index + 1 < recording.entries.len());
+            }
+            steps.push(ReplayStep {
+                input: entry.input.clone(),
+                events: events.try_iter().collect(),
+                sent: sent.try_iter().collect(),
+            });
         }
+        ReplayOutput { steps }
     }
     // </editor-fold>
-}
\ No newline at end of file
+    // running, paused, crashing, crashed or shut down
+    fn state_name(&self) -> &'static str {
+        match self.state {
+            DroneState::Running if self.shutdown => "shut down",
+            DroneState::Running if self.paused => "paused",
+            DroneState::Running => "running",
+            DroneState::Crashing => "crashing",
+            DroneState::Crashed => "crashed",
+        }
+    }
+}
+
+// The counters are left out while the stats are locked, e.g. when formatting the drone in a panic
+// raised while updating them.
+impl fmt::Display for RustaceansWitAttitudesDrone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "drone {} ({}, pdr {}, {} neighbors)", self.id, self.state_name(), self.pdr, self.packet_send.len())?;
+        if let Ok(stats) = self.stats.try_lock() {
+            write!(
+                f,
+                ": {} received, {} fragments dropped, {} Nacks sent",
+                stats.packets_received, stats.fragments_dropped, stats.nacks_sent
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RustaceansWitAttitudesDrone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("RustaceansWitAttitudesDrone");
+        debug
+            .field("id", &self.id)
+            .field("state", &self.state_name())
+            .field("pdr", &self.pdr)
+            .field("neighbors", &self.neighbor_ids());
+        if let Ok(stats) = self.stats.try_lock() {
+            debug
+                .field("packets_received", &stats.packets_received)
+                .field("fragments_dropped", &stats.fragments_dropped)
+                .field("nacks_sent", &stats.nacks_sent)
+                .field("sessions_tracked", &stats.sessions_tracked);
+        }
+        debug.finish_non_exhaustive()
+    }
+}
+
+// `Instant::now() + after` in real time, for the timers of the threads and tasks running drones
+fn real_time(after: Duration) -> std::time::Instant {
+    std::time::Instant::now() + after
+}
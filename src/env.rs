@@ -0,0 +1,42 @@
+use std::env;
+
+/// Replaces the pdr given to `RustaceansWitAttitudesDrone::new`, `SetPacketDropRate` still changes it.
+pub const PDR_ENV: &str = "RWA_PDR";
+/// Simulation seed of the drones whose `DroneOptions::seed` and `simulation_seed` are not set, see `derive_seed`.
+pub const SEED_ENV: &str = "RWA_SEED";
+/// `debug` or `trace` turns on the output of the `debug` feature, any other level turns it off.
+pub const LOG_LEVEL_ENV: &str = "RWA_LOG_LEVEL";
+/// Time scale of the drones given no clock, see `ScaledClock`: 100 runs 100 times faster.
+pub const TIME_SCALE_ENV: &str = "RWA_TIME_SCALE";
+/// Comma-separated `AuxEventKind` names, for the drones whose `DroneOptions::aux_event_filter` is not set.
+pub const EVENT_FILTER_ENV: &str = "RWA_EVENT_FILTER";
+
+// Defaults for a whole simulation run, read when a drone is built. Values that don't parse are ignored.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EnvDefaults {
+    pub(crate) pdr: Option<f32>,
+    pub(crate) seed: Option<u64>,
+    #[cfg(feature = "debug")]
+    pub(crate) debug: Option<bool>,
+    pub(crate) event_filter: Option<Vec<String>>,
+    pub(crate) time_scale: Option<f64>,
+}
+
+impl EnvDefaults {
+    pub(crate) fn read() -> Self {
+        Self {
+            pdr: var(PDR_ENV).and_then(|pdr| pdr.parse().ok()).filter(|pdr| (0.0..=1.0).contains(pdr)),
+            seed: var(SEED_ENV).and_then(|seed| seed.parse().ok()),
+            #[cfg(feature = "debug")]
+            debug: var(LOG_LEVEL_ENV).map(|level| matches!(level.to_ascii_lowercase().as_str(), "debug" | "trace")),
+            event_filter: var(EVENT_FILTER_ENV).map(|filter| {
+                filter.split(',').map(str::trim).filter(|name| !name.is_empty()).map(String::from).collect()
+            }),
+            time_scale: var(TIME_SCALE_ENV).and_then(|scale| scale.parse().ok()).filter(|scale: &f64| scale.is_finite() && *scale > 0.0),
+        }
+    }
+}
+
+fn var(name: &str) -> Option<String> {
+    env::var(name).ok().map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
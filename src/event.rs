@@ -0,0 +1,195 @@
+use std::any::Any;
+use std::fmt;
+use std::time::Duration;
+
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+#[cfg(feature = "conformance")]
+use crate::conformance::Violation;
+use crate::stats::MemoryUsage;
+use crate::violation::ProtocolViolation;
+
+/// Events that have no counterpart in wg_2024's `DroneEvent`.
+/// They are sent on the optional auxiliary channel (see `with_aux_sender`).
+#[derive(Debug, Clone)]
+pub struct AuxEvent {
+    pub drone_id: NodeId,
+    pub kind: AuxEventKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum AuxEventKind {
+    /// An incoming packet violated a protocol invariant.
+    Diagnostic { diagnostic: Diagnostic, packet: Packet },
+    /// Handling the packet panicked (`DroneOptions::catch_panics`), the drone moved on to the next one.
+    Panicked { message: String, packet: Packet },
+    /// A fragment was dropped (`DroneOptions::audit_drops`).
+    FragmentDropped(DropAudit),
+    /// A queue holds more packets than `DroneOptions::congestion_threshold`.
+    Congested { queue: Queue, depth: usize },
+    /// A congested queue is down to half the threshold.
+    Drained { queue: Queue },
+    /// The receiver of a neighbor's channel is gone (`DroneOptions::neighbor_probe_interval`).
+    NeighborDisconnected(NodeId),
+    /// A neighbor that was disconnected can be reached again, or was replaced with `AddSender`.
+    NeighborReconnected(NodeId),
+    /// A FloodRequest could not be sent to a neighbor (full channel, receiver gone or link down), the
+    /// neighbors it was sent to get a `DroneEvent::PacketSent` each.
+    FloodSendFailed { to: NodeId, packet: Packet },
+    /// An `AddSender` was ignored, the drone already has `DroneOptions::max_neighbors` neighbors.
+    SenderRefused { node_id: NodeId, max_neighbors: usize },
+    /// The estimated memory of the drone went over `DroneOptions::memory_budget`, sent again only
+    /// after it went back under.
+    MemoryBudgetExceeded { memory: MemoryUsage, budget: usize },
+    /// The energy budget ran out, the drone entered the crashing state.
+    EnergyDepleted,
+    /// The drone has been handling the same input for longer than `DroneOptions::watchdog_deadline`,
+    /// typically blocked on a full channel.
+    Stalled { busy_for: Duration },
+    /// The input the drone was stalled on is done, `stalled_for` is accurate to a quarter of the deadline.
+    Recovered { stalled_for: Duration },
+    /// A profile was applied with `DroneHandle::reload_profile`, `changed` lists what it changed
+    /// (`pdr`, `links.<id>`, `options.<field>`, `options.*` if the options couldn't be compared).
+    ProfileReloaded { changed: Vec<String> },
+    /// The file watched with `DroneHandle::watch_profile` changed but couldn't be loaded, the drone kept its profile.
+    ProfileReloadFailed { reason: String },
+    /// Sent with every `DroneEvent::ControllerShortcut`, with why the drone couldn't deliver the packet itself.
+    /// `next_hop` is the neighbor it couldn't reach, `None` if the route was unusable.
+    Shortcut { reason: ShortcutReason, next_hop: Option<NodeId>, packet: Packet },
+    /// `count` shortcuts towards `next_hop` in `session_id` weren't sent in the last `over`, see
+    /// `DroneOptions::shortcut_rate_limit`.
+    ShortcutsSuppressed { next_hop: Option<NodeId>, session_id: u64, count: u64, over: Duration },
+    /// A controller command was applied, with the value applied. Not sent for a refused `AddSender`.
+    CommandApplied(AppliedCommand),
+    /// A malformed packet, sent by a `ViolationSink` made of the aux sender.
+    ProtocolViolation(ProtocolViolation),
+    /// A packet was handled in a way the protocol doesn't allow, see `conformance`.
+    #[cfg(feature = "conformance")]
+    ConformanceViolation(Violation),
+}
+
+impl AuxEventKind {
+    /// Name of the variant, as listed in `DroneOptions::aux_event_filter`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuxEventKind::Diagnostic { .. } => "Diagnostic",
+            AuxEventKind::Panicked { .. } => "Panicked",
+            AuxEventKind::FragmentDropped(_) => "FragmentDropped",
+            AuxEventKind::Congested { .. } => "Congested",
+            AuxEventKind::Drained { .. } => "Drained",
+            AuxEventKind::NeighborDisconnected(_) => "NeighborDisconnected",
+            AuxEventKind::NeighborReconnected(_) => "NeighborReconnected",
+            AuxEventKind::FloodSendFailed { .. } => "FloodSendFailed",
+            AuxEventKind::SenderRefused { .. } => "SenderRefused",
+            AuxEventKind::MemoryBudgetExceeded { .. } => "MemoryBudgetExceeded",
+            AuxEventKind::EnergyDepleted => "EnergyDepleted",
+            AuxEventKind::Stalled { .. } => "Stalled",
+            AuxEventKind::Recovered { .. } => "Recovered",
+            AuxEventKind::ProfileReloaded { .. } => "ProfileReloaded",
+            AuxEventKind::ProfileReloadFailed { .. } => "ProfileReloadFailed",
+            AuxEventKind::Shortcut { .. } => "Shortcut",
+            AuxEventKind::ShortcutsSuppressed { .. } => "ShortcutsSuppressed",
+            AuxEventKind::CommandApplied(_) => "CommandApplied",
+            AuxEventKind::ProtocolViolation(_) => "ProtocolViolation",
+            #[cfg(feature = "conformance")]
+            AuxEventKind::ConformanceViolation(_) => "ConformanceViolation",
+        }
+    }
+}
+
+/// A `DroneCommand` the drone applied, see `AuxEventKind::CommandApplied`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AppliedCommand {
+    SetPacketDropRate(f32),
+    /// The drone has a sender to this node.
+    AddSender(NodeId),
+    /// The drone has no sender to this node anymore, also sent while crashing.
+    RemoveSender(NodeId),
+}
+
+/// Why a packet was sent to the controller as a `ControllerShortcut`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutReason {
+    /// The drone has no sender to the next hop.
+    NoSender,
+    /// The link to the next hop is down, see `DroneHandle::link_down`.
+    LinkDown,
+    /// The receiver of the next hop's channel is gone.
+    Disconnected,
+    /// The next hop's channel is full, see `OverflowPolicy::Shortcut`.
+    ChannelFull,
+    /// The drone is paused and its backlog is full, see `DroneOptions::pause_backlog_capacity`.
+    BacklogFull,
+    /// The routing header can't be used, see `BrokenHeaderPolicy::Shortcut`.
+    BrokenHeader,
+}
+
+/// A queue of packets the drone watches for congestion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Queue {
+    /// Packets waiting to be handled by the drone.
+    Ingress,
+    /// Packets sent to a neighbor and not received yet (crossbeam channels only).
+    Neighbor(NodeId),
+}
+
+/// Everything that went into the decision to drop a fragment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DropAudit {
+    /// Time since the drone started.
+    pub at: Duration,
+    pub session_id: u64,
+    pub fragment_index: u64,
+    pub pdr: f32,
+    /// Value drawn from the rng and compared with the pdr, only with `DropMode::Random`.
+    pub sample: Option<f32>,
+    /// Number of fragments the drop mode was applied to before this one.
+    pub position: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The routing header has no hops at all.
+    EmptyRoute,
+    /// The route only contains this drone, so there is nowhere to go (or to Nack back to).
+    ZeroLengthRoute,
+    /// `hop_index` doesn't point inside `hops`.
+    HopIndexOutOfBounds { hop_index: usize, hops_len: usize },
+    /// `hop_index` is 0: it still points to the node that sent the packet, which has no hop to come from.
+    HopIndexNotIncremented,
+    /// The same node id appears more than once in the part of the route already travelled.
+    DuplicateHop(NodeId),
+    /// The remaining route revisits this drone or contains a cycle through the given node.
+    RoutingLoop(NodeId),
+    /// A fragment declares more bytes than `FRAGMENT_DSIZE`.
+    FragmentTooLong { length: u8 },
+    /// A fragment index is not smaller than the number of fragments.
+    FragmentIndexOutOfRange { fragment_index: u64, total_n_fragments: u64 },
+    /// The payload of a fragment doesn't match the checksum it carries, see `checksum`.
+    ChecksumMismatch { carried: u16, computed: u16 },
+    /// The current hop of the route is this node, not the drone.
+    UnexpectedRecipient(NodeId),
+}
+
+impl fmt::Display for DropAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} dropped fragment {} of session {} (pdr {}, ", self.at, self.fragment_index, self.session_id, self.pdr)?;
+        match self.sample {
+            Some(sample) => write!(f, "sample {}, ", sample)?,
+            None => write!(f, "no sample, ")?,
+        }
+        write!(f, "position {})", self.position)
+    }
+}
+
+// Text of a panic payload, panics almost always carry a &str or a String.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    }
+}
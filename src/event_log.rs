@@ -0,0 +1,93 @@
+//! Process-wide log of the `DroneEvent`s of every drone running in the process, numbered in the order they were
+//! reported, to reconstruct what happened across drones after a run ("who dropped fragment 12 of session 9 first?").
+//!
+//! The log is off until `enable` is called. Appending takes an atomic increment and a push on a lock-free queue,
+//! the drones never wait for each other; `drain` and `export` put the events back in order.
+//!
+//! An event is logged when the drone reports it: a `PacketSent` comes after the packet is on the channel, so the
+//! next drone may log what it did with the packet first. A `PacketDropped` is logged before the Nack is sent.
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use web_time::Instant;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+
+/// An event of the log.
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    /// Position in the log, from 0 and without gaps.
+    pub seq: u64,
+    /// Time since the log was first enabled.
+    pub at: Duration,
+    pub drone_id: NodeId,
+    pub event: DroneEvent,
+}
+
+struct Log {
+    start: Instant,
+    send: Sender<LoggedEvent>,
+    recv: Receiver<LoggedEvent>,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SEQ: AtomicU64 = AtomicU64::new(0);
+static LOG: OnceLock<Log> = OnceLock::new();
+
+/// Start logging the events of every drone of the process.
+pub fn enable() {
+    log();
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Stop logging, the events already logged are kept until drained.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// The events logged since the last drain, in order. Events reported while draining may be left for the next one.
+pub fn drain() -> Vec<LoggedEvent> {
+    let Some(log) = LOG.get() else {
+        return Vec::new();
+    };
+    let mut events: Vec<LoggedEvent> = log.recv.try_iter().collect();
+    events.sort_unstable_by_key(|event| event.seq);
+    events
+}
+
+/// Drain the log to `writer`, one line per event: "<seq> <time since enabled> <drone> <event>".
+/// Returns the number of events written.
+pub fn export(mut writer: impl Write) -> io::Result<usize> {
+    let events = drain();
+    for event in &events {
+        writeln!(writer, "{} {:?} {} {:?}", event.seq, event.at, event.drone_id, event.event)?;
+    }
+    writer.flush()?;
+    Ok(events.len())
+}
+
+// called by every drone for every event it reports
+pub(crate) fn append(drone_id: NodeId, event: &DroneEvent) {
+    if !is_enabled() {
+        return;
+    }
+    let log = log();
+    let seq = SEQ.fetch_add(1, Ordering::AcqRel);
+    // the queue lives as long as the process, the send can't fail
+    let _ = log.send.send(LoggedEvent { seq, at: log.start.elapsed(), drone_id, event: event.clone() });
+}
+
+fn log() -> &'static Log {
+    LOG.get_or_init(|| {
+        let (send, recv) = unbounded();
+        Log { start: Instant::now(), send, recv }
+    })
+}
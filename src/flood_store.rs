@@ -0,0 +1,90 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use wg_2024::network::NodeId;
+
+/// The floods each drone has already forwarded, by flood id and initiator.
+///
+/// Every drone has one of its own. Drones running in the same process can share one instead
+/// (`RustaceansWitAttitudesDrone::with_flood_store`), so that each flood is stored once whatever the
+/// number of drones that saw it, and to tell which drones a flood went through. Each drone still
+/// only sees its own entries and forgets its oldest past `DroneOptions::flood_dedup_capacity`.
+#[derive(Debug, Clone, Default)]
+pub struct FloodStore {
+    inner: Arc<Mutex<Floods>>,
+}
+
+#[derive(Debug, Default)]
+struct Floods {
+    // (flood_id, initiator_id) -> drones that forwarded it
+    seen_by: HashMap<(u64, NodeId), HashSet<NodeId>>,
+    // per drone, oldest first
+    order: HashMap<NodeId, VecDeque<(u64, NodeId)>>,
+}
+
+impl FloodStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // false if `drone_id` saw the flood already, its oldest floods are forgotten past `capacity`
+    pub(crate) fn remember(&self, drone_id: NodeId, flood_id: u64, initiator_id: NodeId, capacity: usize) -> bool {
+        let mut floods = self.lock();
+        if !floods.seen_by.entry((flood_id, initiator_id)).or_default().insert(drone_id) {
+            return false;
+        }
+        let mut evicted = Vec::new();
+        let order = floods.order.entry(drone_id).or_default();
+        order.push_back((flood_id, initiator_id));
+        while order.len() > capacity.max(1) {
+            evicted.extend(order.pop_front());
+        }
+        for flood in evicted {
+            if let Some(drones) = floods.seen_by.get_mut(&flood) {
+                drones.remove(&drone_id);
+                if drones.is_empty() {
+                    floods.seen_by.remove(&flood);
+                }
+            }
+        }
+        true
+    }
+
+    // floods remembered by `drone_id`
+    pub(crate) fn len_of(&self, drone_id: NodeId) -> usize {
+        self.lock().order.get(&drone_id).map_or(0, VecDeque::len)
+    }
+
+    // floods remembered by `drone_id`, oldest first
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn floods_of(&self, drone_id: NodeId) -> Vec<(u64, NodeId)> {
+        self.lock().order.get(&drone_id).map(|order| order.iter().copied().collect()).unwrap_or_default()
+    }
+
+    pub fn has_seen(&self, drone_id: NodeId, flood_id: u64, initiator_id: NodeId) -> bool {
+        self.lock().seen_by.get(&(flood_id, initiator_id)).is_some_and(|drones| drones.contains(&drone_id))
+    }
+
+    /// Drones that forwarded the flood, in no particular order.
+    pub fn seen_by(&self, flood_id: u64, initiator_id: NodeId) -> Vec<NodeId> {
+        self.lock().seen_by.get(&(flood_id, initiator_id)).map(|drones| drones.iter().copied().collect()).unwrap_or_default()
+    }
+
+    /// `(flood_id, initiator_id)` of the floods remembered by at least one drone.
+    pub fn floods(&self) -> Vec<(u64, NodeId)> {
+        self.lock().seen_by.keys().copied().collect()
+    }
+
+    /// Number of floods remembered, counted once per drone.
+    pub fn len(&self) -> usize {
+        self.lock().order.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Floods> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
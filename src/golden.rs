@@ -0,0 +1,98 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::record::ReplayOutput;
+
+/// Set to regenerate the golden traces instead of comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "RWA_UPDATE_GOLDEN";
+
+// lines shown before the first difference
+const DIFF_CONTEXT: usize = 3;
+
+/// Text form of a replay: every input followed by the events and packets it caused, one per line.
+/// Timestamps are left out so that the trace only changes when the behaviour does.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub lines: Vec<String>,
+}
+
+/// First difference between a trace and its golden version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceDiff {
+    /// 1-based line of the first difference.
+    pub line: usize,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    /// Lines shared by both traces right before the difference.
+    pub context: Vec<String>,
+    pub expected_len: usize,
+    pub actual_len: usize,
+}
+
+impl Trace {
+    pub fn from_replay(output: &ReplayOutput) -> Self {
+        let mut lines = Vec::new();
+        for step in &output.steps {
+            lines.push(format!("in    {:?}", step.input));
+            for (node_id, packet) in &step.sent {
+                lines.push(format!("  to {} {:?}", node_id, packet));
+            }
+            for event in &step.events {
+                lines.push(format!("  sc  {:?}", event));
+            }
+        }
+        Self { lines }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self { lines: fs::read_to_string(path)?.lines().map(str::to_owned).collect() })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        fs::write(path, text)
+    }
+
+    /// `None` if both traces are the same.
+    pub fn diff(&self, golden: &Trace) -> Option<TraceDiff> {
+        let line = (0..self.lines.len().max(golden.lines.len()))
+            .find(|&i| self.lines.get(i) != golden.lines.get(i))?;
+        Some(TraceDiff {
+            line: line + 1,
+            expected: golden.lines.get(line).cloned(),
+            actual: self.lines.get(line).cloned(),
+            context: self.lines[line.saturating_sub(DIFF_CONTEXT)..line].to_vec(),
+            expected_len: golden.lines.len(),
+            actual_len: self.lines.len(),
+        })
+    }
+}
+
+/// Compare a replay with the golden trace at `path` and panic with the first difference.
+/// The golden trace is written instead if it doesn't exist yet or `RWA_UPDATE_GOLDEN` is set.
+pub fn assert_golden(output: &ReplayOutput, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let trace = Trace::from_replay(output);
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() || !path.exists() {
+        trace.save(path).unwrap_or_else(|e| panic!("can't write golden trace {}: {}", path.display(), e));
+        return;
+    }
+    let golden = Trace::from_file(path).unwrap_or_else(|e| panic!("can't read golden trace {}: {}", path.display(), e));
+    if let Some(diff) = trace.diff(&golden) {
+        panic!("trace differs from {}\n{}\n(set {} to update it)", path.display(), diff, UPDATE_GOLDEN_ENV);
+    }
+}
+
+impl fmt::Display for TraceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "first difference at line {} ({} lines expected, {} found)", self.line, self.expected_len, self.actual_len)?;
+        for line in &self.context {
+            writeln!(f, "  {}", line)?;
+        }
+        writeln!(f, "- {}", self.expected.as_deref().unwrap_or("<end of trace>"))?;
+        write!(f, "+ {}", self.actual.as_deref().unwrap_or("<end of trace>"))
+    }
+}
@@ -0,0 +1,235 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::io;
+#[cfg(feature = "toml")]
+use std::path::PathBuf;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+#[cfg(feature = "toml")]
+use std::time::Duration;
+
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::FloodResponse;
+
+use crate::acl::Acl;
+use crate::drone::RustaceansWitAttitudesDrone;
+use crate::event::panic_message;
+use crate::link::{LinkOutage, LinkPdr, LinkProfile};
+#[cfg(feature = "toml")]
+use crate::profile::{self, DroneProfile, ProfileWatcher};
+#[cfg(feature = "snapshot")]
+use crate::snapshot::DroneSnapshot;
+use crate::split;
+#[cfg(feature = "http")]
+use crate::status;
+use crate::stats::DroneStats;
+
+// Requests that don't exist in wg_2024's DroneCommand, sent by the DroneHandle.
+#[derive(Debug)]
+pub(crate) enum HandleCommand {
+    Pause,
+    Resume,
+    SetPdr(f32),
+    Crash,
+    SetDebug(bool),
+    LinkDown(NodeId, LinkOutage),
+    LinkUp(NodeId),
+    SetLinkPdr(NodeId, LinkPdr),
+    SetLinkProfile(NodeId, LinkProfile),
+    SetAcl(Acl),
+    #[cfg(feature = "toml")]
+    ReloadProfile(Box<DroneProfile>),
+    #[cfg(feature = "toml")]
+    ProfileReloadFailed(String),
+    #[cfg(feature = "snapshot")]
+    Snapshot(Sender<DroneSnapshot>),
+    InitiateFlood(Sender<FloodResponse>),
+    Shutdown,
+}
+
+/// Access to a drone started with `spawn`.
+pub struct DroneHandle {
+    id: NodeId,
+    command_send: Sender<HandleCommand>,
+    stats: Arc<Mutex<DroneStats>>,
+    thread: JoinHandle<()>,
+}
+
+/// How `spawn_with` starts the drone thread.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    /// Handle packets on a second thread, see `spawn_split`.
+    pub split: bool,
+    /// Stack size of the drone thread(s), the std default if `None`.
+    pub stack_size: Option<usize>,
+    /// Where to report that the thread started and exited.
+    pub report_send: Option<Sender<ThreadReport>>,
+}
+
+/// Life cycle of a drone thread started by `spawn_with`.
+#[derive(Debug, Clone)]
+pub struct ThreadReport {
+    pub drone_id: NodeId,
+    pub kind: ThreadReportKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThreadReportKind {
+    Started,
+    /// The drone stopped (shutdown or closed channels).
+    Exited,
+    /// The drone panicked, the panic is still propagated to `JoinHandle::join`.
+    Panicked { message: String },
+}
+
+/// Runs the drone on a thread named `rwa-drone-<id>` and returns a handle to it.
+pub fn spawn(drone: RustaceansWitAttitudesDrone) -> io::Result<DroneHandle> {
+    spawn_with(drone, SpawnOptions::default())
+}
+
+/// Like `spawn`, but packets are handled on a second thread named `rwa-drone-<id>-data`,
+/// so that a burst of packets never delays a controller or handle command.
+pub fn spawn_split(drone: RustaceansWitAttitudesDrone) -> io::Result<DroneHandle> {
+    spawn_with(drone, SpawnOptions { split: true, ..SpawnOptions::default() })
+}
+
+/// `spawn` or `spawn_split` with a custom stack size and reports of the thread life cycle.
+pub fn spawn_with(mut drone: RustaceansWitAttitudesDrone, options: SpawnOptions) -> io::Result<DroneHandle> {
+    let (command_send, command_recv) = unbounded();
+    drone.attach_handle(command_recv);
+    let id = drone.id();
+    let stats = drone.shared_stats();
+    #[cfg(feature = "http")]
+    status::register(id, &stats);
+    let thread = if options.split {
+        let run = split::run(drone, options.stack_size)?;
+        spawn_thread(id, &options, run)?
+    } else {
+        spawn_thread(id, &options, move || drone.run())?
+    };
+    Ok(DroneHandle { id, command_send, stats, thread })
+}
+
+fn spawn_thread(id: NodeId, options: &SpawnOptions, run: impl FnOnce() + Send + 'static) -> io::Result<JoinHandle<()>> {
+    let mut builder = thread::Builder::new().name(format!("rwa-drone-{}", id));
+    if let Some(stack_size) = options.stack_size {
+        builder = builder.stack_size(stack_size);
+    }
+    let report_send = options.report_send.clone();
+    builder.spawn(move || {
+        let report = |kind| {
+            if let Some(report_send) = &report_send {
+                let _ = report_send.send(ThreadReport { drone_id: id, kind });
+            }
+        };
+        report(ThreadReportKind::Started);
+        match panic::catch_unwind(AssertUnwindSafe(run)) {
+            Ok(()) => report(ThreadReportKind::Exited),
+            Err(payload) => {
+                report(ThreadReportKind::Panicked { message: panic_message(payload.as_ref()) });
+                panic::resume_unwind(payload)
+            }
+        }
+    })
+}
+
+impl DroneHandle {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+    pub fn stats(&self) -> DroneStats {
+        self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+    /// Zero the counters, to measure one phase of an experiment.
+    pub fn reset_stats(&self) {
+        self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).reset();
+    }
+    /// Stop processing packets, what happens to them depends on `DroneOptions::pause_policy`.
+    /// Controller commands are still handled.
+    pub fn pause(&self) {
+        self.send(HandleCommand::Pause);
+    }
+    pub fn resume(&self) {
+        self.send(HandleCommand::Resume);
+    }
+    /// Same as the controller's `SetPacketDropRate`.
+    pub fn set_pdr(&self, pdr: f32) {
+        self.send(HandleCommand::SetPdr(pdr));
+    }
+    /// Same as the controller's `Crash`: the drone handles the packets already sent to it and stops
+    /// once its neighbors removed their sender.
+    pub fn crash(&self) {
+        self.send(HandleCommand::Crash);
+    }
+    /// Turn the `debug` feature output of this drone on or off.
+    pub fn set_debug(&self, enabled: bool) {
+        self.send(HandleCommand::SetDebug(enabled));
+    }
+    /// Simulate a failure of the link to a neighbor, it recovers silently.
+    pub fn link_down(&self, node_id: NodeId, outage: LinkOutage) {
+        self.send(HandleCommand::LinkDown(node_id, outage));
+    }
+    pub fn link_up(&self, node_id: NodeId) {
+        self.send(HandleCommand::LinkUp(node_id));
+    }
+    /// Drop packets from and to a neighbor with these probabilities on top of the pdr, `LinkPdr::default()` to stop.
+    pub fn set_link_pdr(&self, node_id: NodeId, link_pdr: LinkPdr) {
+        self.send(HandleCommand::SetLinkPdr(node_id, link_pdr));
+    }
+    /// Replace the delay, jitter, bandwidth and pdr of both directions of the link to a neighbor at once.
+    pub fn set_link_profile(&self, node_id: NodeId, profile: LinkProfile) {
+        self.send(HandleCommand::SetLinkProfile(node_id, profile));
+    }
+    /// Replace the packet filtering rules, `Acl::default()` lets everything through.
+    pub fn set_acl(&self, acl: Acl) {
+        self.send(HandleCommand::SetAcl(acl));
+    }
+    /// Apply the pdr, link profiles and options of `profile` without restarting the drone, what changed is
+    /// reported with `AuxEventKind::ProfileReloaded`. Links missing from the profile go back to the default,
+    /// the seed, energy, watchdog deadline and fault schedule are kept.
+    #[cfg(feature = "toml")]
+    pub fn reload_profile(&self, profile: DroneProfile) {
+        self.send(HandleCommand::ReloadProfile(Box::new(profile)));
+    }
+    /// Check every `interval` whether the profile file was modified, and reload the drone's profile from it.
+    /// Stops when the watcher is dropped.
+    #[cfg(feature = "toml")]
+    pub fn watch_profile(&self, path: impl Into<PathBuf>, interval: Duration) -> io::Result<ProfileWatcher> {
+        profile::watch(self.id, self.command_send.clone(), path.into(), interval)
+    }
+    /// Send a FloodRequest of the drone's own to every neighbor, the FloodResponses that come back
+    /// are received on the returned channel. Drop it to stop collecting them.
+    pub fn initiate_flood(&self) -> Receiver<FloodResponse> {
+        let (response_send, response_recv) = unbounded();
+        self.send(HandleCommand::InitiateFlood(response_send));
+        response_recv
+    }
+    /// State of the drone between two inputs, see `DroneSnapshot`. `None` if the drone stopped.
+    /// A crashing drone still answers, with `crashing` set.
+    #[cfg(feature = "snapshot")]
+    pub fn snapshot(&self) -> Option<DroneSnapshot> {
+        let (snapshot_send, snapshot_recv) = unbounded();
+        self.send(HandleCommand::Snapshot(snapshot_send));
+        snapshot_recv.recv().ok()
+    }
+    /// Let the drone finish the packet it's handling, stop it and wait for its thread.
+    pub fn shutdown(self) -> thread::Result<()> {
+        self.send(HandleCommand::Shutdown);
+        self.thread.join()
+    }
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+    pub fn join_handle(&self) -> &JoinHandle<()> {
+        &self.thread
+    }
+    pub fn into_join_handle(self) -> JoinHandle<()> {
+        self.thread
+    }
+
+    fn send(&self, command: HandleCommand) {
+        // the drone may already be gone (crashed or panicked), nothing to do then
+        let _ = self.command_send.send(command);
+    }
+}
@@ -0,0 +1,68 @@
+//! Internals of the drone for white-box tests, behind the `test-internals` feature.
+//!
+//! Not part of the stable API: names and behaviour follow the implementation and can change with any commit.
+//! A drone driven from here never runs its own loop, the test feeds it one input at a time on its thread
+//! and reads what comes out of the channels it was built with.
+
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet};
+
+use crate::acl::PacketKind;
+use crate::drone::RustaceansWitAttitudesDrone;
+use crate::options::DroneOptions;
+
+pub use crate::nack::{for_packet, from_hop, return_route};
+
+/// What `run` does before its first input: timers, watchdog, outgoing workers...
+pub fn start(drone: &mut RustaceansWitAttitudesDrone) {
+    drone.start();
+}
+
+/// Handle `packet` as if it was just received, in the current state of the drone (running, paused, crashing...).
+pub fn handle_packet(drone: &mut RustaceansWitAttitudesDrone, packet: Packet) {
+    drone.dispatch_packet(packet);
+}
+
+/// Apply `command` as if it was just received from the simulation controller.
+pub fn handle_command(drone: &mut RustaceansWitAttitudesDrone, command: DroneCommand) {
+    drone.dispatch_command(command);
+}
+
+/// What `run` does when no packet is waiting: delayed and held packets, timers, scenario steps...
+pub fn housekeeping(drone: &mut RustaceansWitAttitudesDrone) {
+    drone.housekeeping(false);
+}
+
+/// Replace the options of the drone, without the bookkeeping of `DroneHandle::reload_profile`: timers already
+/// set from the old options stay as they are.
+pub fn set_options(drone: &mut RustaceansWitAttitudesDrone, options: DroneOptions) {
+    drone.set_options(options);
+}
+
+/// How the sends chosen with `fail_sends` fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectedFailure {
+    /// As if the channel was full.
+    Full,
+    /// As if the receiver was gone.
+    Disconnected,
+}
+
+/// Make the sends of the drone to `neighbor` (every neighbor if `None`) of packets of `kind` (any type if `None`)
+/// fail with `failure` until `clear_send_failures`, without touching the channels. The packet never reaches the
+/// neighbor and the drone goes through its usual failure handling: overflow policy, Nack, shortcut...
+pub fn fail_sends(drone: &mut RustaceansWitAttitudesDrone, neighbor: Option<NodeId>, kind: Option<PacketKind>, failure: InjectedFailure) {
+    drone.inject_send_failure(neighbor, kind, failure);
+}
+
+/// Undo every `fail_sends`.
+pub fn clear_send_failures(drone: &mut RustaceansWitAttitudesDrone) {
+    drone.clear_send_failures();
+}
+
+/// Send the Nack of `packet` the drone would, from the hop its header points to, through the usual
+/// deduplication, misreporting and forwarding.
+pub fn send_nack(drone: &mut RustaceansWitAttitudesDrone, packet: &Packet, nack_type: NackType) {
+    drone.send_nack(packet, nack_type);
+}
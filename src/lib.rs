@@ -1,2 +1,106 @@
-mod drone;
-pub use drone::RustaceansWitAttitudesDrone;
\ No newline at end of file
+// Everything but the spec-only drone of `minimal` is behind the default `full` feature.
+macro_rules! cfg_full {
+    ($($item:item)*) => {
+        $(#[cfg(feature = "full")] $item)*
+    };
+}
+
+pub mod checksum;
+pub mod nack;
+pub mod prelude;
+pub use wg_2024::drone::Drone;
+#[cfg(not(feature = "full"))]
+mod minimal;
+#[cfg(not(feature = "full"))]
+pub use minimal::RustaceansWitAttitudesDrone;
+/// Alias for initializers that expect the drone at `crate::MyDrone`, see also `prelude`.
+#[cfg(not(feature = "full"))]
+pub use minimal::RustaceansWitAttitudesDrone as MyDrone;
+
+cfg_full! {
+    mod acl;
+    #[cfg(feature = "tokio")]
+    mod async_drone;
+    mod behavior;
+    mod channel;
+    mod clock;
+    #[cfg(feature = "conformance")]
+    pub mod conformance;
+    mod drone;
+    mod env;
+    mod event;
+    pub mod event_log;
+    mod flood_store;
+    #[cfg(feature = "record")]
+    mod golden;
+    mod handle;
+    #[cfg(feature = "test-internals")]
+    pub mod internals;
+    mod link;
+    #[cfg(feature = "tui")]
+    mod monitor;
+    #[cfg(feature = "otel")]
+    mod otel;
+    mod options;
+    mod outgoing;
+    #[cfg(feature = "toml")]
+    mod profile;
+    #[cfg(feature = "record")]
+    mod record;
+    mod scenario;
+    mod scheduler;
+    mod seed;
+    mod selftest;
+    mod sessions;
+    mod shortcut;
+    mod slow;
+    #[cfg(feature = "snapshot")]
+    mod snapshot;
+    mod split;
+    mod stats;
+    mod stepper;
+    #[cfg(feature = "http")]
+    mod status;
+    mod tap;
+    mod timer_wheel;
+    mod validation;
+    mod violation;
+    mod watchdog;
+    pub use acl::{Acl, AclAction, AclRule, PacketKind};
+    #[cfg(feature = "tokio")]
+    pub use async_drone::RustaceansWitAttitudesDroneAsync;
+    pub use behavior::{Adversarial, BehaviorStrategy, StandardBehavior, StoreAndForward, Unreachable, Verdict};
+    pub use channel::{ChannelReceiver, ChannelSender, SendFailure};
+    pub use clock::{Clock, ManualClock, ScaledClock, SystemClock};
+    pub use drone::RustaceansWitAttitudesDrone;
+    /// Alias for initializers that expect the drone at `crate::MyDrone`, see also `prelude`.
+    pub use drone::RustaceansWitAttitudesDrone as MyDrone;
+    pub use env::{EVENT_FILTER_ENV, LOG_LEVEL_ENV, PDR_ENV, SEED_ENV, TIME_SCALE_ENV};
+    pub use handle::{spawn, spawn_split, spawn_with, DroneHandle, SpawnOptions, ThreadReport, ThreadReportKind};
+    pub use event::{AppliedCommand, AuxEvent, AuxEventKind, Diagnostic, DropAudit, Queue, ShortcutReason};
+    pub use flood_store::FloodStore;
+    #[cfg(feature = "record")]
+    pub use golden::{assert_golden, Trace, TraceDiff, UPDATE_GOLDEN_ENV};
+    #[cfg(feature = "tui")]
+    pub use monitor::monitor;
+    pub use link::{LinkDirection, LinkOutage, LinkPdr, LinkProfile};
+    #[cfg(feature = "otel")]
+    pub use otel::OtlpExporter;
+    pub use options::{BrokenHeaderPolicy, ChaosOptions, ControllerLossPolicy, CrashFloodPolicy, DroneOptions, DropMode, DroppablePackets, DuplicateFloodPolicy, EnergyModel, EventOverflowPolicy, Fairness, HopReliability, OptionsError, OverflowPolicy, PausePolicy, ReorderOptions, ShortcutRateLimit, UnexpectedRecipientPolicy};
+    #[cfg(feature = "toml")]
+    pub use profile::{DroneProfile, ProfileError, ProfileWatcher, Profiles};
+    #[cfg(feature = "record")]
+    pub use record::{RecordedCommand, RecordedEntry, RecordedInput, Recording, RecordingError, RecordingHeader, ReplayOutput, ReplayStep};
+    pub use scenario::{Scenario, ScenarioAction, ScenarioError, ScenarioStep, ScenarioTrigger};
+    pub use scheduler::Scheduler;
+    pub use seed::derive_seed;
+    pub use selftest::{assert_pdr_fidelity, measure_pdr, PdrMeasurement};
+    pub use slow::SlowSender;
+    #[cfg(feature = "snapshot")]
+    pub use snapshot::{DroneSnapshot, SnapshotError};
+    pub use stats::{write_stats_csv, DroneState, DroneStats, FloodRecord, Histogram, MemoryUsage, NackCounts, NeighborStats, SessionStats};
+    pub use stepper::Stepper;
+    pub use violation::{ProtocolViolation, ViolationLog, ViolationSink};
+    #[cfg(feature = "http")]
+    pub use status::{spawned_drones, DroneStatus, StatusServer, Topology};
+}
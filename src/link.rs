@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use web_time::Instant;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How long a neighbor link stays down. While it's down the sender is kept,
+/// fragments routed to that neighbor are Nacked with ErrorInRouting (see `LinkProfile::queue_while_down`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LinkOutage {
+    For(Duration),
+    /// Recovers after this many packets have been routed to the neighbor.
+    Packets(u64),
+    /// Until `link_up` is called.
+    Indefinite,
+}
+
+/// Drop probabilities of the link to a neighbor, on top of the drone's pdr, to model asymmetric links.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LinkPdr {
+    /// Packets received from the neighbor.
+    pub ingress: f32,
+    /// Packets sent to the neighbor.
+    pub egress: f32,
+}
+
+/// How one direction of the link to a neighbor behaves.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct LinkDirection {
+    pub delay: Duration,
+    /// Up to this much more delay, uniformly distributed. Packets can overtake each other.
+    pub jitter: Duration,
+    /// Packets per second, unlimited if `None`. Packets wait for the ones before them.
+    pub bandwidth: Option<u32>,
+    /// Drop probability, on top of the drone's pdr.
+    pub pdr: f32,
+}
+
+impl LinkDirection {
+    // time to put one packet on the link
+    pub(crate) fn transmission_time(&self) -> Duration {
+        self.bandwidth.map_or(Duration::ZERO, |bandwidth| Duration::from_secs(1) / bandwidth.max(1))
+    }
+}
+
+/// Both directions of the link to a neighbor, see `RustaceansWitAttitudesDrone::with_link_profile`.
+/// Delay, jitter and bandwidth apply to the packets the drone forwards, FloodRequests are never held back.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LinkProfile {
+    /// Packets received from the neighbor.
+    pub ingress: LinkDirection,
+    /// Packets sent to the neighbor.
+    pub egress: LinkDirection,
+    /// Fragments kept while the link is down and forwarded once it's back up, instead of Nacked.
+    /// The ones that don't fit are Nacked as usual.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub queue_while_down: usize,
+}
+
+impl LinkProfile {
+    /// The same behaviour in both directions.
+    pub fn symmetric(direction: LinkDirection) -> Self {
+        Self { ingress: direction, egress: direction, queue_while_down: 0 }
+    }
+}
+
+// LinkOutage as tracked by the drone
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LinkDown {
+    Until(Instant),
+    Packets(u64),
+    Indefinite,
+}
+
+impl LinkDown {
+    pub(crate) fn new(outage: LinkOutage, now: Instant) -> Self {
+        match outage {
+            LinkOutage::For(duration) => LinkDown::Until(now + duration),
+            LinkOutage::Packets(packets) => LinkDown::Packets(packets),
+            LinkOutage::Indefinite => LinkDown::Indefinite,
+        }
+    }
+    pub(crate) fn is_over(&self, now: Instant) -> bool {
+        match self {
+            LinkDown::Until(until) => now >= *until,
+            LinkDown::Packets(packets) => *packets == 0,
+            LinkDown::Indefinite => false,
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! The drone built without the `full` feature: the forwarding logic of the protocol specification
+//! and nothing else. No options, statistics, captures, delays nor debug output.
+
+use crossbeam_channel::{never, select_biased, Receiver, Sender};
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{FloodRequest, NackType, NodeType, Packet, PacketType};
+
+use crate::nack;
+
+pub struct RustaceansWitAttitudesDrone {
+    id: NodeId,
+    controller_send: Sender<DroneEvent>,        // send to sc
+    controller_recv: Receiver<DroneCommand>,    // receive from sc (never() once it disconnected)
+    packet_recv: Receiver<Packet>,              // receive from neighbor nodes (never() once it disconnected)
+    pdr: f32,
+    packet_send: HashMap<NodeId, Sender<Packet>>,   // send to neighbor nodes
+    floods: HashSet<(u64, NodeId)>,             // (flood_id, initiator_id) already forwarded
+    crashing: bool,
+    closed: u8,                                 // channels above that disconnected
+}
+
+impl Drone for RustaceansWitAttitudesDrone {
+    fn new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+    ) -> Self {
+        Self {
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            pdr,
+            packet_send,
+            floods: HashSet::new(),
+            crashing: false,
+            closed: 0,
+        }
+    }
+
+    // Runs until the crash is complete, or both channels are closed.
+    fn run(&mut self) {
+        loop {
+            select_biased! {
+                recv(self.controller_recv) -> command => {
+                    match command {
+                        Ok(command) => {
+                            if self.handle_command(command) {
+                                return;
+                            }
+                        }
+                        Err(_) => {
+                            self.controller_recv = never();
+                            self.closed += 1;
+                        }
+                    }
+                },
+                recv(self.packet_recv) -> packet => {
+                    match packet {
+                        Ok(packet) => self.handle_packet(packet),
+                        Err(_) => {
+                            self.packet_recv = never();
+                            self.closed += 1;
+                        }
+                    }
+                },
+            }
+            // never() on both sides, nothing can happen anymore
+            if self.closed == 2 {
+                return;
+            }
+        }
+    }
+}
+
+impl RustaceansWitAttitudesDrone {
+    // <editor-fold desc="Simulation controller commands">
+    // true once the crash is complete
+    fn handle_command(&mut self, command: DroneCommand) -> bool {
+        match command {
+            DroneCommand::RemoveSender(node_id) => {
+                self.packet_send.remove(&node_id);
+                // If no senders are left, the drone can exit the crashing state and be considered as crashed
+                return self.crashing && self.packet_send.is_empty();
+            }
+            // Ignore other commands while crashing
+            _ if self.crashing => {}
+            DroneCommand::SetPacketDropRate(pdr) => self.pdr = pdr,
+            DroneCommand::Crash => self.crashing = true,
+            DroneCommand::AddSender(node_id, sender) => {
+                self.packet_send.insert(node_id, sender);
+            }
+        }
+        false
+    }
+    fn send_event(&self, event: DroneEvent) {
+        // nobody to tell once the controller is gone
+        let _ = self.controller_send.send(event);
+    }
+    // </editor-fold>
+
+    // <editor-fold desc="Packets">
+    fn handle_packet(&mut self, mut packet: Packet) {
+        // FloodRequests ignore hop_index and hops
+        if let PacketType::FloodRequest(flood_request) = packet.pack_type {
+            self.handle_flood_request(packet.session_id, flood_request);
+            return;
+        }
+
+        // a header that points nowhere can't even be Nacked back
+        let Some(current_hop) = packet.routing_header.current_hop() else {
+            return;
+        };
+        if current_hop != self.id {
+            self.send_nack(&packet, NackType::UnexpectedRecipient(self.id));
+            return;
+        }
+        let Some(next_hop) = packet.routing_header.next_hop() else {
+            self.send_nack(&packet, NackType::DestinationIsDrone);
+            return;
+        };
+        if !self.packet_send.contains_key(&next_hop) {
+            self.send_nack(&packet, NackType::ErrorInRouting(next_hop));
+            return;
+        }
+
+        match packet.pack_type {
+            PacketType::MsgFragment(_) => {
+                if self.crashing {
+                    self.send_nack(&packet, NackType::ErrorInRouting(self.id));
+                    return;
+                }
+                if rand::thread_rng().gen_range(0.0..1.0) < self.pdr {
+                    self.send_event(DroneEvent::PacketDropped(packet.clone()));
+                    self.send_nack(&packet, NackType::Dropped);
+                    return;
+                }
+                packet.routing_header.increase_hop_index();
+                if let Some(packet) = self.send_packet(packet) {
+                    self.send_nack(&packet, NackType::ErrorInRouting(next_hop));
+                }
+            }
+            // Acks, Nacks and FloodResponses can't be lost, the controller delivers the ones we can't
+            PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
+                packet.routing_header.increase_hop_index();
+                self.forward_or_shortcut(packet);
+            }
+            PacketType::FloodRequest(_) => unreachable!("handled above"),
+        }
+    }
+    fn handle_flood_request(&mut self, session_id: u64, mut flood_request: FloodRequest) {
+        // Lose FloodRequests while crashing
+        if self.crashing {
+            return;
+        }
+        let previous = flood_request.path_trace.last().map(|(node_id, _)| *node_id);
+        flood_request.increment(self.id, NodeType::Drone);
+        let neighbors: Vec<NodeId> = self.packet_send.keys().copied().filter(|node_id| Some(*node_id) != previous).collect();
+        // a flood seen already, or nobody else to send it to: answer it
+        if !self.floods.insert((flood_request.flood_id, flood_request.initiator_id)) || neighbors.is_empty() {
+            let mut response = flood_request.generate_response(session_id);
+            response.routing_header.increase_hop_index();
+            self.forward_or_shortcut(response);
+            return;
+        }
+        let packet = Packet::new_flood_request(Default::default(), session_id, flood_request);
+        for node_id in neighbors {
+            if let Some(sender) = self.packet_send.get(&node_id) {
+                if sender.send(packet.clone()).is_ok() {
+                    self.send_event(DroneEvent::PacketSent(packet.clone()));
+                }
+            }
+        }
+    }
+    // Nack of `packet` from us back to its source
+    fn send_nack(&mut self, packet: &Packet, nack_type: NackType) {
+        // a Nack is only sent for a fragment, the others go through the controller
+        if !matches!(packet.pack_type, PacketType::MsgFragment(_)) {
+            let mut packet = packet.clone();
+            packet.routing_header.increase_hop_index();
+            self.send_event(DroneEvent::ControllerShortcut(packet));
+            return;
+        }
+        // no way back, the fragment is lost
+        let Some(mut nack) = nack::for_packet(packet, nack_type) else {
+            return;
+        };
+        nack.routing_header.increase_hop_index();
+        self.forward_or_shortcut(nack);
+    }
+    // `packet` points to the next hop already
+    fn forward_or_shortcut(&mut self, packet: Packet) {
+        if let Some(packet) = self.send_packet(packet) {
+            self.send_event(DroneEvent::ControllerShortcut(packet));
+        }
+    }
+    // the packet back if it couldn't be sent
+    fn send_packet(&mut self, packet: Packet) -> Option<Packet> {
+        let Some(sender) = packet.routing_header.current_hop().and_then(|next_hop| self.packet_send.get(&next_hop)) else {
+            return Some(packet);
+        };
+        match sender.send(packet.clone()) {
+            Ok(()) => {
+                self.send_event(DroneEvent::PacketSent(packet));
+                None
+            }
+            Err(e) => Some(e.0),
+        }
+    }
+    // </editor-fold>
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use wg_2024::network::NodeId;
+
+use crate::handle::DroneHandle;
+use crate::stats::DroneStats;
+
+// pdr change of a single + or - key press
+const PDR_STEP: f32 = 0.05;
+
+const HELP: &str = "↑/↓ select  c crash  +/- pdr  p pause/resume  q quit";
+
+/// Live statistics of running drones in the terminal, until `q`, `Esc` or `Ctrl-C` is pressed.
+///
+/// One row per drone with the packets it received, forwarded and dropped, its queue depth and pdr, refreshed
+/// every `refresh`. The selected drone can be crashed, paused and resumed, and its pdr changed.
+pub fn monitor(handles: &[DroneHandle], refresh: Duration) -> io::Result<()> {
+    let mut screen = Screen::enter()?;
+    let mut state = MonitorState {
+        selected: 0,
+        paused: HashSet::new(),
+        crashed: HashSet::new(),
+        previous: handles.iter().map(|handle| forwarded(&handle.stats())).collect(),
+        sampled_at: Instant::now(),
+        rates: vec![0.0; handles.len()],
+    };
+    loop {
+        let rows: Vec<DroneStats> = handles.iter().map(DroneHandle::stats).collect();
+        state.sample(&rows);
+        screen.draw(handles, &rows, &state)?;
+        if !event::poll(refresh)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let quit = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+        if quit || key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
+            return Ok(());
+        }
+        if handles.is_empty() {
+            continue;
+        }
+        match key.code {
+            KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+            KeyCode::Down => state.selected = (state.selected + 1).min(handles.len() - 1),
+            KeyCode::Char('c') => {
+                let handle = &handles[state.selected];
+                handle.crash();
+                state.crashed.insert(handle.id());
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                let pdr = rows[state.selected].pdr;
+                handles[state.selected].set_pdr((pdr + PDR_STEP).min(1.0));
+            }
+            KeyCode::Char('-') => {
+                let pdr = rows[state.selected].pdr;
+                handles[state.selected].set_pdr((pdr - PDR_STEP).max(0.0));
+            }
+            KeyCode::Char('p') => {
+                let handle = &handles[state.selected];
+                if state.paused.remove(&handle.id()) {
+                    handle.resume();
+                } else {
+                    handle.pause();
+                    state.paused.insert(handle.id());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct MonitorState {
+    selected: usize,
+    paused: HashSet<NodeId>,
+    crashed: HashSet<NodeId>,
+    // packets forwarded at the last sample, to compute the rates
+    previous: Vec<u64>,
+    sampled_at: Instant,
+    rates: Vec<f64>,
+}
+
+impl MonitorState {
+    fn sample(&mut self, rows: &[DroneStats]) {
+        let elapsed = self.sampled_at.elapsed().as_secs_f64();
+        if elapsed < 1.0 {
+            return;
+        }
+        for (i, stats) in rows.iter().enumerate() {
+            let forwarded = forwarded(stats);
+            // the counters go back to zero on reset
+            self.rates[i] = forwarded.saturating_sub(self.previous[i]) as f64 / elapsed;
+            self.previous[i] = forwarded;
+        }
+        self.sampled_at = Instant::now();
+    }
+
+    fn status(&self, handle: &DroneHandle) -> &'static str {
+        if handle.is_finished() {
+            "stopped"
+        } else if self.crashed.contains(&handle.id()) {
+            "crashing"
+        } else if self.paused.contains(&handle.id()) {
+            "paused"
+        } else {
+            "running"
+        }
+    }
+}
+
+fn forwarded(stats: &DroneStats) -> u64 {
+    stats.neighbors.values().map(|neighbor| neighbor.packets_forwarded).sum()
+}
+
+// raw mode and the alternate screen, left on drop whatever happened
+struct Screen {
+    stdout: Stdout,
+}
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        if let Err(e) = execute!(stdout, EnterAlternateScreen, cursor::Hide) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e);
+        }
+        Ok(Self { stdout })
+    }
+
+    fn draw(&mut self, handles: &[DroneHandle], rows: &[DroneStats], state: &MonitorState) -> io::Result<()> {
+        queue!(self.stdout, Clear(ClearType::All), cursor::MoveTo(0, 0), Print(HELP))?;
+        let header = format!(
+            "{:>6} {:>9} {:>10} {:>8} {:>9} {:>8} {:>6} {:>5}  {}",
+            "drone", "received", "forwarded", "fwd/s", "dropped", "filtered", "queue", "pdr", "status"
+        );
+        queue!(self.stdout, cursor::MoveTo(0, 2), SetAttribute(Attribute::Bold), Print(header), SetAttribute(Attribute::Reset))?;
+        for (i, (handle, stats)) in handles.iter().zip(rows).enumerate() {
+            let row = format!(
+                "{:>6} {:>9} {:>10} {:>8.1} {:>9} {:>8} {:>6} {:>5.2}  {}",
+                handle.id(),
+                stats.packets_received,
+                forwarded(stats),
+                state.rates[i],
+                stats.fragments_dropped + stats.control_packets_dropped,
+                stats.packets_filtered,
+                stats.queue_depth,
+                stats.pdr,
+                state.status(handle),
+            );
+            queue!(self.stdout, cursor::MoveTo(0, 3 + i as u16))?;
+            if i == state.selected {
+                queue!(self.stdout, SetAttribute(Attribute::Reverse), Print(row), SetAttribute(Attribute::Reset))?;
+            } else {
+                queue!(self.stdout, Print(row))?;
+            }
+        }
+        self.stdout.flush()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.stdout, cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,59 @@
+//! Building the Nacks a drone sends back, usable by any wg_2024 drone.
+
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Nack, NackType, Packet};
+
+/// Route from `hops[hop_index]` back to the first hop, in a single allocation. The hops after
+/// `hop_index` are left out, `hop_index` of the result points to the node sending it.
+///
+/// `None` if there's no way back: `hop_index` is 0 (the first hop has no one to send it to) or past the end.
+pub fn return_route(header: &SourceRoutingHeader, hop_index: usize) -> Option<SourceRoutingHeader> {
+    if hop_index == 0 || hop_index >= header.hops.len() {
+        return None;
+    }
+    Some(SourceRoutingHeader { hop_index: 0, hops: header.hops[..=hop_index].iter().rev().copied().collect() })
+}
+
+/// Nack of `packet` sent by the node `packet.routing_header.hop_index` points to, ready to be forwarded.
+/// `None` if its route can't be followed back, see `return_route`.
+pub fn for_packet(packet: &Packet, nack_type: NackType) -> Option<Packet> {
+    from_hop(packet, packet.routing_header.hop_index, nack_type)
+}
+
+/// Like `for_packet`, sent by `hops[hop_index]` instead, for a packet whose header already points further.
+pub fn from_hop(packet: &Packet, hop_index: usize, nack_type: NackType) -> Option<Packet> {
+    let route = return_route(&packet.routing_header, hop_index)?;
+    Some(Packet::new_nack(route, packet.session_id, Nack { fragment_index: packet.get_fragment_index(), nack_type }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::return_route;
+    use wg_2024::network::SourceRoutingHeader;
+
+    #[test]
+    fn return_route_reverses_the_hops_travelled() {
+        let header = SourceRoutingHeader::new(vec![10, 1, 2, 3, 20], 2);
+        assert_eq!(return_route(&header, 2), Some(SourceRoutingHeader::new(vec![2, 1, 10], 0)));
+        assert_eq!(return_route(&header, 4), Some(SourceRoutingHeader::new(vec![20, 3, 2, 1, 10], 0)));
+    }
+
+    #[test]
+    fn return_route_from_the_second_hop_is_one_hop_back() {
+        let header = SourceRoutingHeader::new(vec![10, 1, 20], 1);
+        assert_eq!(return_route(&header, 1), Some(SourceRoutingHeader::new(vec![1, 10], 0)));
+    }
+
+    #[test]
+    fn return_route_from_the_first_hop_goes_nowhere() {
+        let header = SourceRoutingHeader::new(vec![10, 1, 20], 0);
+        assert_eq!(return_route(&header, 0), None);
+    }
+
+    #[test]
+    fn return_route_past_the_end_goes_nowhere() {
+        let header = SourceRoutingHeader::new(vec![10, 1, 20], 1);
+        assert_eq!(return_route(&header, 3), None);
+        assert_eq!(return_route(&SourceRoutingHeader::new(Vec::new(), 0), 0), None);
+    }
+}
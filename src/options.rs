@@ -0,0 +1,493 @@
+use std::fmt;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::link::{LinkDirection, LinkProfile};
+
+/// How the drone answers a FloodRequest it has already seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DuplicateFloodPolicy {
+    /// Answer every duplicate with a FloodResponse (spec behaviour).
+    #[default]
+    Respond,
+    /// Silently drop duplicates.
+    Drop,
+    /// Answer only the first duplicate of each (flood_id, initiator_id) pair.
+    RespondOncePerInitiator,
+}
+
+/// What a crashing drone does with a FloodRequest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CrashFloodPolicy {
+    /// Lose it (spec behaviour).
+    #[default]
+    Drop,
+    /// Answer with a FloodResponse, as if the drone had no other neighbor.
+    Respond,
+    /// Handle it like a running drone would.
+    Forward,
+}
+
+/// What a paused drone does with the packets sent to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PausePolicy {
+    /// Leave every packet in the channel until the drone is resumed.
+    #[default]
+    Buffer,
+    /// Answer MsgFragments with a Nack(ErrorInRouting) and keep the other packets until the drone is resumed,
+    /// up to `DroneOptions::pause_backlog_capacity`.
+    Nack,
+}
+
+/// What the drone does with a packet when the channel of the next hop is full (bounded channels only).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OverflowPolicy {
+    /// Wait for room in the channel (spec behaviour).
+    #[default]
+    Block,
+    /// Answer MsgFragments with a Nack(Dropped), drop the other packets.
+    NackDropped,
+    /// Send Acks, Nacks and FloodResponses to the controller as ControllerShortcut,
+    /// answer MsgFragments with a Nack(Dropped) and drop FloodRequests.
+    Shortcut,
+}
+
+/// What the drone does with an event when the simulation controller's channel is full (bounded channels only).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EventOverflowPolicy {
+    /// Wait for room in the channel.
+    #[default]
+    Block,
+    /// Keep up to this many events and send them, in order, once there's room again.
+    Buffer(usize),
+    /// Drop the event.
+    Drop,
+}
+
+/// What the drone does with a packet whose current hop is another node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UnexpectedRecipientPolicy {
+    /// Answer with a Nack(UnexpectedRecipient) (spec behaviour).
+    #[default]
+    Nack,
+    /// Lose it without telling anyone.
+    Drop,
+    /// Handle it as if the route were right: from our position in the route if we're in it,
+    /// with our id in place of the current hop otherwise.
+    FixAndForward,
+}
+
+/// What the drone does with a packet whose routing header it can't use, like a `hop_index` past the end
+/// of the route. It's always reported as an `AuxEventKind::Diagnostic`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BrokenHeaderPolicy {
+    /// Lose it.
+    #[default]
+    Drop,
+    /// Send Acks, Nacks and FloodResponses to the controller as ControllerShortcut, so that they can still
+    /// reach their destination (the last hop), lose the other packets.
+    Shortcut,
+}
+
+/// Bound on the `ControllerShortcut`s of each (next hop, session), so that a dead neighbor doesn't flood the
+/// controller. The ones over `burst` in a window are lost and summed up in `AuxEventKind::ShortcutsSuppressed`,
+/// a window that lost some is followed by one twice as long, up to `max_window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShortcutRateLimit {
+    pub window: Duration,
+    pub burst: u32,
+    pub max_window: Duration,
+}
+
+impl Default for ShortcutRateLimit {
+    fn default() -> Self {
+        Self { window: Duration::from_secs(1), burst: 10, max_window: Duration::from_secs(30) }
+    }
+}
+
+/// What the drone does when the channel of the simulation controller disconnects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ControllerLossPolicy {
+    /// Keep forwarding packets without a controller.
+    #[default]
+    DataPlaneOnly,
+    /// Start crashing. With nobody left to remove the senders, the crash completes
+    /// and the drone stops once every neighbor dropped its sender to us.
+    Crash,
+}
+
+/// Order in which the drone handles waiting commands and packets.
+/// Commands from the DroneHandle count as controller commands. Doesn't apply to `spawn_split`,
+/// which handles them on separate threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Fairness {
+    /// Commands always go first, a flood of commands delays every packet.
+    #[default]
+    Biased,
+    /// After every command, a waiting packet goes before the next command.
+    RoundRobin,
+    /// After every command, up to this many waiting packets go before the next command.
+    PacketsPerCommandCheck(u32),
+}
+
+/// Energy budget of the drone, when it runs out the drone starts crashing on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EnergyModel {
+    pub budget: u64,
+    /// Cost of handling a received packet.
+    pub per_packet: u64,
+    /// Cost of every packet sent to a neighbor.
+    pub per_forward: u64,
+}
+
+/// Opt-in adversarial behaviour, meant to test how clients and servers cope with a hostile drone.
+/// Every enabled misbehaviour happens with `probability` on each packet it applies to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChaosOptions {
+    pub probability: f64,
+    /// Overwrite a node id in the path trace of forwarded FloodRequests.
+    pub corrupt_path_traces: bool,
+    /// Swap two hops of the route after the next hop.
+    pub reorder_hops: bool,
+    /// Hold Acks back for `ack_delay`.
+    pub delay_acks: bool,
+    pub ack_delay: Duration,
+    /// Send our Nacks with a wrong NackType.
+    pub misreport_nacks: bool,
+}
+
+/// Fault mode that occasionally holds a fragment back and forwards it after a few later ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReorderOptions {
+    pub probability: f64,
+    /// A held fragment is overtaken by at most this many fragments.
+    pub max_displacement: u32,
+}
+
+/// Experimental link-layer reliability: a forwarded MsgFragment is kept until its Ack (or a Nack) comes back
+/// through the drone, and sent again to the next hop if neither came within `ack_timeout`.
+/// The destination may get the same fragment more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HopReliability {
+    /// Time the Ack has to come back, from the whole rest of the route and back.
+    pub ack_timeout: Duration,
+    /// The fragment is forgotten after being sent again this many times.
+    pub max_retransmissions: u32,
+}
+
+impl Default for HopReliability {
+    fn default() -> Self {
+        Self { ack_timeout: Duration::from_millis(200), max_retransmissions: 3 }
+    }
+}
+
+/// How the drone decides which fragments to drop.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DropMode {
+    /// Drop each fragment with probability pdr (spec behaviour).
+    #[default]
+    Random,
+    /// Drop exactly ⌊pdr·window⌋ of every `window` fragments, spread evenly in a fixed pattern.
+    ExactRate { window: u32 },
+    /// Drop the n-th, 2n-th, ... fragment received, the pdr is ignored.
+    EveryNth(u64),
+    /// Drop the first fragment with one of these `fragment_index`es in each session, the pdr is ignored.
+    /// Retransmissions go through.
+    FragmentIndices(Vec<u64>),
+}
+
+/// Packet types the pdr applies to. Only MsgFragments by default (spec behaviour),
+/// the other types are meant for testing and are dropped without any Nack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DroppablePackets {
+    pub fragments: bool,
+    pub acks: bool,
+    pub nacks: bool,
+    pub flood_requests: bool,
+    pub flood_responses: bool,
+}
+
+impl Default for DroppablePackets {
+    fn default() -> Self {
+        Self { fragments: true, acks: false, nacks: false, flood_requests: false, flood_responses: false }
+    }
+}
+
+/// Extra knobs that don't fit in the wg_2024 `Drone::new` signature.
+/// The defaults match the protocol specification.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct DroneOptions {
+    pub duplicate_flood_policy: DuplicateFloodPolicy,
+    /// Floods remembered to recognize duplicates, by flood id and initiator. The oldest are forgotten first.
+    pub flood_dedup_capacity: usize,
+    /// Floods kept in `DroneStats::floods`.
+    pub flood_log_capacity: usize,
+    /// Ignore `AddSender` commands for new neighbors past this many neighbors (`AuxEventKind::SenderRefused`).
+    pub max_neighbors: Option<usize>,
+    pub crash_flood_policy: CrashFloodPolicy,
+    /// A crashing drone handles the packets sent to it until its senders are removed.
+    /// If that takes longer than this, the crash completes anyway.
+    pub crash_drain_timeout: Option<Duration>,
+    /// Also answer packets whose remaining route loops with a Nack(ErrorInRouting).
+    /// Loops are always reported as a diagnostic.
+    pub nack_routing_loops: bool,
+    pub unexpected_recipient_policy: UnexpectedRecipientPolicy,
+    pub broken_header_policy: BrokenHeaderPolicy,
+    /// Reject MsgFragments with an impossible length or fragment index with a Nack(Dropped).
+    pub validate_fragments: bool,
+    /// Reject MsgFragments whose payload doesn't match their checksum (see `checksum`) with a Nack(Dropped).
+    /// Only for networks whose clients and servers seal every fragment.
+    pub verify_checksums: bool,
+    /// Don't repeat a Nack with the same session, fragment index and type within this window.
+    pub nack_dedup_window: Option<Duration>,
+    /// Per-session statistics are forgotten after a session has been idle this long.
+    pub session_idle_timeout: Duration,
+    /// Sessions remembered at most, the least recently seen are forgotten first.
+    pub session_capacity: usize,
+    /// Zero the statistics at this interval after the drone started.
+    pub stats_reset_interval: Option<Duration>,
+    pub pause_policy: PausePolicy,
+    /// Packets a drone paused with `PausePolicy::Nack` keeps at most. The next ones go through
+    /// `overflow_policy`: left in the channel with `Block`, dropped or shortcut otherwise.
+    pub pause_backlog_capacity: usize,
+    pub energy: Option<EnergyModel>,
+    pub chaos: Option<ChaosOptions>,
+    /// Probability of sending a forwarded MsgFragment twice to the next hop.
+    pub duplicate_fragment_probability: f64,
+    pub reorder: Option<ReorderOptions>,
+    /// Probability of flipping one payload bit of a forwarded MsgFragment.
+    pub corrupt_payload_probability: f64,
+    pub drop_mode: DropMode,
+    /// Send forwarded MsgFragments again until they're Acked (experimental).
+    pub hop_reliability: Option<HopReliability>,
+    /// Profile of the links to the neighbors without one of their own (see `with_link_profile`).
+    pub default_link: Option<LinkProfile>,
+    /// Send `AuxEventKind::FragmentDropped` with the reason of every dropped fragment.
+    pub audit_drops: bool,
+    /// Packet types the pdr applies to.
+    pub droppable: DroppablePackets,
+    pub overflow_policy: OverflowPolicy,
+    /// How long to wait for room in a full channel before applying `overflow_policy`.
+    pub overflow_timeout: Duration,
+    /// Give each neighbor an outgoing queue of this many packets, sent by a thread of its own, so a
+    /// slow neighbor only holds up its own packets. A full queue is a full channel for `overflow_policy`.
+    /// Packets are reported sent once queued.
+    pub outgoing_queue: Option<usize>,
+    /// Send `AuxEventKind::Stalled` when handling a single input takes longer than this.
+    /// Needs an auxiliary sender, runs one more thread per drone.
+    pub watchdog_deadline: Option<Duration>,
+    /// Check at this interval that the neighbors' channels are still connected and send
+    /// `AuxEventKind::NeighborDisconnected`/`NeighborReconnected`. Crossbeam and std channels can't
+    /// tell before a send fails, only tokio channels are really probed.
+    pub neighbor_probe_interval: Option<Duration>,
+    /// Send `AuxEventKind::Congested` when a queue holds more packets than this, `Drained` once it's down to half.
+    pub congestion_threshold: Option<usize>,
+    /// Send `AuxEventKind::MemoryBudgetExceeded` when `DroneStats::memory` adds up to more bytes than this.
+    pub memory_budget: Option<usize>,
+    /// Names of the `AuxEventKind`s to send (see `AuxEventKind::name`), all of them if `None`.
+    /// Defaults to the `RWA_EVENT_FILTER` environment variable.
+    pub aux_event_filter: Option<Vec<String>>,
+    pub controller_loss_policy: ControllerLossPolicy,
+    /// Events that can't be sent are counted in `DroneStats::events_lost`.
+    pub event_overflow_policy: EventOverflowPolicy,
+    pub shortcut_rate_limit: Option<ShortcutRateLimit>,
+    pub fairness: Fairness,
+    /// Report a packet that makes the drone panic with `AuxEventKind::Panicked` and keep running.
+    pub catch_panics: bool,
+    /// Seed of the drone's random decisions (drops, jitter, chaos), derived from `simulation_seed` if `None`.
+    pub seed: Option<u64>,
+    /// Seed of the whole simulation run: every drone derives its own from it and its id (see `derive_seed`), so
+    /// one number reproduces the run. `RWA_SEED` if `None`, random if neither is set.
+    pub simulation_seed: Option<u64>,
+}
+
+impl Default for DroneOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_flood_policy: DuplicateFloodPolicy::default(),
+            flood_dedup_capacity: 4096,
+            flood_log_capacity: 64,
+            max_neighbors: None,
+            crash_flood_policy: CrashFloodPolicy::default(),
+            crash_drain_timeout: None,
+            nack_routing_loops: false,
+            unexpected_recipient_policy: UnexpectedRecipientPolicy::default(),
+            broken_header_policy: BrokenHeaderPolicy::default(),
+            validate_fragments: false,
+            verify_checksums: false,
+            nack_dedup_window: None,
+            session_idle_timeout: Duration::from_secs(60),
+            session_capacity: 4096,
+            stats_reset_interval: None,
+            pause_policy: PausePolicy::default(),
+            pause_backlog_capacity: 1024,
+            energy: None,
+            chaos: None,
+            duplicate_fragment_probability: 0.0,
+            reorder: None,
+            corrupt_payload_probability: 0.0,
+            drop_mode: DropMode::default(),
+            hop_reliability: None,
+            default_link: None,
+            audit_drops: false,
+            droppable: DroppablePackets::default(),
+            overflow_policy: OverflowPolicy::default(),
+            overflow_timeout: Duration::ZERO,
+            outgoing_queue: None,
+            watchdog_deadline: None,
+            neighbor_probe_interval: None,
+            congestion_threshold: None,
+            memory_budget: None,
+            aux_event_filter: None,
+            controller_loss_policy: ControllerLossPolicy::default(),
+            event_overflow_policy: EventOverflowPolicy::default(),
+            shortcut_rate_limit: None,
+            fairness: Fairness::default(),
+            catch_panics: false,
+            seed: None,
+            simulation_seed: None,
+        }
+    }
+}
+
+impl DroneOptions {
+    /// Names accepted by `preset`.
+    pub const PRESETS: [&'static str; 4] = ["reliable", "lossy", "slow", "chaotic"];
+
+    /// Options of a typical kind of intermediary, `None` if `name` isn't one of `PRESETS`.
+    /// The pdr given to `new` still applies on top of them.
+    ///
+    /// - `reliable`: the defaults, plus fragment validation and `catch_panics`.
+    /// - `lossy`: a pdr of 0.2 more towards every neighbor, fragments are Nacked as usual.
+    /// - `slow`: 50ms ± 25ms of latency and 100 packets per second on every link.
+    /// - `chaotic`: duplicates, reordering, corrupted payloads and path traces, delayed Acks and jittery links.
+    pub fn preset(name: &str) -> Option<Self> {
+        let options = match name {
+            "reliable" => Self { validate_fragments: true, catch_panics: true, ..Self::default() },
+            "lossy" => Self {
+                default_link: Some(LinkProfile {
+                    ingress: LinkDirection::default(),
+                    egress: LinkDirection { pdr: 0.2, ..LinkDirection::default() },
+                    queue_while_down: 0,
+                }),
+                ..Self::default()
+            },
+            "slow" => Self {
+                default_link: Some(LinkProfile {
+                    ingress: LinkDirection::default(),
+                    egress: LinkDirection {
+                        delay: Duration::from_millis(50),
+                        jitter: Duration::from_millis(25),
+                        bandwidth: Some(100),
+                        ..LinkDirection::default()
+                    },
+                    queue_while_down: 0,
+                }),
+                ..Self::default()
+            },
+            "chaotic" => Self {
+                chaos: Some(ChaosOptions {
+                    probability: 0.05,
+                    corrupt_path_traces: true,
+                    reorder_hops: false,
+                    delay_acks: true,
+                    ack_delay: Duration::from_millis(100),
+                    misreport_nacks: false,
+                }),
+                duplicate_fragment_probability: 0.05,
+                reorder: Some(ReorderOptions { probability: 0.1, max_displacement: 3 }),
+                corrupt_payload_probability: 0.01,
+                default_link: Some(LinkProfile::symmetric(LinkDirection {
+                    delay: Duration::from_millis(5),
+                    jitter: Duration::from_millis(20),
+                    ..LinkDirection::default()
+                })),
+                catch_panics: true,
+                ..Self::default()
+            },
+            _ => return None,
+        };
+        Some(options)
+    }
+
+    /// Check the options that can't work as given: probabilities outside 0..=1, empty tables and queues,
+    /// zero-length windows and timeouts. `DroneProfile`s are checked when loaded.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let invalid = |option, reason| Err(OptionsError { option, reason });
+        let probabilities = [
+            ("duplicate_fragment_probability", Some(self.duplicate_fragment_probability)),
+            ("corrupt_payload_probability", Some(self.corrupt_payload_probability)),
+            ("chaos.probability", self.chaos.map(|chaos| chaos.probability)),
+            ("reorder.probability", self.reorder.map(|reorder| reorder.probability)),
+            ("default_link.ingress.pdr", self.default_link.map(|link| f64::from(link.ingress.pdr))),
+            ("default_link.egress.pdr", self.default_link.map(|link| f64::from(link.egress.pdr))),
+        ];
+        for (option, probability) in probabilities {
+            if probability.is_some_and(|probability| !(0.0..=1.0).contains(&probability)) {
+                return invalid(option, "must be between 0 and 1");
+            }
+        }
+        if self.flood_dedup_capacity == 0 {
+            return invalid("flood_dedup_capacity", "must be at least 1");
+        }
+        if self.session_capacity == 0 {
+            return invalid("session_capacity", "must be at least 1");
+        }
+        if self.outgoing_queue == Some(0) {
+            return invalid("outgoing_queue", "must be at least 1");
+        }
+        if self.event_overflow_policy == EventOverflowPolicy::Buffer(0) {
+            return invalid("event_overflow_policy", "must buffer at least 1 event, or use `Drop`");
+        }
+        match self.drop_mode {
+            DropMode::ExactRate { window: 0 } => return invalid("drop_mode", "the window must be at least 1 fragment"),
+            DropMode::EveryNth(0) => return invalid("drop_mode", "must drop every n-th fragment for n of at least 1"),
+            _ => {}
+        }
+        if self.hop_reliability.is_some_and(|reliability| reliability.ack_timeout.is_zero()) {
+            return invalid("hop_reliability.ack_timeout", "must not be zero");
+        }
+        if self.shortcut_rate_limit.is_some_and(|limit| limit.window.is_zero()) {
+            return invalid("shortcut_rate_limit.window", "must not be zero");
+        }
+        if self.watchdog_deadline.is_some_and(|deadline| deadline.is_zero()) {
+            return invalid("watchdog_deadline", "must not be zero");
+        }
+        Ok(())
+    }
+}
+
+/// An option `DroneOptions::validate` rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionsError {
+    /// Path of the field, e.g. `reorder.probability`.
+    pub option: &'static str,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.option, self.reason)
+    }
+}
+
+impl std::error::Error for OptionsError {}
@@ -0,0 +1,272 @@
+//! OpenTelemetry traces (`otel` feature): a span for every packet a drone handles, from its receipt to the end of its
+//! handling, exported with OTLP over HTTP (JSON) to a collector, Jaeger included.
+//!
+//! The trace id of a span holds the session id of the packet, so a fragment, its Acks and Nacks and their journey
+//! through every drone end up in the same trace. Each drone is a service of its own, `rwa-drone-<id>`.
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+use crate::seed;
+
+// spans are sent at least this often, and as soon as there are BATCH_SIZE of them
+const BATCH_INTERVAL: Duration = Duration::from_millis(500);
+const BATCH_SIZE: usize = 512;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+// SPAN_KIND_INTERNAL
+const SPAN_KIND: u8 = 1;
+// high half of the trace ids, the low half is the session id: session 0 can't give the invalid trace id 0
+const TRACE_ID_HIGH: u64 = 1;
+
+static NEXT_SPAN: AtomicU64 = AtomicU64::new(1);
+
+// A finished span, waiting to be exported.
+struct Span {
+    drone_id: NodeId,
+    session_id: u64,
+    span_id: u64,
+    name: &'static str,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, Value)>,
+}
+
+enum Value {
+    Int(u64),
+    Str(String),
+}
+
+enum Message {
+    Span(Span),
+    Stop,
+}
+
+/// Sends the spans of the drones given to `with_otlp_exporter` to an OTLP/HTTP collector, in batches, from a
+/// thread of its own. The spans still waiting are sent when it's dropped.
+///
+/// A collector that can't be reached loses the batch, the drones never wait for it.
+pub struct OtlpExporter {
+    send: Sender<Message>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OtlpExporter {
+    /// Export to the collector at `endpoint`, `host:port` of its OTLP/HTTP receiver (Jaeger: `localhost:4318`).
+    pub fn start(endpoint: impl Into<String>) -> io::Result<Self> {
+        let endpoint = endpoint.into();
+        let (send, recv) = unbounded();
+        let thread = thread::Builder::new().name("rwa-otlp".to_string()).spawn(move || export(&endpoint, &recv))?;
+        Ok(Self { send, thread: Some(thread) })
+    }
+
+    pub(crate) fn tracer(&self) -> Tracer {
+        Tracer { send: self.send.clone(), open: None }
+    }
+}
+
+impl Drop for OtlpExporter {
+    fn drop(&mut self) {
+        let _ = self.send.send(Message::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// The span of the packet being handled.
+struct Open {
+    packet: Packet,
+    start: SystemTime,
+    events: Vec<&'static str>,
+}
+
+// A drone's end of the exporter.
+pub(crate) struct Tracer {
+    send: Sender<Message>,
+    open: Option<Open>,
+}
+
+impl Tracer {
+    pub(crate) fn begin(&mut self, packet: &Packet) {
+        self.open = Some(Open { packet: packet.clone(), start: SystemTime::now(), events: Vec::new() });
+    }
+
+    pub(crate) fn observe(&mut self, event: &DroneEvent) {
+        let Some(open) = self.open.as_mut() else {
+            return;
+        };
+        open.events.push(match event {
+            DroneEvent::PacketSent(packet) => match packet.pack_type {
+                PacketType::Nack(_) if !matches!(open.packet.pack_type, PacketType::Nack(_)) => "nacked",
+                PacketType::FloodResponse(_) if matches!(open.packet.pack_type, PacketType::FloodRequest(_)) => "answered",
+                _ => "sent",
+            },
+            DroneEvent::PacketDropped(_) => "dropped",
+            DroneEvent::ControllerShortcut(_) => "shortcut",
+        });
+    }
+
+    pub(crate) fn finish(&mut self, drone_id: NodeId) {
+        let Some(Open { packet, start, events }) = self.open.take() else {
+            return;
+        };
+        let header = &packet.routing_header;
+        let mut attributes = vec![
+            ("drone.id", Value::Int(drone_id.into())),
+            ("packet.session_id", Value::Int(packet.session_id)),
+            ("route.hops", Value::Str(format!("{:?}", header.hops))),
+            ("route.hop_index", Value::Int(header.hop_index as u64)),
+            ("outcome", Value::Str(if events.is_empty() { "none".to_string() } else { events.join(",") })),
+        ];
+        match &packet.pack_type {
+            PacketType::MsgFragment(fragment) => attributes.push(("fragment.index", Value::Int(fragment.fragment_index))),
+            PacketType::Ack(ack) => attributes.push(("fragment.index", Value::Int(ack.fragment_index))),
+            PacketType::Nack(nack) => {
+                attributes.push(("fragment.index", Value::Int(nack.fragment_index)));
+                attributes.push(("nack.type", Value::Str(format!("{:?}", nack.nack_type))));
+            }
+            PacketType::FloodRequest(flood_request) => {
+                attributes.push(("flood.id", Value::Int(flood_request.flood_id)));
+                attributes.push(("flood.initiator", Value::Int(flood_request.initiator_id.into())));
+            }
+            PacketType::FloodResponse(flood_response) => attributes.push(("flood.id", Value::Int(flood_response.flood_id))),
+        }
+        let span = Span {
+            drone_id,
+            session_id: packet.session_id,
+            span_id: span_id(drone_id),
+            name: packet_type_name(&packet.pack_type),
+            start,
+            end: SystemTime::now(),
+            attributes,
+        };
+        // the exporter is gone, nobody is listening anymore
+        let _ = self.send.send(Message::Span(span));
+    }
+}
+
+// unique in the process, never 0 (invalid in OTLP)
+fn span_id(drone_id: NodeId) -> u64 {
+    seed::derive_seed(NEXT_SPAN.fetch_add(1, Ordering::Relaxed), drone_id).max(1)
+}
+
+fn packet_type_name(pack_type: &PacketType) -> &'static str {
+    match pack_type {
+        PacketType::MsgFragment(_) => "MsgFragment",
+        PacketType::Ack(_) => "Ack",
+        PacketType::Nack(_) => "Nack",
+        PacketType::FloodRequest(_) => "FloodRequest",
+        PacketType::FloodResponse(_) => "FloodResponse",
+    }
+}
+
+fn export(endpoint: &str, recv: &Receiver<Message>) {
+    let mut batch = Vec::new();
+    let mut last_post = Instant::now();
+    loop {
+        let stop = match recv.recv_timeout(BATCH_INTERVAL) {
+            Ok(Message::Span(span)) => {
+                batch.push(span);
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => true,
+        };
+        if !batch.is_empty() && (stop || batch.len() >= BATCH_SIZE || last_post.elapsed() >= BATCH_INTERVAL) {
+            // the batch is lost if the collector can't take it
+            let _ = post(endpoint, &to_json(&batch));
+            batch.clear();
+            last_post = Instant::now();
+        }
+        if stop {
+            return;
+        }
+    }
+}
+
+fn post(endpoint: &str, body: &str) -> io::Result<()> {
+    let address = endpoint.trim_start_matches("http://").trim_end_matches('/');
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    write!(
+        stream,
+        "POST /v1/traces HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        address,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(io::Error::other(format!("collector answered {:?}", status_line.trim()))),
+    }
+}
+
+// ExportTraceServiceRequest in the JSON encoding of OTLP, one resource per drone
+fn to_json(spans: &[Span]) -> String {
+    let mut by_drone: BTreeMap<NodeId, Vec<&Span>> = BTreeMap::new();
+    for span in spans {
+        by_drone.entry(span.drone_id).or_default().push(span);
+    }
+    let mut json = String::from(r#"{"resourceSpans":["#);
+    for (i, (drone_id, spans)) in by_drone.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let _ = write!(
+            json,
+            r#"{{"resource":{{"attributes":[{{"key":"service.name","value":{{"stringValue":"rwa-drone-{}"}}}}]}},"scopeSpans":[{{"scope":{{"name":"rustaceans_wit_attitudes"}},"spans":["#,
+            drone_id
+        );
+        for (j, span) in spans.iter().enumerate() {
+            if j > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                r#"{{"traceId":"{:016x}{:016x}","spanId":"{:016x}","name":"{}","kind":{},"startTimeUnixNano":"{}","endTimeUnixNano":"{}","attributes":["#,
+                TRACE_ID_HIGH,
+                span.session_id,
+                span.span_id,
+                span.name,
+                SPAN_KIND,
+                unix_nanos(span.start),
+                unix_nanos(span.end)
+            );
+            for (k, (key, value)) in span.attributes.iter().enumerate() {
+                if k > 0 {
+                    json.push(',');
+                }
+                let _ = match value {
+                    Value::Int(int) => write!(json, r#"{{"key":"{}","value":{{"intValue":"{}"}}}}"#, key, int),
+                    Value::Str(string) => write!(json, r#"{{"key":"{}","value":{{"stringValue":"{}"}}}}"#, key, escape(string)),
+                };
+            }
+            json.push_str("]}");
+        }
+        json.push_str("]}]}");
+    }
+    json.push_str("]}");
+    json
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_nanos())
+}
+
+fn escape(string: &str) -> String {
+    string.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,84 @@
+use crossbeam_channel::{bounded, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+use crate::channel::{ChannelSender, SendFailure};
+
+// The outgoing queue of one neighbor, see DroneOptions::outgoing_queue. The drone only waits for
+// room in the queue, a thread of its own waits for the neighbor.
+pub(crate) struct OutgoingWorker {
+    queue: Sender<Packet>,
+    // cleared by the thread when the neighbor's receiver is gone
+    connected: Arc<AtomicBool>,
+}
+
+impl OutgoingWorker {
+    // The thread sends what's left in the queue and ends once the worker is dropped
+    // (sender removed or drone gone), or when the neighbor's receiver is.
+    // The sender is given back if the thread can't be started.
+    pub(crate) fn spawn(
+        id: NodeId,
+        node_id: NodeId,
+        capacity: usize,
+        sender: Box<dyn ChannelSender<Packet>>,
+    ) -> Result<Self, Box<dyn ChannelSender<Packet>>> {
+        let (queue, packets) = bounded::<Packet>(capacity.max(1));
+        let connected = Arc::new(AtomicBool::new(true));
+        let worker_connected = connected.clone();
+        // handed over once the thread runs, to keep it if it doesn't
+        let (handover_send, handover_recv) = bounded::<Box<dyn ChannelSender<Packet>>>(1);
+        let spawned = thread::Builder::new()
+            .name(format!("rwa-drone-{}-to-{}", id, node_id))
+            .spawn(move || {
+                let Ok(sender) = handover_recv.recv() else {
+                    return;
+                };
+                while let Ok(packet) = packets.recv() {
+                    if sender.send(packet).is_err() {
+                        worker_connected.store(false, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            });
+        if spawned.is_err() {
+            return Err(sender);
+        }
+        // the thread is waiting for it, there's room
+        let _ = handover_send.send(sender);
+        Ok(Self { queue, connected })
+    }
+
+    fn is_gone(&self) -> bool {
+        !self.connected.load(Ordering::Relaxed)
+    }
+}
+
+impl ChannelSender<Packet> for OutgoingWorker {
+    fn send(&self, value: Packet) -> Result<(), Packet> {
+        if self.is_gone() {
+            return Err(value);
+        }
+        ChannelSender::send(&self.queue, value)
+    }
+    fn send_timeout(&self, value: Packet, timeout: Duration) -> Result<(), SendFailure<Packet>> {
+        if self.is_gone() {
+            return Err(SendFailure::Disconnected(value));
+        }
+        ChannelSender::send_timeout(&self.queue, value, timeout)
+    }
+    fn queued(&self) -> Option<usize> {
+        Some(self.queue.len())
+    }
+    fn is_full(&self) -> Option<bool> {
+        Some(self.queue.is_full())
+    }
+    // only known once a packet couldn't be delivered
+    fn is_connected(&self) -> Option<bool> {
+        self.is_gone().then_some(false)
+    }
+}
@@ -0,0 +1,12 @@
+//! Everything a network initializer needs, under the names the course templates expect:
+//! `use rustaceans_wit_attitudes::prelude::*;` then `MyDrone::new(...)`.
+
+pub use wg_2024::drone::Drone;
+
+pub use crate::RustaceansWitAttitudesDrone;
+/// The name most initializers use for the drone of the group.
+pub use crate::RustaceansWitAttitudesDrone as MyDrone;
+#[cfg(feature = "full")]
+pub use crate::handle::{spawn, DroneHandle};
+#[cfg(feature = "full")]
+pub use crate::options::DroneOptions;
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+use crossbeam_channel::{bounded, RecvTimeoutError, Sender};
+use serde::Deserialize;
+use toml::{Table, Value};
+use wg_2024::network::NodeId;
+
+use crate::handle::HandleCommand;
+use crate::link::{LinkDirection, LinkProfile};
+use crate::options::DroneOptions;
+use crate::scenario::{file::StepFile, Scenario};
+
+/// Behaviour of the drones of a simulation, kept in a TOML file and looked up by drone id.
+/// `[default]` applies to every drone, `[drone.<id>]` overrides it key by key:
+///
+/// ```toml
+/// [default]
+/// pdr = 0.05
+/// channel_capacity = 64
+///
+/// [default.options]
+/// drop_mode = { ExactRate = { window = 20 } }
+/// congestion_threshold = 48
+///
+/// [drone.3]
+/// pdr = 0.3
+///
+/// [drone.3.links.7]
+/// queue_while_down = 16
+///
+/// [drone.3.links.7.egress]
+/// delay_ms = 20
+/// jitter_ms = 5
+/// bandwidth = 100
+///
+/// [[drone.3.step]]
+/// at_secs = 10
+/// action = "crash"
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Profiles {
+    pub default: DroneProfile,
+    /// Only the drones with a `[drone.<id>]` section, already merged with the default.
+    pub drones: HashMap<NodeId, DroneProfile>,
+}
+
+/// What a profile sets on a drone, applied with `RustaceansWitAttitudesDrone::with_profile`.
+#[derive(Debug, Clone, Default)]
+pub struct DroneProfile {
+    /// Replaces the pdr given to `new` if set.
+    pub pdr: Option<f32>,
+    pub options: DroneOptions,
+    pub links: HashMap<NodeId, LinkProfile>,
+    /// The drone doesn't create its channels: capacity for whoever does, unbounded if `None`.
+    pub channel_capacity: Option<usize>,
+    /// Fault schedule.
+    pub scenario: Scenario,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    /// A `[drone.<id>]` section whose id isn't a `NodeId`.
+    InvalidDroneId(String),
+    /// The profile of a drone, `None` for the default one, has an invalid value.
+    InvalidProfile { drone_id: Option<NodeId>, reason: String },
+}
+
+impl Profiles {
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, ProfileError> {
+        Self::from_toml_str(&fs::read_to_string(path).map_err(ProfileError::Io)?)
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self, ProfileError> {
+        let file: ProfilesFile = toml::from_str(s).map_err(ProfileError::Toml)?;
+        let default = DroneProfile::from_table(file.default.clone(), None)?;
+        let mut drones = HashMap::new();
+        for (id, table) in file.drone {
+            let drone_id: NodeId = id.parse().map_err(|_| ProfileError::InvalidDroneId(id.clone()))?;
+            let mut merged = file.default.clone();
+            merge(&mut merged, table);
+            drones.insert(drone_id, DroneProfile::from_table(merged, Some(drone_id))?);
+        }
+        Ok(Self { default, drones })
+    }
+
+    /// Profile of `drone_id`, the default one if the file has no section for it.
+    pub fn get(&self, drone_id: NodeId) -> &DroneProfile {
+        self.drones.get(&drone_id).unwrap_or(&self.default)
+    }
+}
+
+impl DroneProfile {
+    fn from_table(table: Table, drone_id: Option<NodeId>) -> Result<Self, ProfileError> {
+        let invalid = |reason: String| ProfileError::InvalidProfile { drone_id, reason };
+        let file: ProfileFile = table.try_into().map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+        if file.pdr.is_some_and(|pdr| !(0.0..=1.0).contains(&pdr)) {
+            return Err(invalid("`pdr` must be between 0 and 1".into()));
+        }
+        file.options.validate().map_err(|e| invalid(format!("options: {}", e)))?;
+        let mut links = HashMap::new();
+        for (id, link) in file.links {
+            let node_id: NodeId = id.parse().map_err(|_| invalid(format!("invalid link `{}`", id)))?;
+            links.insert(node_id, link.into_profile().map_err(|reason| invalid(format!("link {}: {}", node_id, reason)))?);
+        }
+        let steps = file
+            .step
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| step.into_step().map_err(|reason| invalid(format!("step {}: {}", index, reason))))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            pdr: file.pdr,
+            options: file.options,
+            links,
+            channel_capacity: file.channel_capacity,
+            scenario: Scenario { steps },
+        })
+    }
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Io(e) => write!(f, "can't read profiles: {}", e),
+            ProfileError::Toml(e) => write!(f, "invalid profile file: {}", e),
+            ProfileError::InvalidDroneId(id) => write!(f, "invalid drone id `{}`", id),
+            ProfileError::InvalidProfile { drone_id: Some(drone_id), reason } => {
+                write!(f, "invalid profile of drone {}: {}", drone_id, reason)
+            }
+            ProfileError::InvalidProfile { drone_id: None, reason } => write!(f, "invalid default profile: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+/// Watches a profile file for a drone, see `DroneHandle::watch_profile`. Dropping it stops the watch.
+#[derive(Debug)]
+pub struct ProfileWatcher {
+    _stop_send: Sender<()>,
+}
+
+pub(crate) fn watch(drone_id: NodeId, command_send: Sender<HandleCommand>, path: PathBuf, interval: Duration) -> io::Result<ProfileWatcher> {
+    let (stop_send, stop_recv) = bounded::<()>(0);
+    let mut modified = modified_at(&path);
+    thread::Builder::new().name(format!("rwa-profile-{}", drone_id)).spawn(move || loop {
+        match stop_recv.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => {}
+            _ => return,
+        }
+        // editors often remove the file before writing it again
+        let now = modified_at(&path);
+        if now.is_none() || now == modified {
+            continue;
+        }
+        modified = now;
+        let command = match Profiles::from_toml_file(&path) {
+            Ok(profiles) => HandleCommand::ReloadProfile(Box::new(profiles.get(drone_id).clone())),
+            Err(e) => HandleCommand::ProfileReloadFailed(e.to_string()),
+        };
+        if command_send.send(command).is_err() {
+            return;
+        }
+    })?;
+    Ok(ProfileWatcher { _stop_send: stop_send })
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// names of the options whose value differs, `*` if they can't be compared
+pub(crate) fn changed_options(old: &DroneOptions, new: &DroneOptions) -> Vec<String> {
+    // seeds past i64::MAX don't fit in TOML, and the seeds are never reloaded
+    let without_seed = |options: &DroneOptions| Table::try_from(DroneOptions { seed: None, simulation_seed: None, ..options.clone() });
+    let (Ok(old), Ok(new)) = (without_seed(old), without_seed(new)) else {
+        return vec!["*".to_string()];
+    };
+    let mut changed: Vec<String> = old.iter().filter(|(key, value)| new.get(*key) != Some(value)).map(|(key, _)| key.clone()).collect();
+    changed.extend(new.iter().filter(|(key, _)| old.get(*key).is_none()).map(|(key, _)| key.clone()));
+    changed.sort();
+    changed
+}
+
+// tables are merged key by key, anything else in `overlay` replaces the value in `base`
+fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// Flat representation of the TOML file, checked when converted into profiles.
+#[derive(Deserialize)]
+struct ProfilesFile {
+    #[serde(default)]
+    default: Table,
+    #[serde(default)]
+    drone: HashMap<String, Table>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    pdr: Option<f32>,
+    channel_capacity: Option<usize>,
+    #[serde(default)]
+    options: DroneOptions,
+    #[serde(default)]
+    links: HashMap<String, LinkFile>,
+    #[serde(default)]
+    step: Vec<StepFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LinkFile {
+    #[serde(default)]
+    ingress: DirectionFile,
+    #[serde(default)]
+    egress: DirectionFile,
+    #[serde(default)]
+    queue_while_down: usize,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct DirectionFile {
+    delay_ms: u64,
+    jitter_ms: u64,
+    bandwidth: Option<u32>,
+    pdr: f32,
+}
+
+impl LinkFile {
+    fn into_profile(self) -> Result<LinkProfile, String> {
+        Ok(LinkProfile {
+            ingress: self.ingress.into_direction()?,
+            egress: self.egress.into_direction()?,
+            queue_while_down: self.queue_while_down,
+        })
+    }
+}
+
+impl DirectionFile {
+    fn into_direction(self) -> Result<LinkDirection, String> {
+        if !(0.0..=1.0).contains(&self.pdr) {
+            return Err("`pdr` must be between 0 and 1".into());
+        }
+        Ok(LinkDirection {
+            delay: Duration::from_millis(self.delay_ms),
+            jitter: Duration::from_millis(self.jitter_ms),
+            bandwidth: self.bandwidth,
+            pdr: self.pdr,
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+use crate::drone::RustaceansWitAttitudesDrone;
+use crate::options::DroneOptions;
+
+/// The inputs a drone received while running, written by `RustaceansWitAttitudesDrone::with_recording`
+/// as JSON lines: the header first, then one entry per command or packet.
+///
+/// Only controller commands and packets are recorded, `DroneHandle` commands and scenarios are not.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub entries: Vec<RecordedEntry>,
+}
+
+/// State of the drone when it started running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub drone_id: NodeId,
+    pub pdr: f32,
+    pub neighbors: Vec<NodeId>,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    /// Time since the drone started running.
+    pub at: Duration,
+    pub input: RecordedInput,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordedInput {
+    Command(RecordedCommand),
+    Packet(Packet),
+}
+
+/// A `DroneCommand` without the channel of `AddSender`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RecordedCommand {
+    AddSender(NodeId),
+    RemoveSender(NodeId),
+    SetPacketDropRate(f32),
+    Crash,
+}
+
+/// What the drone produced during a replay, grouped by the input that caused it.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOutput {
+    pub steps: Vec<ReplayStep>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayStep {
+    pub input: RecordedInput,
+    pub events: Vec<DroneEvent>,
+    /// Packets sent to the neighbors, in order.
+    pub sent: Vec<(NodeId, Packet)>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    Json { line: usize, error: serde_json::Error },
+    MissingHeader,
+}
+
+impl Recording {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Self::from_reader(BufReader::new(File::open(path).map_err(RecordingError::Io)?))
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, RecordingError> {
+        let mut lines = reader.lines().enumerate().filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()));
+        let (index, header) = lines.next().ok_or(RecordingError::MissingHeader)?;
+        let header = parse_line(index + 1, header)?;
+        let entries = lines
+            .map(|(index, line)| parse_line(index + 1, line))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { header, entries })
+    }
+
+    /// Feed the recorded inputs to a new drone, in order and without waiting between them:
+    /// the drone runs on a `ManualClock` moved to the recorded time of each input.
+    /// `options` should match the recorded run, the seed is taken from the recording.
+    pub fn replay(&self, options: DroneOptions) -> ReplayOutput {
+        RustaceansWitAttitudesDrone::replay(self, options)
+    }
+}
+
+fn parse_line<T: for<'de> Deserialize<'de>>(line: usize, text: io::Result<String>) -> Result<T, RecordingError> {
+    let text = text.map_err(RecordingError::Io)?;
+    serde_json::from_str(&text).map_err(|error| RecordingError::Json { line, error })
+}
+
+impl ReplayOutput {
+    pub fn events(&self) -> impl Iterator<Item = &DroneEvent> {
+        self.steps.iter().flat_map(|step| &step.events)
+    }
+    pub fn sent(&self) -> impl Iterator<Item = &(NodeId, Packet)> {
+        self.steps.iter().flat_map(|step| &step.sent)
+    }
+}
+
+impl From<&DroneCommand> for RecordedCommand {
+    fn from(command: &DroneCommand) -> Self {
+        match command {
+            DroneCommand::AddSender(node_id, _) => RecordedCommand::AddSender(*node_id),
+            DroneCommand::RemoveSender(node_id) => RecordedCommand::RemoveSender(*node_id),
+            DroneCommand::SetPacketDropRate(pdr) => RecordedCommand::SetPacketDropRate(*pdr),
+            DroneCommand::Crash => RecordedCommand::Crash,
+        }
+    }
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(e) => write!(f, "can't read recording: {}", e),
+            RecordingError::Json { line, error } => write!(f, "invalid recording line {}: {}", line, error),
+            RecordingError::MissingHeader => write!(f, "the recording is empty"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+// Writes the recording as the drone runs, a line at a time so a crash loses nothing.
+pub(crate) struct Recorder {
+    writer: LineWriter<File>,
+}
+
+impl Recorder {
+    pub(crate) fn new(file: File) -> Self {
+        Self { writer: LineWriter::new(file) }
+    }
+
+    pub(crate) fn write(&mut self, line: &impl Serialize) {
+        // recording is a debugging aid, it never stops the drone
+        if serde_json::to_writer(&mut self.writer, line).is_ok() {
+            let _ = self.writer.write_all(b"\n");
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt;
+use std::time::Duration;
+#[cfg(feature = "toml")]
+use std::{fs, io, path::Path};
+
+use wg_2024::network::NodeId;
+
+/// A timed fault-injection script executed by the drone itself.
+///
+/// ```toml
+/// [[step]]
+/// after_packets = 500
+/// action = "set_pdr"
+/// pdr = 0.8
+///
+/// [[step]]
+/// at_secs = 10
+/// action = "crash"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scenario {
+    pub steps: Vec<ScenarioStep>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioStep {
+    pub trigger: ScenarioTrigger,
+    pub action: ScenarioAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScenarioTrigger {
+    /// Fires once the drone has received this many packets.
+    AfterPackets(u64),
+    /// Fires this long after the drone started running.
+    At(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScenarioAction {
+    SetPdr(f32),
+    Crash,
+    /// Stop using the link to a neighbor without removing its sender.
+    LinkDown(NodeId),
+    LinkUp(NodeId),
+    Pause,
+    Resume,
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    #[cfg(feature = "toml")]
+    Io(io::Error),
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    InvalidStep { index: usize, reason: String },
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn at(mut self, time: Duration, action: ScenarioAction) -> Self {
+        self.steps.push(ScenarioStep { trigger: ScenarioTrigger::At(time), action });
+        self
+    }
+    pub fn after_packets(mut self, packets: u64, action: ScenarioAction) -> Self {
+        self.steps.push(ScenarioStep { trigger: ScenarioTrigger::AfterPackets(packets), action });
+        self
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::from_toml_str(&fs::read_to_string(path).map_err(ScenarioError::Io)?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(s: &str) -> Result<Self, ScenarioError> {
+        let file: file::ScenarioFile = toml::from_str(s).map_err(ScenarioError::Toml)?;
+        let steps = file
+            .step
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| {
+                step.into_step()
+                    .map_err(|reason| ScenarioError::InvalidStep { index, reason })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { steps })
+    }
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "toml")]
+            ScenarioError::Io(e) => write!(f, "can't read scenario: {}", e),
+            #[cfg(feature = "toml")]
+            ScenarioError::Toml(e) => write!(f, "invalid scenario file: {}", e),
+            ScenarioError::InvalidStep { index, reason } => write!(f, "invalid scenario step {}: {}", index, reason),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+// Flat representation of the TOML file, checked when converted into steps.
+#[cfg(feature = "toml")]
+pub(crate) mod file {
+    use std::time::Duration;
+
+    use serde::Deserialize;
+    use wg_2024::network::NodeId;
+
+    use super::{ScenarioAction, ScenarioStep, ScenarioTrigger};
+
+    #[derive(Deserialize)]
+    pub(super) struct ScenarioFile {
+        #[serde(default)]
+        pub(super) step: Vec<StepFile>,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct StepFile {
+        at_secs: Option<f64>,
+        after_packets: Option<u64>,
+        action: String,
+        pdr: Option<f32>,
+        node: Option<NodeId>,
+    }
+
+    impl StepFile {
+        pub(crate) fn into_step(self) -> Result<ScenarioStep, String> {
+            let trigger = match (self.at_secs, self.after_packets) {
+                (Some(secs), None) if secs >= 0.0 => ScenarioTrigger::At(Duration::from_secs_f64(secs)),
+                (None, Some(packets)) => ScenarioTrigger::AfterPackets(packets),
+                _ => return Err("exactly one of `at_secs` (non negative) and `after_packets` is required".into()),
+            };
+            let node = || self.node.ok_or_else(|| format!("`{}` requires `node`", self.action));
+            let action = match self.action.as_str() {
+                "set_pdr" => match self.pdr {
+                    Some(pdr) if (0.0..=1.0).contains(&pdr) => ScenarioAction::SetPdr(pdr),
+                    _ => return Err("`set_pdr` requires `pdr` between 0 and 1".into()),
+                },
+                "crash" => ScenarioAction::Crash,
+                "link_down" => ScenarioAction::LinkDown(node()?),
+                "link_up" => ScenarioAction::LinkUp(node()?),
+                "pause" => ScenarioAction::Pause,
+                "resume" => ScenarioAction::Resume,
+                other => return Err(format!("unknown action `{}`", other)),
+            };
+            Ok(ScenarioStep { trigger, action })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::time::Duration;
+
+        use super::{NodeId, StepFile};
+        use crate::scenario::{ScenarioAction, ScenarioStep, ScenarioTrigger};
+
+        fn step(at_secs: Option<f64>, after_packets: Option<u64>, action: &str, pdr: Option<f32>, node: Option<NodeId>) -> StepFile {
+            StepFile { at_secs, after_packets, action: action.to_string(), pdr, node }
+        }
+
+        #[test]
+        fn valid_steps() {
+            let cases = [
+                (step(Some(1.5), None, "crash", None, None), ScenarioTrigger::At(Duration::from_millis(1500)), ScenarioAction::Crash),
+                (step(Some(0.0), None, "pause", None, None), ScenarioTrigger::At(Duration::ZERO), ScenarioAction::Pause),
+                (step(None, Some(3), "set_pdr", Some(1.0), None), ScenarioTrigger::AfterPackets(3), ScenarioAction::SetPdr(1.0)),
+                (step(None, Some(0), "set_pdr", Some(0.0), None), ScenarioTrigger::AfterPackets(0), ScenarioAction::SetPdr(0.0)),
+                (step(None, Some(1), "link_down", None, Some(4)), ScenarioTrigger::AfterPackets(1), ScenarioAction::LinkDown(4)),
+                (step(None, Some(1), "link_up", None, Some(4)), ScenarioTrigger::AfterPackets(1), ScenarioAction::LinkUp(4)),
+                // fields the action doesn't use are ignored
+                (step(None, Some(1), "resume", Some(0.5), Some(4)), ScenarioTrigger::AfterPackets(1), ScenarioAction::Resume),
+            ];
+            for (file, trigger, action) in cases {
+                let name = file.action.clone();
+                assert_eq!(file.into_step(), Ok(ScenarioStep { trigger, action }), "{}", name);
+            }
+        }
+
+        #[test]
+        fn invalid_steps() {
+            let trigger = "exactly one of `at_secs` (non negative) and `after_packets` is required";
+            let pdr = "`set_pdr` requires `pdr` between 0 and 1";
+            let cases = [
+                ("both triggers", step(Some(1.0), Some(3), "crash", None, None), trigger),
+                ("no trigger", step(None, None, "crash", None, None), trigger),
+                ("negative at_secs", step(Some(-1.0), None, "crash", None, None), trigger),
+                ("NaN at_secs", step(Some(f64::NAN), None, "crash", None, None), trigger),
+                ("set_pdr without pdr", step(Some(1.0), None, "set_pdr", None, None), pdr),
+                ("pdr above 1", step(Some(1.0), None, "set_pdr", Some(1.5), None), pdr),
+                ("negative pdr", step(Some(1.0), None, "set_pdr", Some(-0.1), None), pdr),
+                ("link_down without node", step(Some(1.0), None, "link_down", None, None), "`link_down` requires `node`"),
+                ("link_up without node", step(Some(1.0), None, "link_up", None, None), "`link_up` requires `node`"),
+                ("unknown action", step(Some(1.0), None, "explode", None, None), "unknown action `explode`"),
+            ];
+            for (case, file, reason) in cases {
+                assert_eq!(file.into_step(), Err(reason.to_string()), "{}", case);
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crossbeam_channel::{unbounded, Receiver, Select, Sender, TryRecvError};
+use std::io;
+use std::thread::{self, JoinHandle};
+
+use crate::drone::RustaceansWitAttitudesDrone;
+use crate::stepper::Stepper;
+
+/// Runs many drones on a few threads instead of one thread per drone.
+///
+/// Each worker handles one input of each of its drones in turn and sleeps while none of them has
+/// anything to do. Drones behave as if they were run on their own thread, they only share the CPU.
+pub struct Scheduler {
+    workers: Vec<(Sender<RustaceansWitAttitudesDrone>, JoinHandle<()>)>,
+    next_worker: usize,
+}
+
+impl Scheduler {
+    /// Start `workers` threads (at least one) named `rwa-worker-<n>`.
+    pub fn new(workers: usize) -> io::Result<Self> {
+        let workers = (0..workers.max(1))
+            .map(|index| {
+                let (drone_send, drone_recv) = unbounded();
+                let thread = thread::Builder::new()
+                    .name(format!("rwa-worker-{}", index))
+                    .spawn(move || work(drone_recv))?;
+                Ok((drone_send, thread))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { workers, next_worker: 0 })
+    }
+
+    /// Start running `drone` on one of the workers, they take turns.
+    pub fn add(&mut self, drone: RustaceansWitAttitudesDrone) {
+        let (drone_send, _) = &self.workers[self.next_worker];
+        // the worker only stops if it panicked, the drone is lost with it
+        let _ = drone_send.send(drone);
+        self.next_worker = (self.next_worker + 1) % self.workers.len();
+    }
+
+    /// Wait for the worker threads. Like the thread of a single drone, they run as long as their drones do.
+    pub fn join(self) -> thread::Result<()> {
+        let threads: Vec<JoinHandle<()>> = self.workers.into_iter().map(|(_, thread)| thread).collect();
+        threads.into_iter().try_for_each(JoinHandle::join)
+    }
+}
+
+fn work(drone_recv: Receiver<RustaceansWitAttitudesDrone>) {
+    let mut stepper = Stepper::new();
+    let mut accepting = true;
+    loop {
+        while accepting {
+            match drone_recv.try_recv() {
+                Ok(drone) => stepper.add(drone),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => accepting = false,
+            }
+        }
+
+        // one input per drone in turn, until they are all idle
+        stepper.run_until_idle();
+        if !accepting && stepper.is_empty() {
+            return;
+        }
+
+        // sleep until a drone has an input or a timer
+        let mut select = Select::new();
+        if accepting {
+            select.recv(&drone_recv);
+        }
+        for drone in stepper.drones() {
+            drone.watch(&mut select);
+        }
+        match stepper.drones().iter().filter_map(|drone| drone.wake_deadline()).min() {
+            Some(deadline) => {
+                let _ = select.ready_deadline(deadline);
+            }
+            None => {
+                select.ready();
+            }
+        }
+    }
+}
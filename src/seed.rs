@@ -0,0 +1,11 @@
+use wg_2024::network::NodeId;
+
+/// Seed of drone `drone_id` in a simulation run seeded with `simulation_seed`, see `DroneOptions::simulation_seed`.
+/// The same two numbers always give the same seed, and the drones of a run get unrelated ones.
+pub fn derive_seed(simulation_seed: u64, drone_id: NodeId) -> u64 {
+    // SplitMix64 over the pair: neighboring seeds and ids don't give correlated generators
+    let mut z = simulation_seed ^ (u64::from(drone_id) + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,95 @@
+use crossbeam_channel::unbounded;
+use std::collections::HashMap;
+use std::fmt;
+
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType, FRAGMENT_DSIZE};
+
+use crate::drone::RustaceansWitAttitudesDrone;
+use crate::options::DroneOptions;
+
+/// Standard deviations used by `assert_pdr_fidelity`, a correct drone fails about once in 15000 runs.
+pub const DEFAULT_Z: f64 = 4.0;
+
+/// Drops observed by `measure_pdr`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdrMeasurement {
+    /// The configured pdr.
+    pub pdr: f32,
+    pub fragments: u64,
+    pub dropped: u64,
+}
+
+impl PdrMeasurement {
+    pub fn observed(&self) -> f64 {
+        if self.fragments == 0 {
+            return 0.0;
+        }
+        self.dropped as f64 / self.fragments as f64
+    }
+
+    /// Half-width of the interval around the pdr the observed ratio should be in, `z` standard deviations
+    /// of the binomial distribution (normal approximation) plus one fragment.
+    pub fn margin(&self, z: f64) -> f64 {
+        if self.fragments == 0 {
+            return 1.0;
+        }
+        let n = self.fragments as f64;
+        let p = f64::from(self.pdr.clamp(0.0, 1.0));
+        z * (p * (1.0 - p) / n).sqrt() + 1.0 / n
+    }
+
+    pub fn is_within(&self, z: f64) -> bool {
+        (self.observed() - f64::from(self.pdr)).abs() <= self.margin(z)
+    }
+
+    /// Panic with the measurement if the observed ratio is too far from the pdr.
+    pub fn assert_within(&self, z: f64) {
+        assert!(self.is_within(z), "{} (allowed ±{:.4})", self, self.margin(z));
+    }
+}
+
+impl fmt::Display for PdrMeasurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dropped {} of {} fragments ({:.4}) with a pdr of {}", self.dropped, self.fragments, self.observed(), self.pdr)
+    }
+}
+
+/// Push `fragments` fragments through a drone with `pdr` and `options` and count the Nack(Dropped) it sends back.
+/// Runs on the calling thread, with `DroneOptions::seed` the result is always the same.
+pub fn measure_pdr(pdr: f32, options: DroneOptions, fragments: u64) -> PdrMeasurement {
+    let (event_send, event_recv) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (previous_send, previous_recv) = unbounded();
+    let (next_send, next_recv) = unbounded();
+    let neighbors = HashMap::from([(0, previous_send), (2, next_send)]);
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, neighbors, pdr)
+        .with_options(options);
+    drone.start();
+
+    let mut dropped = 0;
+    for fragment_index in 0..fragments {
+        let fragment = Fragment { fragment_index, total_n_fragments: fragments, length: 1, data: [0; FRAGMENT_DSIZE] };
+        let packet = Packet::new_fragment(SourceRoutingHeader::new(vec![0, 1, 2], 1), 0, fragment);
+        // the drone can't be gone, it's right here
+        let _ = packet_send.send(packet);
+        while drone.step() {}
+
+        // keep the channels short
+        dropped += previous_recv
+            .try_iter()
+            .filter(|packet| matches!(&packet.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::Dropped)))
+            .count() as u64;
+        next_recv.try_iter().for_each(drop);
+        event_recv.try_iter().for_each(drop);
+    }
+    PdrMeasurement { pdr, fragments, dropped }
+}
+
+/// Check that a drone with `pdr` drops the expected share of `fragments` fragments, panics otherwise.
+/// A few thousand fragments are enough to catch a drop rate that is off by a few percent.
+pub fn assert_pdr_fidelity(pdr: f32, options: DroneOptions, fragments: u64) {
+    measure_pdr(pdr, options, fragments).assert_within(DEFAULT_Z);
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::mem::Discriminant;
+use std::time::Duration;
+
+use web_time::Instant;
+use wg_2024::packet::NackType;
+
+use crate::stats;
+
+// What the drone remembers of a session it has seen recently.
+#[derive(Debug)]
+pub(crate) struct Session {
+    pub(crate) first_seen: Instant,
+    pub(crate) last_seen: Instant,
+    // (fragment, type) -> sent at, see DroneOptions::nack_dedup_window
+    pub(crate) recent_nacks: HashMap<(u64, Discriminant<NackType>), Instant>,
+}
+
+// Sessions by id, their counters are in DroneStats::sessions. Bounded: the ones idle for `idle_timeout`
+// are forgotten when a new one comes, then the least recently seen past `capacity`.
+#[derive(Debug, Default)]
+pub(crate) struct SessionTable {
+    sessions: HashMap<u64, Session>,
+}
+
+impl SessionTable {
+    // the session was seen at `now`, returns the ones forgotten to make room for it
+    pub(crate) fn touch(&mut self, session_id: u64, now: Instant, idle_timeout: Duration, capacity: usize) -> Vec<u64> {
+        if let Some(session) = self.sessions.get_mut(&session_id) {
+            session.last_seen = now;
+            return Vec::new();
+        }
+        let mut evicted = Vec::new();
+        self.sessions.retain(|session_id, session| {
+            let idle = now.duration_since(session.last_seen) >= idle_timeout;
+            if idle {
+                evicted.push(*session_id);
+            }
+            !idle
+        });
+        while self.sessions.len() >= capacity.max(1) {
+            let Some(oldest) = self.sessions.iter().min_by_key(|(_, session)| session.last_seen).map(|(id, _)| *id) else {
+                break;
+            };
+            self.sessions.remove(&oldest);
+            evicted.push(oldest);
+        }
+        self.sessions.insert(session_id, Session {
+            first_seen: now,
+            last_seen: now,
+            recent_nacks: HashMap::new(),
+        });
+        evicted
+    }
+
+    pub(crate) fn get_mut(&mut self, session_id: u64) -> Option<&mut Session> {
+        self.sessions.get_mut(&session_id)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    // restored sessions are idle from `now` on
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn restore(&mut self, session_ids: impl IntoIterator<Item = u64>, now: Instant) {
+        self.sessions = session_ids
+            .into_iter()
+            .map(|session_id| (session_id, Session { first_seen: now, last_seen: now, recent_nacks: HashMap::new() }))
+            .collect();
+    }
+
+    // for DroneStats::memory
+    pub(crate) fn bytes(&self) -> usize {
+        stats::table_bytes::<(u64, Session)>(self.sessions.capacity())
+            + self.sessions.values()
+                .map(|session| stats::table_bytes::<((u64, Discriminant<NackType>), Instant)>(session.recent_nacks.capacity()))
+                .sum::<usize>()
+    }
+}
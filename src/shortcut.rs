@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use web_time::Instant;
+use wg_2024::network::NodeId;
+
+use crate::options::ShortcutRateLimit;
+
+// Shortcuts in the current window of one (next hop, session).
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    length: Duration,
+    sent: u32,
+    suppressed: u64,
+}
+
+// What's left of a window that suppressed shortcuts, for the summary event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Suppressed {
+    pub(crate) next_hop: Option<NodeId>,
+    pub(crate) session_id: u64,
+    pub(crate) count: u64,
+    pub(crate) over: Duration,
+}
+
+// Counts the shortcuts of each (next hop, session), see DroneOptions::shortcut_rate_limit.
+// A window that had to suppress some is followed by one twice as long, up to `max_window`.
+#[derive(Debug, Default)]
+pub(crate) struct ShortcutLimiter {
+    windows: HashMap<(Option<NodeId>, u64), Window>,
+}
+
+impl ShortcutLimiter {
+    // whether the shortcut can be sent, and the summary of the window it closed if any
+    pub(crate) fn allow(
+        &mut self,
+        limit: ShortcutRateLimit,
+        next_hop: Option<NodeId>,
+        session_id: u64,
+        now: Instant,
+    ) -> (bool, Option<Suppressed>) {
+        let window = self.windows.entry((next_hop, session_id)).or_insert(Window {
+            start: now,
+            length: limit.window,
+            sent: 0,
+            suppressed: 0,
+        });
+        let summary = if now >= window.start + window.length { roll(window, limit, next_hop, session_id, now) } else { None };
+        if window.sent < limit.burst {
+            window.sent += 1;
+            (true, summary)
+        } else {
+            window.suppressed += 1;
+            (false, summary)
+        }
+    }
+
+    // summaries of the windows over by `now`, the quiet ones are forgotten
+    pub(crate) fn expire(&mut self, limit: ShortcutRateLimit, now: Instant) -> Vec<Suppressed> {
+        let mut summaries = Vec::new();
+        self.windows.retain(|(next_hop, session_id), window| {
+            if now < window.start + window.length {
+                return true;
+            }
+            let summary = roll(window, limit, *next_hop, *session_id, now);
+            let storm = summary.is_some();
+            summaries.extend(summary);
+            storm
+        });
+        summaries
+    }
+
+    // end of the first window with something to summarize
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.windows.values().filter(|window| window.suppressed > 0).map(|window| window.start + window.length).min()
+    }
+}
+
+// start the next window at `now`, longer if this one had to suppress shortcuts
+fn roll(window: &mut Window, limit: ShortcutRateLimit, next_hop: Option<NodeId>, session_id: u64, now: Instant) -> Option<Suppressed> {
+    let summary = (window.suppressed > 0)
+        .then_some(Suppressed { next_hop, session_id, count: window.suppressed, over: window.length });
+    let length = match summary {
+        Some(_) => (window.length * 2).min(limit.max_window.max(limit.window)),
+        None => limit.window,
+    };
+    *window = Window { start: now, length, sent: 0, suppressed: 0 };
+    summary
+}
@@ -0,0 +1,109 @@
+use crossbeam_channel::Sender;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use web_time::Instant;
+
+use crate::channel::{ChannelSender, SendFailure};
+
+/// The sender of a neighbor that is slow or stuck on demand, to test how a drone copes with a stalled neighbor.
+///
+/// Clones share their state: give one to the drone (`RustaceansWitAttitudesDrone::with_channels`) and keep one to
+/// slow it down from the test. A stalled sender acts like a full channel: `send` waits until `resume`,
+/// `send_timeout` gives up after its timeout with `SendFailure::Full`.
+#[derive(Debug)]
+pub struct SlowSender<T> {
+    inner: Sender<T>,
+    state: Arc<(Mutex<Slowness>, Condvar)>,
+}
+
+#[derive(Debug, Default)]
+struct Slowness {
+    delay: Duration,
+    stalled: bool,
+}
+
+impl<T> Clone for SlowSender<T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), state: self.state.clone() }
+    }
+}
+
+impl<T> SlowSender<T> {
+    pub fn new(inner: Sender<T>) -> Self {
+        Self { inner, state: Arc::default() }
+    }
+
+    /// Every send takes `delay` before it reaches the channel.
+    pub fn set_delay(&self, delay: Duration) {
+        self.lock().delay = delay;
+    }
+
+    pub fn stall(&self) {
+        self.lock().stalled = true;
+    }
+
+    /// Let the waiting sends through.
+    pub fn resume(&self) {
+        self.lock().stalled = false;
+        self.state.1.notify_all();
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.lock().stalled
+    }
+
+    // waits out the delay and the stall, false if `deadline` comes first
+    fn wait(&self, deadline: Option<Instant>) -> bool {
+        let delay = self.lock().delay;
+        if !delay.is_zero() {
+            match deadline {
+                Some(deadline) if deadline < Instant::now() + delay => {
+                    thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    return false;
+                }
+                _ => thread::sleep(delay),
+            }
+        }
+        let mut slowness = self.lock();
+        while slowness.stalled {
+            let resumed = &self.state.1;
+            slowness = match deadline {
+                None => resumed.wait(slowness).unwrap_or_else(|poisoned| poisoned.into_inner()),
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return false;
+                    }
+                    resumed.wait_timeout(slowness, left).unwrap_or_else(|poisoned| poisoned.into_inner()).0
+                }
+            };
+        }
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Slowness> {
+        self.state.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<T: Send> ChannelSender<T> for SlowSender<T> {
+    fn send(&self, value: T) -> Result<(), T> {
+        self.wait(None);
+        ChannelSender::send(&self.inner, value)
+    }
+    fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendFailure<T>> {
+        let deadline = Instant::now() + timeout;
+        if !self.wait(Some(deadline)) {
+            return Err(SendFailure::Full(value));
+        }
+        ChannelSender::send_timeout(&self.inner, value, deadline.saturating_duration_since(Instant::now()))
+    }
+    fn queued(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+    fn is_full(&self) -> Option<bool> {
+        Some(self.is_stalled() || self.inner.is_full())
+    }
+}
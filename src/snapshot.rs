@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use wg_2024::network::NodeId;
+
+use crate::stats::DroneStats;
+
+/// Mutable state of a drone, to checkpoint a simulation and resume it later, or to move a drone to
+/// another thread or process. Taken with `RustaceansWitAttitudesDrone::snapshot` or `DroneHandle::snapshot`,
+/// restored with `RustaceansWitAttitudesDrone::with_snapshot`.
+///
+/// Channels can't be saved: the restored drone is built with new channels to the same neighbors.
+/// The random generator starts over from `seed`, the same decisions are not repeated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DroneSnapshot {
+    pub drone_id: NodeId,
+    pub pdr: f32,
+    /// Sorted.
+    pub neighbors: Vec<NodeId>,
+    /// `(flood_id, initiator_id)` of the floods already forwarded, oldest first.
+    pub floods: Vec<(u64, NodeId)>,
+    /// Duplicate floods already answered (`DuplicateFloodPolicy::RespondOncePerInitiator`).
+    pub answered_floods: Vec<(u64, NodeId)>,
+    pub stats: DroneStats,
+    pub crashing: bool,
+    pub paused: bool,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot is of another drone.
+    WrongDrone { expected: NodeId, found: NodeId },
+    /// The drone was built with other neighbors than the ones in the snapshot.
+    NeighborsMismatch { expected: Vec<NodeId>, found: Vec<NodeId> },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::WrongDrone { expected, found } => {
+                write!(f, "snapshot of drone {} can't be restored into drone {}", expected, found)
+            }
+            SnapshotError::NeighborsMismatch { expected, found } => {
+                write!(f, "snapshot has neighbors {:?}, the drone has {:?}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
@@ -0,0 +1,168 @@
+use crossbeam_channel::{at, never, select_biased, unbounded, Receiver, Select, Sender, TryRecvError};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use wg_2024::controller::DroneCommand;
+
+use crate::drone::RustaceansWitAttitudesDrone;
+use crate::handle::HandleCommand;
+
+// The drone shared by the control and data threads. Commands take precedence: while one is
+// waiting for the lock the data thread doesn't take it again.
+struct Shared {
+    drone: Mutex<RustaceansWitAttitudesDrone>,
+    commands_waiting: AtomicUsize,
+}
+
+impl Shared {
+    fn lock_for_command(&self) -> MutexGuard<'_, RustaceansWitAttitudesDrone> {
+        self.commands_waiting.fetch_add(1, Ordering::SeqCst);
+        let drone = self.lock();
+        self.commands_waiting.fetch_sub(1, Ordering::SeqCst);
+        drone
+    }
+    fn lock_for_packet(&self) -> MutexGuard<'_, RustaceansWitAttitudesDrone> {
+        while self.commands_waiting.load(Ordering::SeqCst) > 0 {
+            thread::yield_now();
+        }
+        self.lock()
+    }
+    fn lock(&self) -> MutexGuard<'_, RustaceansWitAttitudesDrone> {
+        self.drone.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Starts handling the packets on a thread named `rwa-drone-<id>-data`, the returned closure
+// handles the controller and handle commands and the timers on the thread that calls it.
+pub(crate) fn run(mut drone: RustaceansWitAttitudesDrone, stack_size: Option<usize>) -> io::Result<impl FnOnce() + Send + 'static> {
+    let (controller_recv, handle_recv) = drone.receivers();
+    let data_name = format!("rwa-drone-{}-data", drone.id());
+    drone.start();
+    let shared = Arc::new(Shared { drone: Mutex::new(drone), commands_waiting: AtomicUsize::new(0) });
+    // tells the data thread that the state changed (pause, crash, shutdown)
+    let (wake_send, wake_recv) = unbounded();
+    // disconnects when the data thread stops, it can shut the drone down (crash without a controller)
+    let (data_done_send, data_done_recv) = unbounded::<()>();
+    let data = {
+        let shared = shared.clone();
+        let mut builder = thread::Builder::new().name(data_name);
+        if let Some(stack_size) = stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        builder.spawn(move || {
+            run_data(&shared, wake_recv);
+            drop(data_done_send);
+        })?
+    };
+    Ok(move || {
+        run_control(&shared, controller_recv, handle_recv, &wake_send, &data_done_recv);
+        drop(wake_send);
+        let _ = data.join();
+    })
+}
+
+fn run_control(
+    shared: &Shared,
+    mut controller_recv: Receiver<DroneCommand>,
+    mut handle_recv: Receiver<HandleCommand>,
+    wake_send: &Sender<()>,
+    data_done: &Receiver<()>,
+) {
+    loop {
+        let timer = {
+            let drone = shared.lock_for_command();
+            if drone.is_shut_down() {
+                return;
+            }
+            // like run(), no timers but the drain timeout while crashing
+            let timer = if drone.is_crashing() { drone.crash_deadline() } else { drone.timer_deadline() };
+            timer.map_or_else(never, at)
+        };
+        select_biased! {
+            recv(controller_recv) -> command => {
+                match command {
+                    Ok(command) => {
+                        let mut drone = shared.lock_for_command();
+                        let crashing = drone.is_crashing();
+                        drone.dispatch_command(command);
+                        if !crashing {
+                            let packets_pending = drone.packets_pending();
+                            drone.housekeeping(packets_pending);
+                        }
+                    }
+                    Err(_) => {
+                        shared.lock_for_command().controller_disconnected();
+                        controller_recv = never();
+                    }
+                }
+            },
+            recv(handle_recv) -> command => {
+                match command {
+                    Ok(command) => {
+                        let mut drone = shared.lock_for_command();
+                        let crashing = drone.is_crashing();
+                        drone.handle_handle_command(command);
+                        if !crashing {
+                            let packets_pending = drone.packets_pending();
+                            drone.housekeeping(packets_pending);
+                        }
+                    }
+                    // the DroneHandle was dropped
+                    Err(_) => handle_recv = never(),
+                }
+            },
+            recv(timer) -> _ => {
+                let mut drone = shared.lock_for_command();
+                if drone.is_crashing() {
+                    drone.check_crash_deadline();
+                } else {
+                    let packets_pending = drone.packets_pending();
+                    drone.housekeeping(packets_pending);
+                }
+            },
+            recv(data_done) -> _ => {},
+        }
+        let _ = wake_send.send(());
+    }
+}
+
+fn run_data(shared: &Shared, wake_recv: Receiver<()>) {
+    loop {
+        // taken again each time, the drone stops reading an ingress channel once it's disconnected
+        let ingress = {
+            let drone = shared.lock_for_packet();
+            if drone.is_shut_down() {
+                return;
+            }
+            if drone.accepts_packets() {
+                drone.ingress()
+            } else {
+                Vec::new()
+            }
+        };
+        let mut select = Select::new();
+        for packet_recv in &ingress {
+            select.recv(packet_recv);
+        }
+        let wake = select.recv(&wake_recv);
+        if select.ready() == wake {
+            // the control thread is gone
+            if wake_recv.try_recv() == Err(TryRecvError::Disconnected) {
+                return;
+            }
+            continue;
+        }
+        // the drone reads its ingress channels in turn, not necessarily the one that's ready
+        let mut drone = shared.lock_for_packet();
+        let crashing = drone.is_crashing();
+        if let Some(packet) = drone.try_recv_packet() {
+            drone.dispatch_packet(packet);
+            if !crashing {
+                let packets_pending = drone.packets_pending();
+                drone.housekeeping(packets_pending);
+            }
+        }
+    }
+}
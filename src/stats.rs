@@ -0,0 +1,452 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::mem;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, NodeType, Packet, PacketType};
+
+/// Counters collected while the drone runs, see `RustaceansWitAttitudesDrone::stats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DroneStats {
+    pub packets_received: u64,
+    pub fragments_dropped: u64,
+    /// Acks, Nacks and flood packets dropped, see `DroneOptions::droppable`.
+    pub control_packets_dropped: u64,
+    pub nacks_sent: u64,
+    /// `nacks_sent` by type, as sent (after `ChaosOptions::misreport_nacks`).
+    pub nacks_by_type: NackCounts,
+    /// Packets whose routing header was unusable, see `DroneOptions::broken_header_policy`.
+    pub broken_headers: u64,
+    /// Shortcuts not sent, see `DroneOptions::shortcut_rate_limit`.
+    pub shortcuts_suppressed: u64,
+    /// Events the simulation controller never got: its channel was full (see `DroneOptions::event_overflow_policy`)
+    /// or disconnected.
+    pub events_lost: u64,
+    /// Packets dropped or Nacked by the `Acl`.
+    pub packets_filtered: u64,
+    /// Nacks not sent because an identical one was sent within `nack_dedup_window`.
+    pub nacks_suppressed: u64,
+    /// Packets handled in a way the protocol doesn't allow, see `conformance`.
+    #[cfg(feature = "conformance")]
+    pub conformance_violations: u64,
+    /// Fragment counters of the sessions seen recently, keyed by session id.
+    pub sessions: HashMap<u64, SessionStats>,
+    /// Sessions the drone remembers, updated after every input. At most `DroneOptions::session_capacity`.
+    pub sessions_tracked: usize,
+    /// Sessions forgotten for being idle (`session_idle_timeout`) or to make room for a new one.
+    pub sessions_evicted: u64,
+    /// `None` unless `DroneOptions::energy` is set.
+    pub energy_remaining: Option<u64>,
+    /// Times the drone misbehaved on purpose, see `DroneOptions::chaos` and `Adversarial`.
+    pub misbehaviors: u64,
+    /// Extra copies sent, see `DroneOptions::duplicate_fragment_probability`.
+    pub fragments_duplicated: u64,
+    /// See `DroneOptions::corrupt_payload_probability`.
+    pub fragments_corrupted: u64,
+    /// Fragments received with a wrong checksum, see `DroneOptions::verify_checksums`.
+    pub checksum_failures: u64,
+    /// Packets not sent because the next hop's channel, or the backlog of a paused drone, was full, see
+    /// `DroneOptions::overflow_policy`.
+    pub packets_overflowed: u64,
+    /// Times a packet was kept because its next hop couldn't be reached, see `StoreAndForward`.
+    pub packets_stored: u64,
+    /// Fragments kept while their next hop's link was down, see `LinkProfile::queue_while_down`.
+    pub fragments_queued: u64,
+    /// Forwarded fragments sent again because their Ack was late, see `DroneOptions::hop_reliability`.
+    pub hop_retransmissions: u64,
+    /// Forwarded fragments given up on after `HopReliability::max_retransmissions`.
+    pub hop_acks_missed: u64,
+    /// Traffic on the link to each neighbor, keyed by node id.
+    pub neighbors: HashMap<NodeId, NeighborStats>,
+    /// The last floods seen, oldest first, at most `DroneOptions::flood_log_capacity`.
+    pub floods: VecDeque<FloodRecord>,
+    /// Neighbors each new flood was forwarded to.
+    pub flood_fan_out: Histogram,
+    /// Microseconds from the first FloodRequest of a flood to the first FloodResponse of that flood passing back,
+    /// for the floods still in `floods` when it does.
+    pub flood_response_latency: Histogram,
+    /// Estimate of the memory the drone holds, updated after every input.
+    pub memory: MemoryUsage,
+    /// Current pdr, updated after every input.
+    pub pdr: f32,
+    /// Packets waiting in the incoming channel, updated after every input.
+    pub queue_depth: usize,
+    /// Whether an incoming channel is full (bounded channels only), updated after every input.
+    pub ingress_full: bool,
+    /// Nodes the drone has a sender to, sorted.
+    pub neighbor_ids: Vec<NodeId>,
+    pub state: DroneState,
+}
+
+impl DroneStats {
+    /// Zero every counter, `energy_remaining`, `pdr`, `queue_depth`, `ingress_full`, `sessions_tracked`,
+    /// `neighbor_ids` and `state` are kept.
+    pub fn reset(&mut self) {
+        *self = DroneStats {
+            energy_remaining: self.energy_remaining,
+            pdr: self.pdr,
+            queue_depth: self.queue_depth,
+            ingress_full: self.ingress_full,
+            sessions_tracked: self.sessions_tracked,
+            neighbor_ids: mem::take(&mut self.neighbor_ids),
+            state: self.state,
+            ..DroneStats::default()
+        };
+    }
+
+    fn write_csv(&self, csv: &mut Csv<impl Write>) -> io::Result<()> {
+        let global = [
+            ("packets_received", self.packets_received),
+            ("fragments_dropped", self.fragments_dropped),
+            ("control_packets_dropped", self.control_packets_dropped),
+            ("nacks_sent", self.nacks_sent),
+            ("broken_headers", self.broken_headers),
+            ("shortcuts_suppressed", self.shortcuts_suppressed),
+            ("events_lost", self.events_lost),
+            ("packets_filtered", self.packets_filtered),
+            ("nacks_suppressed", self.nacks_suppressed),
+            #[cfg(feature = "conformance")]
+            ("conformance_violations", self.conformance_violations),
+            ("sessions_tracked", self.sessions_tracked as u64),
+            ("sessions_evicted", self.sessions_evicted),
+            ("misbehaviors", self.misbehaviors),
+            ("fragments_duplicated", self.fragments_duplicated),
+            ("fragments_corrupted", self.fragments_corrupted),
+            ("checksum_failures", self.checksum_failures),
+            ("packets_overflowed", self.packets_overflowed),
+            ("packets_stored", self.packets_stored),
+            ("fragments_queued", self.fragments_queued),
+            ("hop_retransmissions", self.hop_retransmissions),
+            ("hop_acks_missed", self.hop_acks_missed),
+            ("queue_depth", self.queue_depth as u64),
+            ("ingress_full", u64::from(self.ingress_full)),
+        ];
+        for (counter, value) in global {
+            csv.row("global", "", counter, value)?;
+        }
+        csv.row("global", "", "pdr", self.pdr)?;
+        if let Some(energy_remaining) = self.energy_remaining {
+            csv.row("global", "", "energy_remaining", energy_remaining)?;
+        }
+
+        let nacks = &self.nacks_by_type;
+        csv.row("nack_type", "ErrorInRouting", "sent", nacks.error_in_routing)?;
+        csv.row("nack_type", "DestinationIsDrone", "sent", nacks.destination_is_drone)?;
+        csv.row("nack_type", "Dropped", "sent", nacks.dropped)?;
+        csv.row("nack_type", "UnexpectedRecipient", "sent", nacks.unexpected_recipient)?;
+
+        let mut neighbors: Vec<_> = self.neighbors.iter().collect();
+        neighbors.sort_unstable_by_key(|(node_id, _)| **node_id);
+        for (node_id, neighbor) in neighbors {
+            csv.row("neighbor", node_id, "packets_forwarded", neighbor.packets_forwarded)?;
+            csv.row("neighbor", node_id, "packets_received", neighbor.packets_received)?;
+            csv.row("neighbor", node_id, "send_failures", neighbor.send_failures)?;
+            csv.row("neighbor", node_id, "nacks_sent", neighbor.nacks_sent)?;
+            if let Some(queued) = neighbor.queued {
+                csv.row("neighbor", node_id, "queued", queued)?;
+            }
+        }
+
+        let mut sessions: Vec<_> = self.sessions.iter().collect();
+        sessions.sort_unstable_by_key(|(session_id, _)| **session_id);
+        for (session_id, session) in sessions {
+            csv.row("session", session_id, "fragments_seen", session.fragments_seen)?;
+            csv.row("session", session_id, "fragments_dropped", session.fragments_dropped)?;
+            csv.row("session", session_id, "fragments_forwarded", session.fragments_forwarded)?;
+        }
+
+        for flood in &self.floods {
+            let key = format!("{}/{}", flood.initiator_id, flood.flood_id);
+            csv.row("flood", &key, "forwarded_to", flood.forwarded_to)?;
+            csv.row("flood", &key, "duplicates", flood.duplicates)?;
+            csv.row("flood", &key, "responses", flood.responses)?;
+            if let Some(first_response) = flood.first_response {
+                csv.row("flood", &key, "first_response_us", first_response.as_micros())?;
+            }
+        }
+
+        for (scope, histogram) in [("flood_fan_out", &self.flood_fan_out), ("flood_response_latency", &self.flood_response_latency)] {
+            csv.row(scope, "", "count", histogram.count)?;
+            csv.row(scope, "", "sum", histogram.sum)?;
+            csv.row(scope, "", "max", histogram.max)?;
+            for (bucket, count) in histogram.buckets.iter().enumerate() {
+                csv.row(scope, Histogram::upper_bound(bucket), "values", count)?;
+            }
+        }
+
+        let memory = &self.memory;
+        csv.row("memory", "", "flood_table", memory.flood_table)?;
+        csv.row("memory", "", "queued_packets", memory.queued_packets)?;
+        csv.row("memory", "", "sessions", memory.sessions)?;
+        csv.row("memory", "", "capture_buffers", memory.capture_buffers)?;
+        csv.row("memory", "", "statistics", memory.statistics)
+    }
+}
+
+/// Write the statistics of `drones` as CSV, one row per counter: "drone,scope,key,counter,value".
+///
+/// `scope` is `global`, `nack_type` (`key` is the type), `neighbor`, `session` or `flood` (`key` is the node id, the
+/// session id or `initiator/flood id`), `flood_fan_out` and `flood_response_latency` (`key` is the upper bound of
+/// each bucket, empty for the count, sum and max) or `memory`. Durations are in microseconds.
+pub fn write_stats_csv(writer: impl Write, drones: impl IntoIterator<Item = (NodeId, impl Borrow<DroneStats>)>) -> io::Result<()> {
+    let mut csv = Csv { writer, drone_id: 0 };
+    writeln!(csv.writer, "drone,scope,key,counter,value")?;
+    for (drone_id, stats) in drones {
+        csv.drone_id = drone_id;
+        stats.borrow().write_csv(&mut csv)?;
+    }
+    csv.writer.flush()
+}
+
+// rows of one drone at a time, the keys and values never need quoting
+struct Csv<W> {
+    writer: W,
+    drone_id: NodeId,
+}
+
+impl<W: Write> Csv<W> {
+    fn row(&mut self, scope: &str, key: impl Display, counter: &str, value: impl Display) -> io::Result<()> {
+        writeln!(self.writer, "{},{},{},{},{}", self.drone_id, scope, key, counter, value)
+    }
+}
+
+/// Nacks of each type, see `DroneStats::nacks_by_type`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NackCounts {
+    pub error_in_routing: u64,
+    pub destination_is_drone: u64,
+    pub dropped: u64,
+    pub unexpected_recipient: u64,
+}
+
+impl NackCounts {
+    pub(crate) fn record(&mut self, nack_type: &NackType) {
+        match nack_type {
+            NackType::ErrorInRouting(_) => self.error_in_routing += 1,
+            NackType::DestinationIsDrone => self.destination_is_drone += 1,
+            NackType::Dropped => self.dropped += 1,
+            NackType::UnexpectedRecipient(_) => self.unexpected_recipient += 1,
+        }
+    }
+}
+
+/// Where the drone is in its life cycle. A `Crash` moves it from `Running` to `Crashing`, removing its
+/// last sender (or `DroneOptions::crash_drain_timeout`) from `Crashing` to `Crashed`. It never goes back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DroneState {
+    #[default]
+    Running,
+    /// Only `RemoveSender` is applied, another `Crash` and the other commands are ignored. Packets are
+    /// Nacked or passed on as the crash policies say.
+    Crashing,
+    /// The drone stopped, its thread (or `Stepper` slot) is gone.
+    Crashed,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionStats {
+    /// Time since the drone started.
+    pub first_seen: Duration,
+    pub last_seen: Duration,
+    pub fragments_seen: u64,
+    pub fragments_dropped: u64,
+    pub fragments_forwarded: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NeighborStats {
+    pub packets_forwarded: u64,
+    pub packets_received: u64,
+    /// Packets that couldn't be sent: no sender, link down, full or disconnected channel.
+    pub send_failures: u64,
+    /// Nacks sent to this neighbor, ours and forwarded ones.
+    pub nacks_sent: u64,
+    /// Time since the drone started of the last packet sent to or received from this neighbor.
+    pub last_activity: Option<Duration>,
+    /// Packets waiting in the channel to this neighbor (its outgoing queue with `DroneOptions::outgoing_queue`),
+    /// `None` if the channel doesn't tell. Updated after every input, like `full`.
+    pub queued: Option<usize>,
+    /// Whether that channel is full, `None` if it doesn't tell or can't be full.
+    pub full: Option<bool>,
+}
+
+/// Bytes held by the parts of the drone that grow as it runs, see `DroneStats::memory`.
+/// The sizes are estimates: allocator overhead and the data behind pointers of the channels are left out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MemoryUsage {
+    /// Floods remembered by the drone (its share of a shared `FloodStore`) and duplicates answered.
+    pub flood_table: usize,
+    /// Packets waiting in the incoming channel, the paused backlog, or held back by the drone.
+    pub queued_packets: usize,
+    /// Per-session state and recent Nacks.
+    pub sessions: usize,
+    /// Buffers of the capture files, drop log and recording.
+    pub capture_buffers: usize,
+    /// Neighbor statistics and the flood log.
+    pub statistics: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.flood_table + self.queued_packets + self.sessions + self.capture_buffers + self.statistics
+    }
+}
+
+// Bytes allocated by a map or set holding `capacity` entries of type T.
+pub(crate) fn table_bytes<T>(capacity: usize) -> usize {
+    capacity * mem::size_of::<T>()
+}
+
+// Bytes of a packet, with its route and path trace.
+pub(crate) fn packet_bytes(packet: &Packet) -> usize {
+    let path_trace = match &packet.pack_type {
+        PacketType::FloodRequest(flood_request) => flood_request.path_trace.capacity(),
+        PacketType::FloodResponse(flood_response) => flood_response.path_trace.capacity(),
+        _ => 0,
+    };
+    mem::size_of::<Packet>()
+        + packet.routing_header.hops.capacity() * mem::size_of::<NodeId>()
+        + path_trace * mem::size_of::<(NodeId, NodeType)>()
+}
+
+/// What the drone did with a flood, see `DroneStats::floods`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FloodRecord {
+    pub flood_id: u64,
+    pub initiator_id: NodeId,
+    /// Time since the drone started.
+    pub first_seen: Duration,
+    /// Neighbor the first FloodRequest came from, `None` if the path trace was empty.
+    pub from: Option<NodeId>,
+    /// Neighbors the first FloodRequest was forwarded to.
+    pub forwarded_to: u64,
+    /// FloodRequests of this flood received again.
+    pub duplicates: u64,
+    /// FloodResponses generated for the duplicates.
+    pub responses: u64,
+    /// Time from `first_seen` to the first FloodResponse of this flood forwarded back, `None` until then.
+    pub first_response: Option<Duration>,
+}
+
+/// Distribution of values in power-of-two buckets: bucket 0 counts the zeros, bucket `i` the values from
+/// `2^(i-1)` to `2^i - 1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Histogram {
+    /// Values per bucket, up to the last non-empty one.
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum: u64,
+    pub max: u64,
+}
+
+impl Histogram {
+    pub fn record(&mut self, value: u64) {
+        let bucket = (u64::BITS - value.leading_zeros()) as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.max = self.max.max(value);
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum as f64 / self.count as f64)
+    }
+
+    // largest value of a bucket
+    fn upper_bound(bucket: usize) -> u64 {
+        if bucket == 0 {
+            0
+        } else {
+            u64::MAX >> (u64::BITS as usize - bucket)
+        }
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile (0.5 for the median), `None` without values.
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Self::upper_bound(bucket).min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DroneStats, Histogram};
+
+    // the bucket a single value lands in
+    fn bucket_of(value: u64) -> usize {
+        let mut histogram = Histogram::default();
+        histogram.record(value);
+        histogram.buckets.len() - 1
+    }
+
+    #[test]
+    fn values_land_in_the_bucket_of_their_upper_bound() {
+        let cases = [(0, 0), (1, 1), (2, 2), (3, 2), (4, 3), (7, 3), (8, 4), (1023, 10), (1024, 11), (u64::MAX, 64)];
+        for (value, bucket) in cases {
+            assert_eq!(bucket_of(value), bucket, "{}", value);
+            assert!(value <= Histogram::upper_bound(bucket), "{}", value);
+            if bucket > 0 {
+                assert!(value > Histogram::upper_bound(bucket - 1), "{}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn upper_bounds_are_powers_of_two_minus_one() {
+        let cases = [(0, 0), (1, 1), (2, 3), (3, 7), (10, 1023), (63, u64::MAX >> 1), (64, u64::MAX)];
+        for (bucket, upper_bound) in cases {
+            assert_eq!(Histogram::upper_bound(bucket), upper_bound, "{}", bucket);
+        }
+    }
+
+    #[test]
+    fn quantile_is_capped_by_the_max() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+        for value in [0, 1, 5, 5] {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.buckets, vec![1, 1, 0, 2]);
+        assert_eq!(histogram.quantile(0.25), Some(0));
+        assert_eq!(histogram.quantile(0.5), Some(1));
+        assert_eq!(histogram.quantile(1.0), Some(5));
+        assert_eq!(histogram.mean(), Some(2.75));
+    }
+
+    #[test]
+    fn reset_clears_the_histograms() {
+        let mut stats = DroneStats::default();
+        stats.flood_fan_out.record(3);
+        stats.flood_response_latency.record(1500);
+        stats.reset();
+        assert_eq!(stats.flood_fan_out, Histogram::default());
+        assert_eq!(stats.flood_response_latency, Histogram::default());
+    }
+}
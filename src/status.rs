@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::Serialize;
+use wg_2024::network::NodeId;
+
+use crate::stats::DroneStats;
+
+// how often the server checks whether it was stopped
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+// requests are a single line, the headers are read and ignored
+const MAX_REQUEST_LINE: usize = 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+// statistics of every drone started with spawn in this process, dropped with their handle
+static SPAWNED: Mutex<Vec<(NodeId, Weak<Mutex<DroneStats>>)>> = Mutex::new(Vec::new());
+
+pub(crate) fn register(drone_id: NodeId, stats: &Arc<Mutex<DroneStats>>) {
+    spawned().push((drone_id, Arc::downgrade(stats)));
+}
+
+fn spawned() -> MutexGuard<'static, Vec<(NodeId, Weak<Mutex<DroneStats>>)>> {
+    SPAWNED.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Statistics of a drone, as served by the status server.
+#[derive(Debug, Clone, Serialize)]
+pub struct DroneStatus {
+    pub id: NodeId,
+    pub stats: DroneStats,
+}
+
+/// Links between the spawned drones and their neighbors, as served by the status server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Topology {
+    /// Sorted.
+    pub drones: Vec<NodeId>,
+    /// Drones and their neighbors, clients and servers included, sorted.
+    pub nodes: Vec<NodeId>,
+    /// `[a, b]` with `a < b`, once per pair of nodes where at least one has a sender to the other.
+    pub links: Vec<(NodeId, NodeId)>,
+}
+
+/// Statistics of the drones started with `spawn` in this process that still have a handle, by id.
+pub fn spawned_drones() -> Vec<DroneStatus> {
+    let mut spawned = spawned();
+    spawned.retain(|(_, stats)| stats.strong_count() > 0);
+    let mut drones: Vec<DroneStatus> = spawned
+        .iter()
+        .filter_map(|(id, stats)| {
+            let stats = stats.upgrade()?.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+            Some(DroneStatus { id: *id, stats })
+        })
+        .collect();
+    drones.sort_by_key(|drone| drone.id);
+    drones
+}
+
+impl Topology {
+    /// Topology formed by these drones, see `spawned_drones`.
+    pub fn of(drones: &[DroneStatus]) -> Self {
+        let mut nodes = BTreeSet::new();
+        let mut links = BTreeSet::new();
+        for drone in drones {
+            nodes.insert(drone.id);
+            for neighbor in &drone.stats.neighbor_ids {
+                nodes.insert(*neighbor);
+                links.insert((drone.id.min(*neighbor), drone.id.max(*neighbor)));
+            }
+        }
+        Self {
+            drones: drones.iter().map(|drone| drone.id).collect(),
+            nodes: nodes.into_iter().collect(),
+            links: links.into_iter().collect(),
+        }
+    }
+}
+
+/// A tiny HTTP server with the state of every drone spawned in the process, for dashboards:
+///
+/// - `GET /stats`: `[{"id": 1, "stats": {...}}, ...]`
+/// - `GET /stats/<id>`: `{"id": 1, "stats": {...}}`
+/// - `GET /topology`: `{"drones": [...], "nodes": [...], "links": [[1, 2], ...]}`
+/// - `GET /`: `{"drones": <stats>, "topology": <topology>}`
+///
+/// Responses allow any origin, so that a page opened from a file can fetch them. Stops when dropped.
+pub struct StatusServer {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StatusServer {
+    /// Listens on `addr`, port 0 picks a free one (see `local_addr`).
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new().name("rwa-status".to_string()).spawn({
+            let stop = stop.clone();
+            move || accept(listener, &stop)
+        })?;
+        Ok(Self { local_addr, stop, thread: Some(thread) })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn accept(listener: TcpListener, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            // a slow client only delays the others, requests are small
+            Ok((stream, _)) => {
+                let _ = respond(stream);
+            }
+            // WouldBlock when nobody is connecting, other errors are about a single connection
+            Err(_) => thread::sleep(ACCEPT_INTERVAL),
+        }
+    }
+}
+
+fn respond(stream: TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.by_ref().take(MAX_REQUEST_LINE as u64).read_line(&mut request_line)?;
+    // skip the headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut stream = reader.into_inner();
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let (status, body) = if method != "GET" {
+        ("405 Method Not Allowed", None)
+    } else {
+        let drones = spawned_drones();
+        match path.trim_end_matches('/') {
+            "" => (
+                "200 OK",
+                serde_json::to_string(&Status { topology: Topology::of(&drones), drones }).ok(),
+            ),
+            "/stats" => ("200 OK", serde_json::to_string(&drones).ok()),
+            "/topology" => ("200 OK", serde_json::to_string(&Topology::of(&drones)).ok()),
+            path => match path.strip_prefix("/stats/").and_then(|id| id.parse::<NodeId>().ok()) {
+                Some(id) => match drones.iter().find(|drone| drone.id == id) {
+                    Some(drone) => ("200 OK", serde_json::to_string(drone).ok()),
+                    None => ("404 Not Found", None),
+                },
+                None => ("404 Not Found", None),
+            },
+        }
+    };
+    let (status, body) = match (status, body) {
+        ("200 OK", None) => ("500 Internal Server Error", String::new()),
+        (status, body) => (status, body.unwrap_or_default()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[derive(Serialize)]
+struct Status {
+    topology: Topology,
+    drones: Vec<DroneStatus>,
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use wg_2024::network::NodeId;
+
+use crate::drone::RustaceansWitAttitudesDrone;
+
+/// Runs drones on the calling thread, one input at a time, for the targets without threads (wasm32)
+/// or a caller that wants to decide when the drones do their work.
+///
+/// Nothing blocks: `step` handles what is already in the channels and the timers that are due, then returns.
+/// A page in the browser calls it from its animation frame or a timer, see `next_wake` for how long it
+/// can wait when the drones are idle. Drones that shut down are removed.
+#[derive(Default)]
+pub struct Stepper {
+    drones: Vec<RustaceansWitAttitudesDrone>,
+}
+
+impl Stepper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start `drone`, as `run` would, without its thread.
+    pub fn add(&mut self, mut drone: RustaceansWitAttitudesDrone) {
+        drone.start();
+        self.drones.push(drone);
+    }
+
+    /// One input of each drone in turn, and the timers that are due. False if they were all idle.
+    pub fn step(&mut self) -> bool {
+        let busy = self.drones.iter_mut().fold(false, |busy, drone| drone.step() | busy);
+        self.drones.retain(|drone| !drone.is_shut_down());
+        busy
+    }
+
+    /// One input of the drone `id` only, and its timers that are due. False if it was idle or isn't running.
+    ///
+    /// Choosing the drone to step from a seeded generator lets a test replay any interleaving of the network.
+    pub fn step_drone(&mut self, id: NodeId) -> bool {
+        let Some(index) = self.drones.iter().position(|drone| drone.id() == id) else {
+            return false;
+        };
+        let busy = self.drones[index].step();
+        if self.drones[index].is_shut_down() {
+            self.drones.remove(index);
+        }
+        busy
+    }
+
+    /// Step until every drone is idle, returns the number of steps that had something to do.
+    ///
+    /// Drones forwarding packets to each other in a loop never get idle, use `step` if that can happen.
+    pub fn run_until_idle(&mut self) -> usize {
+        let mut steps = 0;
+        while self.step() {
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Time until the next timer of a drone (delayed packets, scenario steps, drain timeouts),
+    /// in the time of their clocks. `None` if they have none, new inputs can come at any time.
+    pub fn next_wake(&self) -> Option<Duration> {
+        self.drones.iter().filter_map(RustaceansWitAttitudesDrone::time_to_wake).min()
+    }
+
+    /// The drones still running, in the order they were added.
+    pub fn drones(&self) -> &[RustaceansWitAttitudesDrone] {
+        &self.drones
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.drones.is_empty()
+    }
+}
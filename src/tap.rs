@@ -0,0 +1,40 @@
+use crossbeam_channel::Sender;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::time::Duration;
+
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+// Where the drone mirrors the packets it sends to its neighbors.
+pub(crate) enum Tap {
+    Channel(Sender<Packet>),
+    // one line per packet: "<time since start> <drone> -> <neighbor> <packet>"
+    Capture(RefCell<LineWriter<File>>),
+    // packets with the neighbor they were sent to, collected by replays
+    #[cfg(feature = "record")]
+    Routed(Sender<(NodeId, Packet)>),
+}
+
+impl Tap {
+    pub(crate) fn capture(file: File) -> Self {
+        Tap::Capture(RefCell::new(LineWriter::new(file)))
+    }
+
+    pub(crate) fn mirror(&self, elapsed: Duration, from: NodeId, to: NodeId, packet: &Packet) {
+        // a tap never changes what the drone does, failures are ignored
+        match self {
+            Tap::Channel(sender) => {
+                let _ = sender.send(packet.clone());
+            }
+            Tap::Capture(writer) => {
+                let _ = writeln!(writer.borrow_mut(), "{:?} {} -> {} {:?}", elapsed, from, to, packet);
+            }
+            #[cfg(feature = "record")]
+            Tap::Routed(sender) => {
+                let _ = sender.send((to, packet.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::mem;
+use std::time::Duration;
+
+use web_time::Instant;
+
+// Width of a slot, and number of slots: one turn of the wheel covers 256 ms.
+const TICK: Duration = Duration::from_millis(1);
+const SLOTS: usize = 256;
+
+// Items keyed by release time, for the packets held back by delays (link profiles, chaos).
+// Scheduling is O(1): an item goes to the slot of its tick, items more than a turn away stay in their slot until
+// the wheel comes around on their turn. Expiring only visits the slots of the ticks since the last call.
+#[derive(Debug)]
+pub(crate) struct TimerWheel<T> {
+    slots: Vec<Vec<(Instant, T)>>,
+    // time of tick 0, set by the first item
+    start: Option<Instant>,
+    // ticks before this one are empty
+    cursor: u64,
+    len: usize,
+}
+
+impl<T> Default for TimerWheel<T> {
+    fn default() -> Self {
+        Self { slots: (0..SLOTS).map(|_| Vec::new()).collect(), start: None, cursor: 0, len: 0 }
+    }
+}
+
+impl<T> TimerWheel<T> {
+    pub(crate) fn insert(&mut self, at: Instant, item: T) {
+        let start = *self.start.get_or_insert(at);
+        // already due: expired with the current tick
+        let tick = tick_of(start, at).max(self.cursor);
+        self.slots[slot_of(tick)].push((at, item));
+        self.len += 1;
+    }
+
+    // the items due by `now`, in no particular order
+    pub(crate) fn expire(&mut self, now: Instant) -> Vec<(Instant, T)> {
+        let Some(start) = self.start else {
+            return Vec::new();
+        };
+        let mut due = Vec::new();
+        if self.len == 0 {
+            self.cursor = self.cursor.max(tick_of(start, now));
+            return due;
+        }
+        let now_tick = tick_of(start, now);
+        // a whole turn visits every slot
+        let last = now_tick.min(self.cursor + SLOTS as u64 - 1);
+        for tick in self.cursor..=last {
+            let slot = &mut self.slots[slot_of(tick)];
+            if slot.iter().all(|(at, _)| *at > now) {
+                continue;
+            }
+            let (expired, pending): (Vec<_>, Vec<_>) = mem::take(slot).into_iter().partition(|(at, _)| *at <= now);
+            *slot = pending;
+            due.extend(expired);
+        }
+        // the current tick may still get items due later in it
+        self.cursor = self.cursor.max(now_tick);
+        self.len -= due.len();
+        due
+    }
+
+    // release time of the next item
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        if self.len == 0 {
+            return None;
+        }
+        // the first slot holding an item of this turn
+        for tick in self.cursor..self.cursor + SLOTS as u64 {
+            let turn = self.slots[slot_of(tick)].iter().map(|(at, _)| *at).filter(|at| self.tick(*at) <= tick).min();
+            if turn.is_some() {
+                return turn;
+            }
+        }
+        // only items more than a turn away
+        self.iter().map(|(at, _)| *at).min()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &(Instant, T)> {
+        self.slots.iter().flatten()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn tick(&self, at: Instant) -> u64 {
+        self.start.map_or(0, |start| tick_of(start, at))
+    }
+}
+
+fn tick_of(start: Instant, at: Instant) -> u64 {
+    (at.saturating_duration_since(start).as_nanos() / TICK.as_nanos()) as u64
+}
+
+fn slot_of(tick: u64) -> usize {
+    (tick % SLOTS as u64) as usize
+}
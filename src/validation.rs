@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Packet, PacketType, FRAGMENT_DSIZE};
+
+use crate::checksum;
+use crate::event::Diagnostic;
+
+// Violations that make the header unusable: the packet can't be forwarded nor Nacked back.
+pub(crate) fn check_routing_header(header: &SourceRoutingHeader) -> Result<(), Diagnostic> {
+    if header.hops.is_empty() {
+        return Err(Diagnostic::EmptyRoute);
+    }
+    if header.hop_index >= header.hops.len() {
+        return Err(Diagnostic::HopIndexOutOfBounds {
+            hop_index: header.hop_index,
+            hops_len: header.hops.len(),
+        });
+    }
+    if header.hops.len() == 1 {
+        return Err(Diagnostic::ZeroLengthRoute);
+    }
+    if header.hop_index == 0 {
+        return Err(Diagnostic::HopIndexNotIncremented);
+    }
+    Ok(())
+}
+
+// Node the packet claims to come from, before the header is checked.
+pub(crate) fn sender_of(packet: &Packet) -> Option<NodeId> {
+    match &packet.pack_type {
+        PacketType::FloodRequest(flood_request) => flood_request.path_trace.last().map(|(node_id, _)| *node_id),
+        _ => packet.routing_header.hop_index.checked_sub(1).and_then(|index| packet.routing_header.hops.get(index).copied()),
+    }
+}
+
+// Suspicious but not fatal: the packet can still be handled.
+pub(crate) fn find_duplicate_hop(hops: &[NodeId]) -> Option<NodeId> {
+    let mut seen = HashSet::new();
+    hops.iter().find(|hop| !seen.insert(**hop)).cloned()
+}
+
+// The rest of the route must not come back to us nor visit a node twice.
+pub(crate) fn find_routing_loop(id: NodeId, remaining: &[NodeId]) -> Option<NodeId> {
+    if remaining.contains(&id) {
+        return Some(id);
+    }
+    find_duplicate_hop(remaining)
+}
+
+pub(crate) fn check_fragment(fragment: &Fragment) -> Result<(), Diagnostic> {
+    if fragment.length as usize > FRAGMENT_DSIZE {
+        return Err(Diagnostic::FragmentTooLong { length: fragment.length });
+    }
+    if fragment.fragment_index >= fragment.total_n_fragments {
+        return Err(Diagnostic::FragmentIndexOutOfRange {
+            fragment_index: fragment.fragment_index,
+            total_n_fragments: fragment.total_n_fragments,
+        });
+    }
+    Ok(())
+}
+
+// Fragments too long to carry a checksum pass.
+pub(crate) fn check_checksum(fragment: &Fragment) -> Result<(), Diagnostic> {
+    let Some(carried) = checksum::carried(fragment) else {
+        return Ok(());
+    };
+    let computed = checksum::of(&fragment.data[..fragment.length as usize]);
+    if carried != computed {
+        return Err(Diagnostic::ChecksumMismatch { carried, computed });
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crossbeam_channel::Sender;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+use crate::event::{AuxEvent, AuxEventKind, Diagnostic};
+
+/// A malformed packet the drone received, with the node it claims to come from.
+#[derive(Debug, Clone)]
+pub struct ProtocolViolation {
+    pub drone_id: NodeId,
+    /// Time since the drone started.
+    pub at: Duration,
+    /// The previous hop of the route (the last node of the path trace for a FloodRequest), `None` if the
+    /// header doesn't say.
+    pub from: Option<NodeId>,
+    pub diagnostic: Diagnostic,
+    /// The packet as it was received.
+    pub packet: Packet,
+}
+
+impl fmt::Display for ProtocolViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {} <- ", self.at, self.drone_id)?;
+        match self.from {
+            Some(from) => write!(f, "{}", from)?,
+            None => write!(f, "?")?,
+        }
+        write!(f, " {:?} {:?}", self.diagnostic, self.packet)
+    }
+}
+
+/// Where the drone reports the malformed packets it receives, see `with_violation_sink`.
+/// A sink never changes what the drone does with the packet.
+pub trait ViolationSink: Send {
+    fn report(&mut self, violation: &ProtocolViolation);
+}
+
+/// Any closure.
+impl<F: FnMut(&ProtocolViolation) + Send> ViolationSink for F {
+    fn report(&mut self, violation: &ProtocolViolation) {
+        self(violation)
+    }
+}
+
+/// The auxiliary channel of the controller, as `AuxEventKind::ProtocolViolation`.
+impl ViolationSink for Sender<AuxEvent> {
+    fn report(&mut self, violation: &ProtocolViolation) {
+        let _ = self.send(AuxEvent { drone_id: violation.drone_id, kind: AuxEventKind::ProtocolViolation(violation.clone()) });
+    }
+}
+
+/// A file with one line per violation: "<time since start> <drone> <- <previous hop> <diagnostic> <packet>".
+pub struct ViolationLog(LineWriter<File>);
+
+impl ViolationLog {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(ViolationLog(LineWriter::new(File::create(path)?)))
+    }
+}
+
+impl ViolationSink for ViolationLog {
+    fn report(&mut self, violation: &ProtocolViolation) {
+        // failures are ignored, like the other logs of the drone
+        let _ = writeln!(self.0, "{}", violation);
+    }
+}
@@ -0,0 +1,83 @@
+use crossbeam_channel::Sender;
+use std::io;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use wg_2024::network::NodeId;
+
+use crate::event::{AuxEvent, AuxEventKind};
+
+// Shared between the drone and the thread watching it. The drone marks itself busy while it
+// handles an input, waiting for the next input is not a stall.
+pub(crate) struct Watchdog {
+    busy_since: Mutex<Option<Instant>>,
+}
+
+// Marks the drone busy until dropped.
+pub(crate) struct Busy(Arc<Watchdog>);
+
+impl Watchdog {
+    // The thread ends with the drone (or when nobody listens to the events anymore).
+    // Stalls are measured in real time, whatever the clock of the drone.
+    pub(crate) fn spawn(id: NodeId, deadline: Duration, aux_send: Sender<AuxEvent>) -> io::Result<Arc<Self>> {
+        let watchdog = Arc::new(Self { busy_since: Mutex::new(None) });
+        let watched = Arc::downgrade(&watchdog);
+        thread::Builder::new()
+            .name(format!("rwa-drone-{}-watchdog", id))
+            .spawn(move || watch(id, deadline, watched, aux_send))?;
+        Ok(watchdog)
+    }
+
+    // None if already busy, only the outermost input counts
+    pub(crate) fn busy(self: &Arc<Self>) -> Option<Busy> {
+        let mut busy_since = self.lock();
+        if busy_since.is_some() {
+            return None;
+        }
+        *busy_since = Some(Instant::now());
+        Some(Busy(self.clone()))
+    }
+
+    fn busy_since(&self) -> Option<Instant> {
+        *self.lock()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Instant>> {
+        self.busy_since.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        *self.0.lock() = None;
+    }
+}
+
+fn watch(id: NodeId, deadline: Duration, watched: Weak<Watchdog>, aux_send: Sender<AuxEvent>) {
+    let period = (deadline / 4).max(Duration::from_millis(1));
+    // start of the input the drone is stuck on
+    let mut stalled_on: Option<Instant> = None;
+    loop {
+        thread::sleep(period);
+        let busy_since = match watched.upgrade() {
+            Some(watchdog) => watchdog.busy_since(),
+            None => return,
+        };
+        let kind = match (busy_since, stalled_on) {
+            (Some(since), None) if since.elapsed() >= deadline => {
+                stalled_on = Some(since);
+                AuxEventKind::Stalled { busy_for: since.elapsed() }
+            }
+            // done with that input, maybe busy with the next one already
+            (busy_since, Some(since)) if busy_since != Some(since) => {
+                stalled_on = None;
+                AuxEventKind::Recovered { stalled_for: since.elapsed() }
+            }
+            _ => continue,
+        };
+        if aux_send.send(AuxEvent { drone_id: id, kind }).is_err() {
+            return;
+        }
+    }
+}
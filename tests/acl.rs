@@ -0,0 +1,75 @@
+// Packet filtering rules: drone 1 linked to nodes 10, 2 and 3, the packets come from 10 unless said otherwise.
+mod common;
+
+use common::{ack, fragment, mock_network_with, MockNetwork, TIMEOUT};
+use rustaceans_wit_attitudes::{Acl, AclAction, AclRule, PacketKind};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+fn filtered(acl: Acl) -> MockNetwork {
+    mock_network_with(1, &[10, 2, 3], |drone| drone.with_acl(acl))
+}
+
+// fragment of `session_id` from 10 to `to`
+fn fragment_of(session_id: u64, to: NodeId) -> Packet {
+    let mut packet = fragment(&[10, 1, to], 1);
+    packet.session_id = session_id;
+    packet
+}
+
+// the PacketSent of the packets let through come first
+fn expect_dropped(network: &MockNetwork) {
+    loop {
+        match network.event_recv.recv_timeout(TIMEOUT).expect("no PacketDropped") {
+            DroneEvent::PacketSent(_) => {}
+            DroneEvent::PacketDropped(_) => return,
+            event => panic!("expected PacketDropped, got {:?}", event),
+        }
+    }
+}
+
+#[test]
+fn a_nack_rule_nacks_fragments_towards_the_destination() {
+    let network = filtered(Acl::new().rule(AclRule::new(AclAction::Nack).to(3)));
+    network.send(fragment_of(7, 3));
+    let packet = network.expect(10);
+    assert!(matches!(&packet.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(1))));
+    network.expect_nothing(3);
+
+    network.send(fragment_of(7, 2));
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+}
+
+#[test]
+fn the_first_matching_rule_decides() {
+    let acl = Acl::new()
+        .rule(AclRule::new(AclAction::Allow).sessions(0..=9))
+        .rule(AclRule::new(AclAction::Drop).packet_type(PacketKind::Fragment));
+    let network = filtered(acl);
+    network.send(fragment_of(5, 2));
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+
+    network.send(fragment_of(10, 2));
+    expect_dropped(&network);
+    network.expect_nothing(2);
+    network.expect_nothing(10);
+    assert_eq!(network.handle.stats().packets_filtered, 1);
+}
+
+#[test]
+fn packets_that_cant_be_nacked_are_dropped() {
+    let network = filtered(Acl::new().rule(AclRule::new(AclAction::Nack).from(2)));
+    network.send(ack(&[3, 2, 1, 10], 2));
+    expect_dropped(&network);
+    network.expect_nothing(10);
+    network.expect_nothing(3);
+}
+
+#[test]
+fn the_rules_can_be_replaced_at_runtime() {
+    let network = filtered(Acl::new().rule(AclRule::new(AclAction::Drop)));
+    network.handle.set_acl(Acl::default());
+    network.send(fragment_of(7, 2));
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+}
@@ -0,0 +1,136 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rustaceans_wit_attitudes::{
+    spawn, AuxEvent, AuxEventKind, BrokenHeaderPolicy, Diagnostic, DroneHandle, DroneOptions, RustaceansWitAttitudesDrone,
+};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, Packet, PacketType};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// drone 1 between nodes 0 and 2
+struct Line {
+    handle: DroneHandle,
+    packet_send: Sender<Packet>,
+    command_send: Sender<DroneCommand>,
+    event_recv: Receiver<DroneEvent>,
+    aux_recv: Receiver<AuxEvent>,
+    from_0: Receiver<Packet>,
+    from_2: Receiver<Packet>,
+}
+
+fn line(broken_header_policy: BrokenHeaderPolicy) -> Line {
+    let (event_send, event_recv) = unbounded();
+    let (command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (aux_send, aux_recv) = unbounded();
+    let (send_0, from_0) = unbounded();
+    let (send_2, from_2) = unbounded();
+    let neighbors = HashMap::from([(0, send_0), (2, send_2)]);
+    let drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, neighbors, 0.0)
+        .with_options(DroneOptions { broken_header_policy, ..Default::default() })
+        .with_aux_sender(aux_send);
+    let handle = spawn(drone).unwrap();
+    Line { handle, packet_send, command_send, event_recv, aux_recv, from_0, from_2 }
+}
+
+fn fragment(hop_index: usize) -> Packet {
+    let fragment = Fragment { fragment_index: 0, total_n_fragments: 1, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader { hop_index, hops: vec![0, 1, 2] }, 7, fragment)
+}
+
+fn ack(hop_index: usize) -> Packet {
+    Packet::new_ack(SourceRoutingHeader { hop_index, hops: vec![2, 1, 0] }, 7, 0)
+}
+
+impl Line {
+    fn send(&self, packet: Packet) {
+        self.packet_send.send(packet).unwrap();
+    }
+
+    fn diagnostic(&self) -> Diagnostic {
+        loop {
+            let event = self.aux_recv.recv_timeout(TIMEOUT).expect("no diagnostic");
+            if let AuxEventKind::Diagnostic { diagnostic, .. } = event.kind {
+                return diagnostic;
+            }
+        }
+    }
+
+    // the drone still forwards a well-formed fragment after the broken packet
+    fn assert_still_forwards(&self) {
+        self.send(fragment(1));
+        let packet = self.from_2.recv_timeout(TIMEOUT).expect("fragment not forwarded");
+        assert!(matches!(packet.pack_type, PacketType::MsgFragment(_)));
+        assert!(!self.handle.is_finished());
+    }
+
+    fn shortcuts(&self) -> usize {
+        self.event_recv.try_iter().filter(|event| matches!(event, DroneEvent::ControllerShortcut(_))).count()
+    }
+}
+
+#[test]
+fn hop_index_past_the_end_is_reported_and_dropped() {
+    let line = line(BrokenHeaderPolicy::Drop);
+    line.send(fragment(5));
+    assert_eq!(line.diagnostic(), Diagnostic::HopIndexOutOfBounds { hop_index: 5, hops_len: 3 });
+    line.assert_still_forwards();
+    assert!(line.from_0.try_recv().is_err());
+    assert_eq!(line.handle.stats().broken_headers, 1);
+}
+
+#[test]
+fn hop_index_one_past_the_last_hop_is_reported() {
+    let line = line(BrokenHeaderPolicy::Drop);
+    line.send(ack(3));
+    assert_eq!(line.diagnostic(), Diagnostic::HopIndexOutOfBounds { hop_index: 3, hops_len: 3 });
+    line.assert_still_forwards();
+    assert_eq!(line.shortcuts(), 0);
+}
+
+#[test]
+fn hop_index_still_at_the_sender_is_reported_and_dropped() {
+    let line = line(BrokenHeaderPolicy::Drop);
+    line.send(fragment(0));
+    assert_eq!(line.diagnostic(), Diagnostic::HopIndexNotIncremented);
+    line.assert_still_forwards();
+    assert!(line.from_0.try_recv().is_err());
+    assert_eq!(line.handle.stats().broken_headers, 1);
+}
+
+#[test]
+fn broken_acks_are_shortcut_to_the_controller() {
+    let line = line(BrokenHeaderPolicy::Shortcut);
+    line.send(ack(3));
+    line.diagnostic();
+    line.assert_still_forwards();
+    assert_eq!(line.shortcuts(), 1);
+}
+
+#[test]
+fn broken_fragments_are_never_shortcut() {
+    let line = line(BrokenHeaderPolicy::Shortcut);
+    line.send(fragment(3));
+    line.diagnostic();
+    line.assert_still_forwards();
+    assert_eq!(line.shortcuts(), 0);
+}
+
+#[test]
+fn a_crashing_drone_survives_a_broken_header() {
+    let line = line(BrokenHeaderPolicy::Shortcut);
+    line.command_send.send(DroneCommand::Crash).unwrap();
+    line.send(ack(3));
+    assert_eq!(line.diagnostic(), Diagnostic::HopIndexOutOfBounds { hop_index: 3, hops_len: 3 });
+    // a fragment sent to a crashing drone is Nacked back
+    line.send(fragment(1));
+    let packet = line.from_0.recv_timeout(TIMEOUT).expect("fragment not Nacked");
+    assert!(matches!(packet.pack_type, PacketType::Nack(_)));
+    assert!(!line.handle.is_finished());
+    assert_eq!(line.shortcuts(), 1);
+}
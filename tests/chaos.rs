@@ -0,0 +1,77 @@
+// Chaos options on the Nacks a drone sends, the drone driven synchronously: drone 1 between node 10 and node 2.
+use crossbeam_channel::{unbounded, Receiver};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rustaceans_wit_attitudes::internals;
+use rustaceans_wit_attitudes::{ChaosOptions, DroneOptions, RustaceansWitAttitudesDrone};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+struct Harness {
+    drone: RustaceansWitAttitudesDrone,
+    from_10: Receiver<Packet>,
+}
+
+// every Nack is misreported with `probability`
+fn harness(probability: f64) -> Harness {
+    let chaos = ChaosOptions {
+        probability,
+        corrupt_path_traces: false,
+        reorder_hops: false,
+        delay_acks: false,
+        ack_delay: Duration::ZERO,
+        misreport_nacks: true,
+    };
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let (send_10, from_10) = unbounded();
+    let (send_2, _from_2) = unbounded();
+    let senders = HashMap::from([(10, send_10), (2, send_2)]);
+    let options = DroneOptions { chaos: Some(chaos), seed: Some(7), ..DroneOptions::default() };
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0).with_options(options);
+    internals::start(&mut drone);
+    Harness { drone, from_10 }
+}
+
+// fragment 0 of `session_id`, from 10 to 2
+fn fragment(session_id: u64) -> Packet {
+    let fragment = Fragment { fragment_index: 0, total_n_fragments: 1, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, 2], 1), session_id, fragment)
+}
+
+// Nacks(Dropped) for 20 sessions, the number of them that came back with another type
+fn misreported(h: &mut Harness) -> u64 {
+    for session_id in 0..20 {
+        internals::send_nack(&mut h.drone, &fragment(session_id), NackType::Dropped);
+    }
+    let nacks: Vec<Packet> = h.from_10.try_iter().collect();
+    assert_eq!(nacks.len(), 20);
+    let dropped = |packet: &&Packet| matches!(&packet.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::Dropped));
+    nacks.iter().filter(|packet| !dropped(packet)).count() as u64
+}
+
+#[test]
+fn every_misreported_nack_is_a_misbehavior() {
+    let mut h = harness(0.5);
+    let misreported = misreported(&mut h);
+    assert_eq!(h.drone.stats().misbehaviors, misreported);
+}
+
+#[test]
+fn certain_chaos_misreports_every_nack() {
+    let mut h = harness(1.0);
+    assert_eq!(misreported(&mut h), 20);
+    assert_eq!(h.drone.stats().misbehaviors, 20);
+}
+
+#[test]
+fn zero_or_nan_probability_never_misbehaves() {
+    for probability in [0.0, f64::NAN] {
+        let mut h = harness(probability);
+        assert_eq!(misreported(&mut h), 0);
+        assert_eq!(h.drone.stats().misbehaviors, 0);
+    }
+}
@@ -0,0 +1,94 @@
+// Timed behaviours on a ManualClock, the drone run by a Stepper on the test's thread so that nothing depends on the
+// real time: drone 1 between node 10 and node 2.
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rustaceans_wit_attitudes::{DroneOptions, ManualClock, RustaceansWitAttitudesDrone, Scenario, ScenarioAction, Stepper};
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+struct Harness {
+    stepper: Stepper,
+    clock: ManualClock,
+    packet_send: Sender<Packet>,
+    neighbors: HashMap<NodeId, Receiver<Packet>>,
+}
+
+impl Harness {
+    fn drone(&self) -> &RustaceansWitAttitudesDrone {
+        &self.stepper.drones()[0]
+    }
+
+    fn handle(&mut self, packet: Packet) {
+        self.packet_send.send(packet).unwrap();
+        self.stepper.run_until_idle();
+    }
+
+    fn received(&self, node_id: NodeId) -> Vec<Packet> {
+        self.neighbors[&node_id].try_iter().collect()
+    }
+}
+
+fn harness(options: DroneOptions, scenario: Scenario) -> Harness {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let mut senders = HashMap::new();
+    let mut neighbors = HashMap::new();
+    for node_id in [10, 2] {
+        let (send, recv) = unbounded();
+        senders.insert(node_id, send);
+        neighbors.insert(node_id, recv);
+    }
+    let clock = ManualClock::new();
+    let drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_options(options)
+        .with_clock(clock.clone())
+        .with_scenario(scenario);
+    let mut stepper = Stepper::new();
+    stepper.add(drone);
+    Harness { stepper, clock, packet_send, neighbors }
+}
+
+// fragment 0 of session 7 for node 9, which drone 1 has no link to
+fn misrouted() -> Packet {
+    let fragment = Fragment { fragment_index: 0, total_n_fragments: 1, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, 9], 1), 7, fragment)
+}
+
+#[test]
+fn timed_scenario_step_runs_once_the_clock_gets_there() {
+    let mut h = harness(DroneOptions::default(), Scenario::new().at(Duration::from_secs(10), ScenarioAction::SetPdr(1.0)));
+    assert_eq!(h.stepper.next_wake(), Some(Duration::from_secs(10)));
+
+    h.clock.advance(Duration::from_millis(9_999));
+    h.stepper.step();
+    assert_eq!(h.drone().pdr(), 0.0);
+    assert_eq!(h.stepper.next_wake(), Some(Duration::from_millis(1)));
+
+    h.clock.advance(Duration::from_millis(1));
+    h.stepper.step();
+    assert_eq!(h.drone().pdr(), 1.0);
+    assert_eq!(h.stepper.next_wake(), None);
+}
+
+#[test]
+fn repeated_nack_is_suppressed_until_the_dedup_window_is_over() {
+    let options = DroneOptions { nack_dedup_window: Some(Duration::from_secs(1)), ..DroneOptions::default() };
+    let mut h = harness(options, Scenario::new());
+    h.handle(misrouted());
+    h.clock.advance(Duration::from_millis(999));
+    h.handle(misrouted());
+    let nacks = h.received(10);
+    assert_eq!(nacks.len(), 1);
+    assert!(matches!(&nacks[0].pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(_))));
+    assert_eq!(h.drone().stats().nacks_suppressed, 1);
+
+    // a window after the first one
+    h.clock.advance(Duration::from_millis(1));
+    h.handle(misrouted());
+    assert_eq!(h.received(10).len(), 1);
+    assert_eq!(h.drone().stats().nacks_suppressed, 1);
+}
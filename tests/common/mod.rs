@@ -0,0 +1,182 @@
+// Mock utilities shared by the integration tests: a drone under test, spawned with mock neighbors and a mock
+// simulation controller whose channels the test reads and writes by hand.
+#![allow(dead_code)] // each test uses its own part
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rustaceans_wit_attitudes::{spawn, DroneHandle, DroneOptions, DroneState, RustaceansWitAttitudesDrone};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, FloodRequest, FloodResponse, Nack, NackType, NodeType, Packet};
+
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+// how long nothing has to come for `expect_nothing`
+pub const QUIET: Duration = Duration::from_millis(100);
+
+pub struct MockNetwork {
+    pub handle: DroneHandle,
+    pub command_send: Sender<DroneCommand>,
+    pub event_recv: Receiver<DroneEvent>,
+    pub packet_send: Sender<Packet>,
+    neighbors: HashMap<NodeId, Receiver<Packet>>,
+}
+
+// Drone `id` with pdr 0, linked to a mock node for each of `neighbor_ids`.
+pub fn mock_network(id: NodeId, neighbor_ids: &[NodeId], options: DroneOptions) -> MockNetwork {
+    mock_network_with(id, neighbor_ids, |drone| drone.with_options(options))
+}
+
+// Same as `mock_network`, `build` sets up the drone before it's spawned.
+pub fn mock_network_with(
+    id: NodeId,
+    neighbor_ids: &[NodeId],
+    build: impl FnOnce(RustaceansWitAttitudesDrone) -> RustaceansWitAttitudesDrone,
+) -> MockNetwork {
+    let (event_send, event_recv) = unbounded();
+    let (command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let mut senders = HashMap::new();
+    let mut neighbors = HashMap::new();
+    for node_id in neighbor_ids {
+        let (send, recv) = unbounded();
+        senders.insert(*node_id, send);
+        neighbors.insert(*node_id, recv);
+    }
+    let drone = build(RustaceansWitAttitudesDrone::new(id, event_send, command_recv, packet_recv, senders, 0.0));
+    let handle = spawn(drone).unwrap();
+    MockNetwork { handle, command_send, event_recv, packet_send, neighbors }
+}
+
+impl MockNetwork {
+    pub fn send(&self, packet: Packet) {
+        self.packet_send.send(packet).unwrap();
+    }
+
+    pub fn command(&self, command: DroneCommand) {
+        self.command_send.send(command).unwrap();
+    }
+
+    /// Next packet the drone sent to the mock node `node_id`.
+    pub fn expect(&self, node_id: NodeId) -> Packet {
+        self.neighbors[&node_id].recv_timeout(TIMEOUT).unwrap_or_else(|_| panic!("nothing sent to {}", node_id))
+    }
+
+    pub fn expect_nothing(&self, node_id: NodeId) {
+        if let Ok(packet) = self.neighbors[&node_id].recv_timeout(QUIET) {
+            panic!("unexpected packet sent to {}: {:?}", node_id, packet);
+        }
+    }
+
+    pub fn wait_for(&self, state: DroneState) {
+        let deadline = Instant::now() + TIMEOUT;
+        while self.handle.stats().state != state {
+            assert!(Instant::now() < deadline, "still {:?}, expected {:?}", self.handle.stats().state, state);
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    pub fn wait_for_exit(&self) {
+        let deadline = Instant::now() + TIMEOUT;
+        while !self.handle.is_finished() {
+            assert!(Instant::now() < deadline, "the drone is still running");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Events sent to the mock controller so far.
+    pub fn events(&self) -> Vec<DroneEvent> {
+        self.event_recv.try_iter().collect()
+    }
+}
+
+// A stub client or server: a node of the topology that is not a drone, its packets are sent and read by hand.
+pub struct StubNode {
+    pub id: NodeId,
+    pub recv: Receiver<Packet>,
+    // to the drone it is attached to
+    pub send: Sender<Packet>,
+}
+
+impl StubNode {
+    // what the stub received until nothing came for `QUIET`
+    pub fn collect(&self) -> Vec<Packet> {
+        let mut packets = Vec::new();
+        while let Ok(packet) = self.recv.recv_timeout(QUIET) {
+            packets.push(packet);
+        }
+        packets
+    }
+}
+
+pub struct DroneNetwork {
+    pub drones: HashMap<NodeId, DroneHandle>,
+    pub stubs: HashMap<NodeId, StubNode>,
+    pub command_sends: HashMap<NodeId, Sender<DroneCommand>>,
+    // the drones stop sending events to a controller that is gone
+    pub event_recv: Receiver<DroneEvent>,
+}
+
+// Our drones linked by `edges`, with pdr 0, and a stub node for each (stub id, drone id) of `stubs`.
+pub fn drone_network(edges: &[(NodeId, NodeId)], stubs: &[(NodeId, NodeId)], options: DroneOptions) -> DroneNetwork {
+    let mut channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)> = HashMap::new();
+    let mut links: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+    for (a, b) in edges.iter().chain(stubs) {
+        for node_id in [a, b] {
+            channels.entry(*node_id).or_insert_with(unbounded);
+        }
+        links.entry(*a).or_default().push(*b);
+        links.entry(*b).or_default().push(*a);
+    }
+    let stub_ids: Vec<NodeId> = stubs.iter().map(|(stub_id, _)| *stub_id).collect();
+    let (event_send, event_recv) = unbounded();
+    let mut network = DroneNetwork { drones: HashMap::new(), stubs: HashMap::new(), command_sends: HashMap::new(), event_recv };
+    for (node_id, (_, packet_recv)) in &channels {
+        if stub_ids.contains(node_id) {
+            let drone_id = stubs.iter().find(|(stub_id, _)| stub_id == node_id).unwrap().1;
+            let send = channels[&drone_id].0.clone();
+            network.stubs.insert(*node_id, StubNode { id: *node_id, recv: packet_recv.clone(), send });
+            continue;
+        }
+        let senders = links[node_id].iter().map(|neighbor| (*neighbor, channels[neighbor].0.clone())).collect();
+        let (command_send, command_recv) = unbounded();
+        let drone = RustaceansWitAttitudesDrone::new(*node_id, event_send.clone(), command_recv, packet_recv.clone(), senders, 0.0)
+            .with_options(options.clone());
+        network.drones.insert(*node_id, spawn(drone).unwrap());
+        network.command_sends.insert(*node_id, command_send);
+    }
+    network
+}
+
+// Packets along `hops`, `hop_index` pointing to the node receiving them.
+pub fn fragment(hops: &[NodeId], hop_index: usize) -> Packet {
+    let fragment = Fragment { fragment_index: 0, total_n_fragments: 1, length: 0, data: [0; 128] };
+    Packet::new_fragment(route(hops, hop_index), 7, fragment)
+}
+
+pub fn ack(hops: &[NodeId], hop_index: usize) -> Packet {
+    Packet::new_ack(route(hops, hop_index), 7, 0)
+}
+
+pub fn nack(hops: &[NodeId], hop_index: usize, nack_type: NackType) -> Packet {
+    Packet::new_nack(route(hops, hop_index), 7, Nack { fragment_index: 0, nack_type })
+}
+
+pub fn flood_response(hops: &[NodeId], hop_index: usize) -> Packet {
+    let path_trace = hops.iter().rev().map(|node_id| (*node_id, NodeType::Drone)).collect();
+    Packet::new_flood_response(route(hops, hop_index), 7, FloodResponse { flood_id: 1, path_trace })
+}
+
+// FloodRequest started by `initiator_id` that went through `path`.
+pub fn flood_request(flood_id: u64, initiator_id: NodeId, path: &[NodeId]) -> Packet {
+    let path_trace = path.iter().map(|node_id| (*node_id, NodeType::Drone)).collect();
+    let flood_request = FloodRequest { flood_id, initiator_id, path_trace };
+    Packet::new_flood_request(SourceRoutingHeader { hop_index: 0, hops: Vec::new() }, 7, flood_request)
+}
+
+fn route(hops: &[NodeId], hop_index: usize) -> SourceRoutingHeader {
+    SourceRoutingHeader { hop_index, hops: hops.to_vec() }
+}
@@ -0,0 +1,58 @@
+// The payload corruption fault mode: drone 1 between node 10 and node 2.
+mod common;
+
+use common::{mock_network, MockNetwork};
+use rustaceans_wit_attitudes::DroneOptions;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, Packet, PacketType};
+
+fn line(corrupt_payload_probability: f64) -> MockNetwork {
+    mock_network(1, &[10, 2], DroneOptions { corrupt_payload_probability, ..DroneOptions::default() })
+}
+
+// fragment from 10 to 2 with `length` bytes of 0xAA
+fn fragment(length: u8) -> Packet {
+    let fragment = Fragment { fragment_index: 3, total_n_fragments: 5, length, data: [0xAA; 128] };
+    Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, 2], 1), 7, fragment)
+}
+
+fn forwarded(network: &MockNetwork) -> (Packet, Fragment) {
+    let packet = network.expect(2);
+    let PacketType::MsgFragment(fragment) = packet.pack_type.clone() else {
+        panic!("expected a fragment, got {:?}", packet.pack_type);
+    };
+    (packet, fragment)
+}
+
+#[test]
+fn one_payload_bit_is_flipped_with_probability_one() {
+    let network = line(1.0);
+    network.send(fragment(16));
+    let (packet, fragment) = forwarded(&network);
+    let flipped: u32 = fragment.data.iter().map(|byte| (byte ^ 0xAA).count_ones()).sum();
+    assert_eq!(flipped, 1);
+    // only in the payload, the rest of the packet is untouched
+    assert_eq!(fragment.data[16..], [0xAA; 112]);
+    assert_eq!((fragment.fragment_index, fragment.total_n_fragments, fragment.length), (3, 5, 16));
+    assert_eq!(packet.routing_header, SourceRoutingHeader::new(vec![10, 1, 2], 2));
+    assert_eq!(packet.session_id, 7);
+    assert_eq!(network.handle.stats().fragments_corrupted, 1);
+}
+
+#[test]
+fn an_empty_payload_is_never_corrupted() {
+    let network = line(1.0);
+    network.send(fragment(0));
+    let (_, fragment) = forwarded(&network);
+    assert_eq!(fragment.data, [0xAA; 128]);
+    assert_eq!(network.handle.stats().fragments_corrupted, 0);
+}
+
+#[test]
+fn nothing_is_corrupted_by_default() {
+    let network = line(0.0);
+    network.send(fragment(128));
+    let (_, fragment) = forwarded(&network);
+    assert_eq!(fragment.data, [0xAA; 128]);
+    assert_eq!(network.handle.stats().fragments_corrupted, 0);
+}
@@ -0,0 +1,121 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rustaceans_wit_attitudes::{
+    spawn, AppliedCommand, AuxEvent, AuxEventKind, DroneHandle, DroneOptions, DroneState, RustaceansWitAttitudesDrone,
+};
+use wg_2024::controller::DroneCommand;
+use wg_2024::drone::Drone;
+use wg_2024::packet::Packet;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// drone 1 between nodes 0 and 2
+struct Line {
+    handle: DroneHandle,
+    command_send: Sender<DroneCommand>,
+    aux_recv: Receiver<AuxEvent>,
+    // the drone stops once every sender to it is gone, keep ours
+    _packet_send: Sender<Packet>,
+}
+
+fn line(options: DroneOptions) -> Line {
+    let (event_send, _event_recv) = unbounded();
+    let (command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (aux_send, aux_recv) = unbounded();
+    let neighbors = HashMap::from([(0, unbounded().0), (2, unbounded().0)]);
+    let drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, neighbors, 0.0)
+        .with_options(options)
+        .with_aux_sender(aux_send);
+    let handle = spawn(drone).unwrap();
+    Line { handle, command_send, aux_recv, _packet_send: packet_send }
+}
+
+impl Line {
+    fn command(&self, command: DroneCommand) {
+        self.command_send.send(command).unwrap();
+    }
+
+    fn wait_for(&self, state: DroneState) {
+        let deadline = Instant::now() + TIMEOUT;
+        while self.handle.stats().state != state {
+            assert!(Instant::now() < deadline, "still {:?}, expected {:?}", self.handle.stats().state, state);
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn wait_for_exit(&self) {
+        let deadline = Instant::now() + TIMEOUT;
+        while !self.handle.is_finished() {
+            assert!(Instant::now() < deadline, "the crashed drone is still running");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn applied(&self) -> AppliedCommand {
+        loop {
+            let event = self.aux_recv.recv_timeout(TIMEOUT).expect("no command applied");
+            if let AuxEventKind::CommandApplied(command) = event.kind {
+                return command;
+            }
+        }
+    }
+}
+
+#[test]
+fn a_new_drone_is_running() {
+    let line = line(DroneOptions::default());
+    assert_eq!(line.handle.stats().state, DroneState::Running);
+}
+
+#[test]
+fn removing_the_last_sender_completes_the_crash() {
+    let line = line(DroneOptions::default());
+    line.command(DroneCommand::Crash);
+    line.wait_for(DroneState::Crashing);
+    line.command(DroneCommand::RemoveSender(0));
+    assert_eq!(line.applied(), AppliedCommand::RemoveSender(0));
+    assert_eq!(line.handle.stats().state, DroneState::Crashing);
+    line.command(DroneCommand::RemoveSender(2));
+    line.wait_for(DroneState::Crashed);
+    line.wait_for_exit();
+}
+
+#[test]
+fn a_second_crash_is_ignored() {
+    let line = line(DroneOptions::default());
+    line.command(DroneCommand::Crash);
+    line.command(DroneCommand::Crash);
+    line.command(DroneCommand::RemoveSender(0));
+    assert_eq!(line.applied(), AppliedCommand::RemoveSender(0));
+    assert_eq!(line.handle.stats().state, DroneState::Crashing);
+    line.command(DroneCommand::RemoveSender(2));
+    line.wait_for(DroneState::Crashed);
+}
+
+#[test]
+fn only_remove_sender_is_applied_while_crashing() {
+    let line = line(DroneOptions::default());
+    line.command(DroneCommand::Crash);
+    line.command(DroneCommand::SetPacketDropRate(0.5));
+    line.command(DroneCommand::AddSender(3, unbounded().0));
+    line.command(DroneCommand::RemoveSender(0));
+    // commands are applied in order, the ones before were ignored
+    assert_eq!(line.applied(), AppliedCommand::RemoveSender(0));
+    let stats = line.handle.stats();
+    assert_eq!(stats.pdr, 0.0);
+    assert_eq!(stats.neighbor_ids, vec![2]);
+    assert_eq!(stats.state, DroneState::Crashing);
+}
+
+#[test]
+fn the_drain_timeout_completes_the_crash() {
+    let line = line(DroneOptions { crash_drain_timeout: Some(Duration::from_millis(20)), ..Default::default() });
+    line.command(DroneCommand::Crash);
+    line.wait_for(DroneState::Crashed);
+    line.wait_for_exit();
+    assert_eq!(line.handle.stats().neighbor_ids, vec![0, 2]);
+}
@@ -0,0 +1,111 @@
+// The crash protocol: a crashing drone Nacks fragments, forwards Acks, Nacks and FloodResponses, loses
+// FloodRequests and exits once the simulation controller removed its last sender.
+mod common;
+
+use common::{ack, flood_request, flood_response, fragment, mock_network, nack, MockNetwork};
+use rustaceans_wit_attitudes::{DroneOptions, DroneState};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::packet::{NackType, PacketType};
+
+// drone 1 between mock nodes 0 and 2, already crashing
+fn crashing() -> MockNetwork {
+    let network = mock_network(1, &[0, 2], DroneOptions::default());
+    network.command(DroneCommand::Crash);
+    network.wait_for(DroneState::Crashing);
+    network
+}
+
+#[test]
+fn fragments_are_nacked_with_error_in_routing() {
+    let network = crashing();
+    network.send(fragment(&[0, 1, 2], 1));
+    let packet = network.expect(0);
+    let PacketType::Nack(nack) = packet.pack_type else {
+        panic!("expected a Nack, got {:?}", packet.pack_type);
+    };
+    assert!(matches!(nack.nack_type, NackType::ErrorInRouting(1)), "{:?}", nack.nack_type);
+    assert_eq!(packet.routing_header.hops, vec![1, 0]);
+    assert_eq!(packet.routing_header.hop_index, 1);
+    network.expect_nothing(2);
+}
+
+#[test]
+fn acks_are_forwarded() {
+    let network = crashing();
+    network.send(ack(&[2, 1, 0], 1));
+    let packet = network.expect(0);
+    assert!(matches!(packet.pack_type, PacketType::Ack(_)));
+    assert_eq!(packet.routing_header.hop_index, 2);
+}
+
+#[test]
+fn nacks_are_forwarded_unchanged() {
+    let network = crashing();
+    network.send(nack(&[2, 1, 0], 1, NackType::Dropped));
+    let packet = network.expect(0);
+    let PacketType::Nack(nack) = packet.pack_type else {
+        panic!("expected a Nack, got {:?}", packet.pack_type);
+    };
+    assert!(matches!(nack.nack_type, NackType::Dropped), "{:?}", nack.nack_type);
+}
+
+#[test]
+fn flood_responses_are_forwarded() {
+    let network = crashing();
+    network.send(flood_response(&[2, 1, 0], 1));
+    let packet = network.expect(0);
+    assert!(matches!(packet.pack_type, PacketType::FloodResponse(_)));
+    assert_eq!(packet.routing_header.hop_index, 2);
+}
+
+#[test]
+fn flood_requests_are_lost() {
+    let network = crashing();
+    network.send(flood_request(1, 0, &[0]));
+    network.expect_nothing(0);
+    network.expect_nothing(2);
+    assert!(!network.events().iter().any(|event| matches!(event, DroneEvent::ControllerShortcut(_))));
+}
+
+#[test]
+fn acks_to_a_removed_neighbor_go_through_the_controller() {
+    let network = crashing();
+    network.command(DroneCommand::RemoveSender(0));
+    network.send(ack(&[2, 1, 0], 1));
+    network.expect_nothing(0);
+    let shortcut = network.events().into_iter().find_map(|event| match event {
+        DroneEvent::ControllerShortcut(packet) => Some(packet),
+        _ => None,
+    });
+    assert!(matches!(shortcut.expect("no shortcut").pack_type, PacketType::Ack(_)));
+}
+
+#[test]
+fn the_drone_exits_after_the_last_remove_sender() {
+    let network = crashing();
+    network.command(DroneCommand::RemoveSender(0));
+    // still forwarding to the neighbor left
+    network.send(ack(&[0, 1, 2], 1));
+    network.expect(2);
+    assert!(!network.handle.is_finished());
+    network.command(DroneCommand::RemoveSender(2));
+    network.wait_for_exit();
+    assert_eq!(network.handle.stats().state, DroneState::Crashed);
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn a_crashing_drone_still_answers_a_snapshot() {
+    let network = crashing();
+    let snapshot = network.handle.snapshot().expect("no snapshot");
+    assert!(snapshot.crashing);
+    assert_eq!(snapshot.neighbors, vec![0, 2]);
+}
+
+#[test]
+fn a_crashing_drone_can_be_shut_down() {
+    let network = crashing();
+    // paused or not, the rest of the handle commands are ignored
+    network.handle.pause();
+    network.handle.shutdown().unwrap();
+}
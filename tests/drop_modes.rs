@@ -0,0 +1,93 @@
+// Which fragments each drop mode drops, the drone driven synchronously: drone 1 between node 10 and node 2.
+use crossbeam_channel::{unbounded, Receiver};
+use std::collections::HashMap;
+
+use rustaceans_wit_attitudes::internals;
+use rustaceans_wit_attitudes::{DropMode, DroneOptions, RustaceansWitAttitudesDrone};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+struct Harness {
+    drone: RustaceansWitAttitudesDrone,
+    from_10: Receiver<Packet>,
+    from_2: Receiver<Packet>,
+}
+
+impl Harness {
+    // fragment indexes of `session_id` sent in this order, the ones Nacked with Dropped
+    fn dropped(&mut self, session_id: u64, fragment_indexes: impl IntoIterator<Item = u64>) -> Vec<u64> {
+        let mut dropped = Vec::new();
+        for fragment_index in fragment_indexes {
+            internals::handle_packet(&mut self.drone, fragment(session_id, fragment_index));
+            match (self.from_10.try_recv(), self.from_2.try_recv()) {
+                (Ok(nack), Err(_)) => {
+                    let PacketType::Nack(nack) = nack.pack_type else {
+                        panic!("expected a Nack, got {:?}", nack.pack_type);
+                    };
+                    assert!(matches!(nack.nack_type, NackType::Dropped), "{:?}", nack.nack_type);
+                    dropped.push(fragment_index);
+                }
+                (Err(_), Ok(_)) => {}
+                other => panic!("fragment {} neither forwarded nor Nacked: {:?}", fragment_index, other),
+            }
+        }
+        dropped
+    }
+}
+
+fn harness(drop_mode: DropMode, pdr: f32) -> Harness {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let (send_10, from_10) = unbounded();
+    let (send_2, from_2) = unbounded();
+    let senders = HashMap::from([(10, send_10), (2, send_2)]);
+    let options = DroneOptions { drop_mode, ..DroneOptions::default() };
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, pdr).with_options(options);
+    internals::start(&mut drone);
+    Harness { drone, from_10, from_2 }
+}
+
+// fragment `fragment_index` of `session_id`, from 10 to 2
+fn fragment(session_id: u64, fragment_index: u64) -> Packet {
+    let fragment = Fragment { fragment_index, total_n_fragments: 100, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, 2], 1), session_id, fragment)
+}
+
+#[test]
+fn exact_rate_drops_the_same_positions_in_every_window() {
+    let mut h = harness(DropMode::ExactRate { window: 4 }, 0.5);
+    assert_eq!(h.dropped(7, 0..12), vec![1, 3, 5, 7, 9, 11]);
+}
+
+#[test]
+fn exact_rate_spreads_the_drops_over_the_window() {
+    let mut h = harness(DropMode::ExactRate { window: 10 }, 0.3);
+    assert_eq!(h.dropped(7, 0..10), vec![3, 6, 9]);
+    // the window counts fragments, whatever their session
+    assert_eq!(h.dropped(8, 0..10), vec![3, 6, 9]);
+}
+
+#[test]
+fn exact_rate_drops_nothing_or_everything() {
+    assert!(harness(DropMode::ExactRate { window: 5 }, 0.0).dropped(7, 0..10).is_empty());
+    assert_eq!(harness(DropMode::ExactRate { window: 5 }, 1.0).dropped(7, 0..10).len(), 10);
+}
+
+#[test]
+fn every_nth_drops_every_nth_fragment_whatever_the_pdr() {
+    let mut h = harness(DropMode::EveryNth(3), 0.0);
+    assert_eq!(h.dropped(7, 0..9), vec![2, 5, 8]);
+    // the count goes on across sessions
+    assert_eq!(h.dropped(8, 0..3), vec![2]);
+}
+
+#[test]
+fn listed_indices_are_dropped_once_per_session() {
+    let mut h = harness(DropMode::FragmentIndices(vec![1, 3]), 1.0);
+    assert_eq!(h.dropped(7, 0..5), vec![1, 3]);
+    // retransmissions go through
+    assert!(h.dropped(7, [1, 3]).is_empty());
+    assert_eq!(h.dropped(8, [3, 1, 3]), vec![3, 1]);
+}
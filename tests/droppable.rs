@@ -0,0 +1,53 @@
+// Packet types the pdr applies to: drone 1 between node 10 and node 2, with a pdr of 1.
+mod common;
+
+use common::{ack, flood_response, fragment, mock_network, nack, MockNetwork, TIMEOUT};
+use rustaceans_wit_attitudes::{DroneOptions, DroppablePackets};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::packet::{NackType, PacketType};
+
+fn line(droppable: DroppablePackets) -> MockNetwork {
+    let network = mock_network(1, &[10, 2], DroneOptions { droppable, ..DroneOptions::default() });
+    network.command(DroneCommand::SetPacketDropRate(1.0));
+    network
+}
+
+fn expect_dropped(network: &MockNetwork) {
+    let event = network.event_recv.recv_timeout(TIMEOUT).expect("no PacketDropped");
+    assert!(matches!(event, DroneEvent::PacketDropped(_)), "{:?}", event);
+}
+
+#[test]
+fn only_fragments_are_dropped_by_default() {
+    let network = line(DroppablePackets::default());
+    network.send(ack(&[10, 1, 2], 1));
+    assert!(matches!(network.expect(2).pack_type, PacketType::Ack(_)));
+    network.send(fragment(&[10, 1, 2], 1));
+    let packet = network.expect(10);
+    assert!(matches!(&packet.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::Dropped)));
+    network.expect_nothing(2);
+}
+
+#[test]
+fn droppable_control_packets_are_lost_without_a_nack() {
+    let droppable = DroppablePackets { fragments: true, acks: true, nacks: true, flood_requests: false, flood_responses: true };
+    let network = line(droppable);
+    network.send(ack(&[10, 1, 2], 1));
+    network.send(nack(&[10, 1, 2], 1, NackType::Dropped));
+    network.send(flood_response(&[10, 1, 2], 1));
+    for _ in 0..3 {
+        expect_dropped(&network);
+    }
+    network.expect_nothing(2);
+    network.expect_nothing(10);
+    assert_eq!(network.handle.stats().control_packets_dropped, 3);
+}
+
+#[test]
+fn fragments_can_be_spared() {
+    let droppable = DroppablePackets { fragments: false, ..DroppablePackets::default() };
+    let network = line(droppable);
+    network.send(fragment(&[10, 1, 2], 1));
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+    network.expect_nothing(10);
+}
@@ -0,0 +1,59 @@
+// The duplicate flood policies: drone 1 between node 10, which starts flood 1, and node 2, which sends it back.
+mod common;
+
+use common::{flood_request, mock_network, MockNetwork};
+use rustaceans_wit_attitudes::{DroneOptions, DuplicateFloodPolicy};
+use wg_2024::packet::PacketType;
+
+// the drone has already forwarded flood 1 of node 10 to node 2
+fn flooded(duplicate_flood_policy: DuplicateFloodPolicy) -> MockNetwork {
+    let network = mock_network(1, &[10, 2], DroneOptions { duplicate_flood_policy, ..DroneOptions::default() });
+    network.send(flood_request(1, 10, &[10]));
+    assert!(matches!(network.expect(2).pack_type, PacketType::FloodRequest(_)));
+    network
+}
+
+// the same flood again, through node 2
+fn send_duplicate(network: &MockNetwork) {
+    network.send(flood_request(1, 10, &[10, 2]));
+}
+
+fn expect_flood_response(network: &MockNetwork) {
+    let packet = network.expect(2);
+    let PacketType::FloodResponse(flood_response) = packet.pack_type else {
+        panic!("expected a FloodResponse, got {:?}", packet.pack_type);
+    };
+    assert_eq!(flood_response.flood_id, 1);
+    assert_eq!(packet.routing_header.hops, vec![1, 2, 10]);
+}
+
+#[test]
+fn respond_answers_every_duplicate() {
+    let network = flooded(DuplicateFloodPolicy::Respond);
+    send_duplicate(&network);
+    send_duplicate(&network);
+    expect_flood_response(&network);
+    expect_flood_response(&network);
+    network.expect_nothing(10);
+}
+
+#[test]
+fn drop_answers_no_duplicate() {
+    let network = flooded(DuplicateFloodPolicy::Drop);
+    send_duplicate(&network);
+    network.expect_nothing(2);
+    network.expect_nothing(10);
+}
+
+#[test]
+fn respond_once_per_initiator_answers_the_first_duplicate_only() {
+    let network = flooded(DuplicateFloodPolicy::RespondOncePerInitiator);
+    send_duplicate(&network);
+    send_duplicate(&network);
+    expect_flood_response(&network);
+    network.expect_nothing(2);
+
+    // the same flood id from another initiator is another flood
+    network.send(flood_request(1, 20, &[20, 10]));
+    assert!(matches!(network.expect(2).pack_type, PacketType::FloodRequest(_)));
+}
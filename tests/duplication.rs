@@ -0,0 +1,42 @@
+// The fragment duplication fault mode: drone 1 between node 10 and node 2.
+mod common;
+
+use common::{ack, fragment, mock_network, MockNetwork};
+use rustaceans_wit_attitudes::DroneOptions;
+use wg_2024::packet::PacketType;
+
+fn line(duplicate_fragment_probability: f64) -> MockNetwork {
+    mock_network(1, &[10, 2], DroneOptions { duplicate_fragment_probability, ..DroneOptions::default() })
+}
+
+#[test]
+fn every_fragment_is_sent_twice_with_probability_one() {
+    let network = line(1.0);
+    network.send(fragment(&[10, 1, 2], 1));
+    let first = network.expect(2);
+    let second = network.expect(2);
+    for packet in [&first, &second] {
+        assert!(matches!(packet.pack_type, PacketType::MsgFragment(_)));
+    }
+    assert_eq!(first.routing_header, second.routing_header);
+    network.expect_nothing(2);
+    assert_eq!(network.handle.stats().fragments_duplicated, 1);
+}
+
+#[test]
+fn only_fragments_are_duplicated() {
+    let network = line(1.0);
+    network.send(ack(&[10, 1, 2], 1));
+    assert!(matches!(network.expect(2).pack_type, PacketType::Ack(_)));
+    network.expect_nothing(2);
+    assert_eq!(network.handle.stats().fragments_duplicated, 0);
+}
+
+#[test]
+fn nothing_is_duplicated_by_default() {
+    let network = line(0.0);
+    network.send(fragment(&[10, 1, 2], 1));
+    network.expect(2);
+    network.expect_nothing(2);
+    assert_eq!(network.handle.stats().fragments_duplicated, 0);
+}
@@ -0,0 +1,126 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rustaceans_wit_attitudes::{spawn, DroneHandle, DroneOptions, RustaceansWitAttitudesDrone};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// drone 1 between drones 0 and 2, floods come from 0
+struct Line {
+    handle: DroneHandle,
+    packet_send: Sender<Packet>,
+    from_0: Receiver<Packet>,
+    from_2: Receiver<Packet>,
+    // keep the controller channels open for the whole test
+    _controller: (Sender<DroneCommand>, Receiver<DroneEvent>),
+}
+
+fn line(options: DroneOptions) -> Line {
+    let (event_send, event_recv) = unbounded();
+    let (command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (send_0, from_0) = unbounded();
+    let (send_2, from_2) = unbounded();
+    let neighbors = HashMap::from([(0, send_0), (2, send_2)]);
+    let drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, neighbors, 0.0).with_options(options);
+    let handle = spawn(drone).unwrap();
+    Line { handle, packet_send, from_0, from_2, _controller: (command_send, event_recv) }
+}
+
+fn flood(flood_id: u64, initiator_id: u8) -> Packet {
+    let flood_request = FloodRequest {
+        flood_id,
+        initiator_id,
+        path_trace: vec![(initiator_id, NodeType::Client), (0, NodeType::Drone)],
+    };
+    Packet::new_flood_request(SourceRoutingHeader { hop_index: 0, hops: Vec::new() }, flood_id, flood_request)
+}
+
+impl Line {
+    // next FloodRequest forwarded to 2
+    fn forwarded(&self) -> (u64, u8) {
+        loop {
+            let packet = self.from_2.recv_timeout(TIMEOUT).expect("no FloodRequest forwarded");
+            if let PacketType::FloodRequest(flood_request) = packet.pack_type {
+                return (flood_request.flood_id, flood_request.initiator_id);
+            }
+        }
+    }
+
+    // next FloodResponse sent back to 0, the drone also forwards the FloodRequests there
+    fn answered(&self) -> (u64, u8) {
+        loop {
+            let packet = self.from_0.recv_timeout(TIMEOUT).expect("no FloodResponse sent back");
+            if let PacketType::FloodResponse(flood_response) = packet.pack_type {
+                let initiator_id = flood_response.path_trace.first().map(|(node_id, _)| *node_id).unwrap();
+                return (flood_response.flood_id, initiator_id);
+            }
+        }
+    }
+
+    fn send(&self, packet: Packet) {
+        self.packet_send.send(packet).unwrap();
+    }
+}
+
+#[test]
+fn colliding_flood_ids_from_different_initiators_are_both_forwarded() {
+    let line = line(DroneOptions::default());
+    line.send(flood(7, 10));
+    assert_eq!(line.forwarded(), (7, 10));
+    line.send(flood(7, 20));
+    assert_eq!(line.forwarded(), (7, 20));
+    line.handle.shutdown().unwrap();
+}
+
+#[test]
+fn a_colliding_flood_does_not_make_the_first_one_new_again() {
+    let line = line(DroneOptions::default());
+    line.send(flood(7, 10));
+    assert_eq!(line.forwarded(), (7, 10));
+    line.send(flood(7, 20));
+    assert_eq!(line.forwarded(), (7, 20));
+    // used to be forwarded again, the second initiator had replaced the first
+    line.send(flood(7, 10));
+    assert_eq!(line.answered(), (7, 10));
+    line.send(flood(7, 20));
+    assert_eq!(line.answered(), (7, 20));
+    assert!(line.from_2.try_iter().all(|packet| !matches!(packet.pack_type, PacketType::FloodRequest(_))));
+    line.handle.shutdown().unwrap();
+}
+
+#[test]
+fn interleaved_floods_are_each_forwarded_once() {
+    let line = line(DroneOptions::default());
+    for round in 0..3 {
+        for initiator_id in [10, 20, 30] {
+            line.send(flood(7, initiator_id));
+            if round == 0 {
+                assert_eq!(line.forwarded(), (7, initiator_id));
+            } else {
+                assert_eq!(line.answered(), (7, initiator_id));
+            }
+        }
+    }
+    line.handle.shutdown().unwrap();
+}
+
+#[test]
+fn the_oldest_floods_are_forgotten_past_the_capacity() {
+    let line = line(DroneOptions { flood_dedup_capacity: 2, ..DroneOptions::default() });
+    for initiator_id in [10, 20, 30] {
+        line.send(flood(7, initiator_id));
+        assert_eq!(line.forwarded(), (7, initiator_id));
+    }
+    // (7, 10) was evicted by (7, 30), (7, 30) is still known
+    line.send(flood(7, 30));
+    assert_eq!(line.answered(), (7, 30));
+    line.send(flood(7, 10));
+    assert_eq!(line.forwarded(), (7, 10));
+    line.handle.shutdown().unwrap();
+}
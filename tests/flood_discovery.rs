@@ -0,0 +1,122 @@
+// Floods initiated by a stub client through meshes and rings of our drones: the FloodResponses it collects
+// have to describe the topology, every drone in them and each path trace along real links. A path trace can go
+// through a drone twice: the flood may reach it again over a longer path, and the duplicate is answered.
+mod common;
+
+use std::collections::HashSet;
+
+use common::{drone_network, DroneNetwork};
+use rustaceans_wit_attitudes::DroneOptions;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+
+const CLIENT: NodeId = 100;
+
+// drones 1..=9, row by row, each linked to the ones on its right and below
+fn mesh() -> Vec<(NodeId, NodeId)> {
+    let mut edges = Vec::new();
+    for row in 0..3 {
+        for column in 0..3 {
+            let id = 1 + 3 * row + column;
+            if column < 2 {
+                edges.push((id, id + 1));
+            }
+            if row < 2 {
+                edges.push((id, id + 3));
+            }
+        }
+    }
+    edges
+}
+
+fn ring(len: NodeId) -> Vec<(NodeId, NodeId)> {
+    (1..=len).map(|id| (id, id % len + 1)).collect()
+}
+
+// the route of a FloodRequest is empty, only the path trace says where it came from
+fn flood_request(flood_id: u64) -> Packet {
+    let flood_request = FloodRequest { flood_id, initiator_id: CLIENT, path_trace: vec![(CLIENT, NodeType::Client)] };
+    Packet::new_flood_request(SourceRoutingHeader { hop_index: 0, hops: Vec::new() }, flood_id, flood_request)
+}
+
+// path traces of the FloodResponses to flood `flood_id` from CLIENT, attached to drone `entry`
+fn flood(network: &DroneNetwork, flood_id: u64) -> Vec<Vec<(NodeId, NodeType)>> {
+    let client = &network.stubs[&CLIENT];
+    client.send.send(flood_request(flood_id)).unwrap();
+    client
+        .collect()
+        .into_iter()
+        .filter_map(|packet| match packet.pack_type {
+            PacketType::FloodResponse(flood_response) => {
+                assert_eq!(flood_response.flood_id, flood_id);
+                // routed back along the path trace, up to the client
+                let hops: Vec<NodeId> = flood_response.path_trace.iter().rev().map(|(node_id, _)| *node_id).collect();
+                assert_eq!(packet.routing_header.hops, hops);
+                assert_eq!(packet.routing_header.hop_index, hops.len() - 1);
+                Some(flood_response.path_trace)
+            }
+            // the client doesn't take part in the flood it initiated
+            _ => None,
+        })
+        .collect()
+}
+
+fn assert_discovered(edges: &[(NodeId, NodeId)], entry: NodeId, path_traces: &[Vec<(NodeId, NodeType)>]) {
+    let linked = |a: NodeId, b: NodeId| {
+        edges.contains(&(a, b)) || edges.contains(&(b, a)) || [(a, b), (b, a)].contains(&(CLIENT, entry))
+    };
+    assert!(!path_traces.is_empty(), "no FloodResponse");
+    let mut discovered = HashSet::new();
+    for path_trace in path_traces {
+        assert_eq!(path_trace[0], (CLIENT, NodeType::Client));
+        for (node_id, node_type) in &path_trace[1..] {
+            assert_eq!(*node_type, NodeType::Drone, "{:?}", path_trace);
+            discovered.insert(*node_id);
+        }
+        for pair in path_trace.windows(2) {
+            assert!(linked(pair[0].0, pair[1].0), "no link {} - {} in {:?}", pair[0].0, pair[1].0, path_trace);
+        }
+    }
+    let drones: HashSet<NodeId> = edges.iter().flat_map(|(a, b)| [*a, *b]).collect();
+    assert_eq!(discovered, drones);
+}
+
+#[test]
+fn a_flood_discovers_a_mesh() {
+    let edges = mesh();
+    let network = drone_network(&edges, &[(CLIENT, 1)], DroneOptions::default());
+    assert_discovered(&edges, 1, &flood(&network, 1));
+}
+
+#[test]
+fn a_flood_from_the_middle_discovers_a_mesh() {
+    let edges = mesh();
+    let network = drone_network(&edges, &[(CLIENT, 5)], DroneOptions::default());
+    assert_discovered(&edges, 5, &flood(&network, 1));
+}
+
+#[test]
+fn a_flood_discovers_a_ring() {
+    let edges = ring(6);
+    let network = drone_network(&edges, &[(CLIENT, 1)], DroneOptions::default());
+    assert_discovered(&edges, 1, &flood(&network, 1));
+}
+
+#[test]
+fn consecutive_floods_discover_the_same_ring() {
+    let edges = ring(5);
+    let network = drone_network(&edges, &[(CLIENT, 3)], DroneOptions::default());
+    for flood_id in 1..=3 {
+        assert_discovered(&edges, 3, &flood(&network, flood_id));
+    }
+}
+
+#[test]
+fn a_flood_is_not_sent_back_to_the_client() {
+    let network = drone_network(&ring(3), &[(CLIENT, 1)], DroneOptions::default());
+    let client = &network.stubs[&CLIENT];
+    client.send.send(flood_request(1)).unwrap();
+    let received = client.collect();
+    assert!(!received.is_empty(), "no FloodResponse");
+    assert!(received.iter().all(|packet| matches!(packet.pack_type, PacketType::FloodResponse(_))), "{:?}", received);
+}
@@ -0,0 +1,78 @@
+// Fragment consistency checks: drone 1 between node 10 and node 2, impossible fragments are Nacked back to 10.
+mod common;
+
+use crossbeam_channel::{unbounded, Receiver};
+
+use common::{fragment, mock_network_with, MockNetwork, TIMEOUT};
+use rustaceans_wit_attitudes::{AuxEvent, AuxEventKind, Diagnostic, DroneOptions};
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+fn line(validate_fragments: bool) -> (MockNetwork, Receiver<AuxEvent>) {
+    let (aux_send, aux_recv) = unbounded();
+    let options = DroneOptions { validate_fragments, ..DroneOptions::default() };
+    let network = mock_network_with(1, &[10, 2], |drone| drone.with_options(options).with_aux_sender(aux_send));
+    (network, aux_recv)
+}
+
+// a fragment from 10 to 2 with `length` bytes, index `fragment_index` of `total_n_fragments`
+fn fragment_with(length: u8, fragment_index: u64, total_n_fragments: u64) -> Packet {
+    let mut packet = fragment(&[10, 1, 2], 1);
+    if let PacketType::MsgFragment(fragment) = &mut packet.pack_type {
+        fragment.length = length;
+        fragment.fragment_index = fragment_index;
+        fragment.total_n_fragments = total_n_fragments;
+    }
+    packet
+}
+
+fn diagnostic(aux_recv: &Receiver<AuxEvent>) -> Diagnostic {
+    loop {
+        let event = aux_recv.recv_timeout(TIMEOUT).expect("no diagnostic");
+        if let AuxEventKind::Diagnostic { diagnostic, .. } = event.kind {
+            return diagnostic;
+        }
+    }
+}
+
+fn expect_nack_dropped(network: &MockNetwork) {
+    let packet = network.expect(10);
+    let PacketType::Nack(nack) = &packet.pack_type else {
+        panic!("expected a Nack, got {:?}", packet.pack_type);
+    };
+    assert!(matches!(nack.nack_type, NackType::Dropped), "{:?}", nack.nack_type);
+    assert_eq!(packet.routing_header.hops, vec![1, 10]);
+}
+
+#[test]
+fn a_fragment_longer_than_its_payload_is_nacked() {
+    let (network, aux_recv) = line(true);
+    network.send(fragment_with(129, 0, 1));
+    expect_nack_dropped(&network);
+    assert_eq!(diagnostic(&aux_recv), Diagnostic::FragmentTooLong { length: 129 });
+    network.expect_nothing(2);
+}
+
+#[test]
+fn a_fragment_index_out_of_range_is_nacked() {
+    let (network, aux_recv) = line(true);
+    network.send(fragment_with(0, 2, 2));
+    expect_nack_dropped(&network);
+    assert_eq!(diagnostic(&aux_recv), Diagnostic::FragmentIndexOutOfRange { fragment_index: 2, total_n_fragments: 2 });
+    network.expect_nothing(2);
+}
+
+#[test]
+fn a_consistent_fragment_is_forwarded() {
+    let (network, _aux_recv) = line(true);
+    network.send(fragment_with(128, 1, 2));
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+    network.expect_nothing(10);
+}
+
+#[test]
+fn fragments_are_not_checked_by_default() {
+    let (network, _aux_recv) = line(false);
+    network.send(fragment_with(129, 2, 2));
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+    network.expect_nothing(10);
+}
@@ -0,0 +1,76 @@
+// Temporary link failures set with the DroneHandle: drone 1 between node 10 and node 2, the link to 2 goes down.
+mod common;
+
+use std::time::Duration;
+
+use common::{ack, fragment, mock_network, mock_network_with, MockNetwork, TIMEOUT};
+use rustaceans_wit_attitudes::{DroneOptions, LinkOutage, ManualClock};
+use wg_2024::controller::DroneEvent;
+use wg_2024::packet::{NackType, PacketType};
+
+fn line() -> MockNetwork {
+    mock_network(1, &[10, 2], DroneOptions::default())
+}
+
+fn expect_error_in_routing(network: &MockNetwork) {
+    let packet = network.expect(10);
+    let PacketType::Nack(nack) = &packet.pack_type else {
+        panic!("expected a Nack, got {:?}", packet.pack_type);
+    };
+    assert!(matches!(nack.nack_type, NackType::ErrorInRouting(_)), "{:?}", nack.nack_type);
+    assert_eq!(packet.routing_header.hops, vec![1, 10]);
+}
+
+fn expect_forwarded(network: &MockNetwork) {
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+}
+
+#[test]
+fn fragments_are_nacked_for_the_given_number_of_packets() {
+    let network = line();
+    network.handle.link_down(2, LinkOutage::Packets(2));
+    for _ in 0..2 {
+        network.send(fragment(&[10, 1, 2], 1));
+        expect_error_in_routing(&network);
+    }
+    network.expect_nothing(2);
+
+    network.send(fragment(&[10, 1, 2], 1));
+    expect_forwarded(&network);
+}
+
+#[test]
+fn an_indefinite_outage_lasts_until_link_up() {
+    let network = line();
+    network.handle.link_down(2, LinkOutage::Indefinite);
+    network.send(fragment(&[10, 1, 2], 1));
+    expect_error_in_routing(&network);
+
+    network.handle.link_up(2);
+    network.send(fragment(&[10, 1, 2], 1));
+    expect_forwarded(&network);
+    network.expect_nothing(10);
+}
+
+#[test]
+fn a_timed_outage_ends_once_the_clock_gets_there() {
+    let clock = ManualClock::new();
+    let network = mock_network_with(1, &[10, 2], |drone| drone.with_clock(clock.clone()));
+    network.handle.link_down(2, LinkOutage::For(Duration::from_secs(10)));
+    network.send(fragment(&[10, 1, 2], 1));
+    expect_error_in_routing(&network);
+
+    clock.advance(Duration::from_secs(10));
+    network.send(fragment(&[10, 1, 2], 1));
+    expect_forwarded(&network);
+}
+
+#[test]
+fn acks_over_a_down_link_go_through_the_controller() {
+    let network = line();
+    network.handle.link_down(2, LinkOutage::Indefinite);
+    network.send(ack(&[10, 1, 2], 1));
+    let event = network.event_recv.recv_timeout(TIMEOUT).expect("no shortcut for the Ack");
+    assert!(matches!(&event, DroneEvent::ControllerShortcut(packet) if matches!(packet.pack_type, PacketType::Ack(_))));
+    network.expect_nothing(2);
+}
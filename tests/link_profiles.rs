@@ -0,0 +1,103 @@
+// Per-neighbor link profiles: drone 1 between node 10 and node 2, run by a Stepper on a ManualClock so that
+// the delays can be stepped through.
+mod common;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use common::{flood_request, fragment};
+use rustaceans_wit_attitudes::{LinkDirection, LinkProfile, ManualClock, RustaceansWitAttitudesDrone, Stepper};
+use wg_2024::drone::Drone;
+use wg_2024::packet::{Packet, PacketType};
+
+struct Harness {
+    stepper: Stepper,
+    clock: ManualClock,
+    packet_send: Sender<Packet>,
+    from_2: Receiver<Packet>,
+}
+
+impl Harness {
+    fn send(&mut self, packet: Packet) {
+        self.packet_send.send(packet).unwrap();
+        self.stepper.run_until_idle();
+    }
+
+    fn advance(&mut self, millis: u64) {
+        self.clock.advance(Duration::from_millis(millis));
+        self.stepper.run_until_idle();
+    }
+
+    fn forwarded(&self) -> usize {
+        self.from_2.try_iter().count()
+    }
+}
+
+fn harness(links: &[(u8, LinkProfile)]) -> Harness {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (send_2, from_2) = unbounded();
+    let (send_10, _from_10) = unbounded();
+    let senders = HashMap::from([(10, send_10), (2, send_2)]);
+    let clock = ManualClock::new();
+    let mut drone =
+        RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0).with_clock(clock.clone());
+    for (node_id, profile) in links {
+        drone = drone.with_link_profile(*node_id, *profile);
+    }
+    let mut stepper = Stepper::new();
+    stepper.add(drone);
+    Harness { stepper, clock, packet_send, from_2 }
+}
+
+fn delay(millis: u64) -> LinkDirection {
+    LinkDirection { delay: Duration::from_millis(millis), ..LinkDirection::default() }
+}
+
+#[test]
+fn egress_delay_holds_fragments_back() {
+    let mut harness = harness(&[(2, LinkProfile { egress: delay(30), ..LinkProfile::default() })]);
+    harness.send(fragment(&[10, 1, 2], 1));
+    assert_eq!(harness.forwarded(), 0);
+    harness.advance(29);
+    assert_eq!(harness.forwarded(), 0);
+    harness.advance(1);
+    assert_eq!(harness.forwarded(), 1);
+}
+
+#[test]
+fn ingress_and_egress_delays_add_up() {
+    let mut harness = harness(&[
+        (10, LinkProfile { ingress: delay(20), ..LinkProfile::default() }),
+        (2, LinkProfile { egress: delay(30), ..LinkProfile::default() }),
+    ]);
+    harness.send(fragment(&[10, 1, 2], 1));
+    harness.advance(49);
+    assert_eq!(harness.forwarded(), 0);
+    harness.advance(1);
+    assert_eq!(harness.forwarded(), 1);
+}
+
+#[test]
+fn bandwidth_spaces_packets_out() {
+    let bandwidth = LinkDirection { bandwidth: Some(10), ..LinkDirection::default() };
+    let mut harness = harness(&[(2, LinkProfile { egress: bandwidth, ..LinkProfile::default() })]);
+    harness.send(fragment(&[10, 1, 2], 1));
+    harness.send(fragment(&[10, 1, 2], 1));
+    // 100ms per packet, the second one waits for the first
+    harness.advance(100);
+    assert_eq!(harness.forwarded(), 1);
+    harness.advance(99);
+    assert_eq!(harness.forwarded(), 0);
+    harness.advance(1);
+    assert_eq!(harness.forwarded(), 1);
+}
+
+#[test]
+fn flood_requests_are_not_held_back() {
+    let mut harness = harness(&[(2, LinkProfile::symmetric(delay(30)))]);
+    harness.send(flood_request(1, 10, &[10]));
+    assert!(matches!(harness.from_2.try_recv().expect("flood request held back").pack_type, PacketType::FloodRequest(_)));
+}
@@ -0,0 +1,87 @@
+// The cap on the number of neighbors, the drone driven synchronously: drone 1 between node 10 and node 2, with
+// room for two neighbors.
+use crossbeam_channel::{unbounded, Receiver};
+use std::collections::HashMap;
+
+use rustaceans_wit_attitudes::internals;
+use rustaceans_wit_attitudes::{AuxEvent, AuxEventKind, DroneOptions, RustaceansWitAttitudesDrone};
+use wg_2024::controller::DroneCommand;
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+struct Harness {
+    drone: RustaceansWitAttitudesDrone,
+    aux_recv: Receiver<AuxEvent>,
+    from_10: Receiver<Packet>,
+}
+
+impl Harness {
+    // AddSender for node `node_id`, the receiving end of its channel
+    fn add_sender(&mut self, node_id: NodeId) -> Receiver<Packet> {
+        let (send, recv) = unbounded();
+        internals::handle_command(&mut self.drone, DroneCommand::AddSender(node_id, send));
+        recv
+    }
+
+    fn refused(&self) -> Vec<(NodeId, usize)> {
+        self.aux_recv.try_iter().filter_map(|event| match event.kind {
+            AuxEventKind::SenderRefused { node_id, max_neighbors } => Some((node_id, max_neighbors)),
+            _ => None,
+        }).collect()
+    }
+}
+
+fn harness() -> Harness {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let (aux_send, aux_recv) = unbounded();
+    let (send_10, from_10) = unbounded();
+    let (send_2, _from_2) = unbounded();
+    let senders = HashMap::from([(10, send_10), (2, send_2)]);
+    let options = DroneOptions { max_neighbors: Some(2), ..DroneOptions::default() };
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_options(options)
+        .with_aux_sender(aux_send);
+    internals::start(&mut drone);
+    Harness { drone, aux_recv, from_10 }
+}
+
+// fragment from 10 to `to`
+fn fragment(to: NodeId) -> Packet {
+    let fragment = Fragment { fragment_index: 0, total_n_fragments: 1, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, to], 1), 7, fragment)
+}
+
+#[test]
+fn a_sender_past_the_cap_is_refused() {
+    let mut h = harness();
+    let from_3 = h.add_sender(3);
+    assert_eq!(h.refused(), vec![(3, 2)]);
+    assert_eq!(h.drone.stats().neighbor_ids, vec![2, 10]);
+
+    internals::handle_packet(&mut h.drone, fragment(3));
+    assert!(from_3.try_recv().is_err());
+    let nack = h.from_10.try_recv().expect("fragment not Nacked");
+    assert!(matches!(&nack.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(_))));
+}
+
+#[test]
+fn the_sender_of_a_neighbor_can_be_replaced() {
+    let mut h = harness();
+    let from_2 = h.add_sender(2);
+    assert!(h.refused().is_empty());
+    internals::handle_packet(&mut h.drone, fragment(2));
+    assert!(from_2.try_recv().is_ok());
+}
+
+#[test]
+fn a_removed_neighbor_makes_room() {
+    let mut h = harness();
+    internals::handle_command(&mut h.drone, DroneCommand::RemoveSender(2));
+    let from_3 = h.add_sender(3);
+    assert!(h.refused().is_empty());
+    internals::handle_packet(&mut h.drone, fragment(3));
+    assert!(from_3.try_recv().is_ok());
+}
@@ -0,0 +1,35 @@
+use rustaceans_wit_attitudes::nack;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType, FRAGMENT_DSIZE};
+
+fn fragment(hops: Vec<u8>, hop_index: usize, fragment_index: u64) -> Packet {
+    let fragment = Fragment { fragment_index, total_n_fragments: 10, length: 1, data: [0; FRAGMENT_DSIZE] };
+    Packet::new_fragment(SourceRoutingHeader::new(hops, hop_index), 42, fragment)
+}
+
+#[test]
+fn for_packet_nacks_from_the_current_hop() {
+    let nack = nack::for_packet(&fragment(vec![10, 1, 2, 20], 1, 3), NackType::Dropped).expect("no Nack");
+    assert_eq!(nack.routing_header, SourceRoutingHeader::new(vec![1, 10], 0));
+    assert_eq!(nack.session_id, 42);
+    match nack.pack_type {
+        PacketType::Nack(nack) => {
+            assert_eq!(nack.fragment_index, 3);
+            assert!(matches!(nack.nack_type, NackType::Dropped));
+        }
+        other => panic!("expected a Nack, got {:?}", other),
+    }
+}
+
+#[test]
+fn for_packet_at_the_first_hop_has_no_nack() {
+    assert!(nack::for_packet(&fragment(vec![10, 1, 2, 20], 0, 3), NackType::Dropped).is_none());
+}
+
+#[test]
+fn from_hop_nacks_from_an_earlier_hop() {
+    // the header already points to the next hop
+    let nack = nack::from_hop(&fragment(vec![10, 1, 2, 20], 2, 0), 1, NackType::ErrorInRouting(2)).expect("no Nack");
+    assert_eq!(nack.routing_header.hops, vec![1, 10]);
+    assert!(matches!(nack.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(2))));
+}
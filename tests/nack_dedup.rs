@@ -0,0 +1,78 @@
+// The Nack dedup window, the drone driven synchronously: drone 1 between node 10 and node 2. The window is long
+// enough not to end during a test, tests/clock.rs has it end.
+use crossbeam_channel::{unbounded, Receiver};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rustaceans_wit_attitudes::internals;
+use rustaceans_wit_attitudes::{DroneOptions, RustaceansWitAttitudesDrone};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, NackType, Packet};
+
+struct Harness {
+    drone: RustaceansWitAttitudesDrone,
+    from_10: Receiver<Packet>,
+}
+
+impl Harness {
+    fn nack(&mut self, session_id: u64, fragment_index: u64, nack_type: NackType) {
+        internals::send_nack(&mut self.drone, &fragment(session_id, fragment_index), nack_type);
+    }
+
+    fn nacks_sent(&self) -> usize {
+        self.from_10.try_iter().count()
+    }
+}
+
+fn harness(nack_dedup_window: Option<Duration>) -> Harness {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let (send_10, from_10) = unbounded();
+    let (send_2, _from_2) = unbounded();
+    let senders = HashMap::from([(10, send_10), (2, send_2)]);
+    let options = DroneOptions { nack_dedup_window, ..DroneOptions::default() };
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0).with_options(options);
+    internals::start(&mut drone);
+    Harness { drone, from_10 }
+}
+
+// fragment `fragment_index` of `session_id`, from 10 to 2
+fn fragment(session_id: u64, fragment_index: u64) -> Packet {
+    let fragment = Fragment { fragment_index, total_n_fragments: 10, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, 2], 1), session_id, fragment)
+}
+
+#[test]
+fn the_same_nack_is_sent_once_in_the_window() {
+    let mut h = harness(Some(Duration::from_secs(60)));
+    for _ in 0..3 {
+        h.nack(7, 0, NackType::Dropped);
+    }
+    assert_eq!(h.nacks_sent(), 1);
+    assert_eq!(h.drone.stats().nacks_suppressed, 2);
+    // suppressed Nacks aren't counted as sent
+    assert_eq!(h.drone.stats().nacks_sent, 1);
+}
+
+#[test]
+fn nacks_of_other_fragments_sessions_or_types_are_sent() {
+    let mut h = harness(Some(Duration::from_secs(60)));
+    h.nack(7, 0, NackType::Dropped);
+    h.nack(7, 1, NackType::Dropped);
+    h.nack(8, 0, NackType::Dropped);
+    h.nack(7, 0, NackType::ErrorInRouting(2));
+    assert_eq!(h.nacks_sent(), 4);
+    assert_eq!(h.drone.stats().nacks_suppressed, 0);
+}
+
+#[test]
+fn every_nack_is_sent_without_a_window() {
+    let mut h = harness(None);
+    for _ in 0..3 {
+        h.nack(7, 0, NackType::Dropped);
+    }
+    assert_eq!(h.nacks_sent(), 3);
+    assert_eq!(h.drone.stats().nacks_suppressed, 0);
+}
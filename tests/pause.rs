@@ -0,0 +1,127 @@
+// A drone paused with its DroneHandle, with both pause policies and a bounded backlog: drone 1 between node 10 and
+// node 2.
+mod common;
+
+use common::{fragment, mock_network, MockNetwork, TIMEOUT};
+use rustaceans_wit_attitudes::{DroneOptions, DroneState, OverflowPolicy, PausePolicy};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+fn paused(options: DroneOptions) -> MockNetwork {
+    let network = mock_network(1, &[10, 2], options);
+    network.handle.pause();
+    network
+}
+
+fn nack_policy(pause_backlog_capacity: usize, overflow_policy: OverflowPolicy) -> DroneOptions {
+    DroneOptions { pause_policy: PausePolicy::Nack, pause_backlog_capacity, overflow_policy, ..DroneOptions::default() }
+}
+
+// the Ack of fragment `fragment_index`, from 10 to 2
+fn ack(fragment_index: u64) -> Packet {
+    Packet::new_ack(SourceRoutingHeader::new(vec![10, 1, 2], 1), 7, fragment_index)
+}
+
+fn ack_index(packet: &Packet) -> u64 {
+    match &packet.pack_type {
+        PacketType::Ack(ack) => ack.fragment_index,
+        other => panic!("expected an Ack, got {:?}", other),
+    }
+}
+
+#[test]
+fn buffer_policy_leaves_the_packets_in_the_channel() {
+    let network = paused(DroneOptions { pause_policy: PausePolicy::Buffer, ..DroneOptions::default() });
+    network.send(fragment(&[10, 1, 2], 1));
+    network.expect_nothing(10);
+    network.expect_nothing(2);
+
+    network.handle.resume();
+    assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+}
+
+#[test]
+fn nack_policy_nacks_fragments_and_keeps_the_rest_in_order() {
+    let network = paused(nack_policy(1024, OverflowPolicy::Block));
+    network.send(fragment(&[10, 1, 2], 1));
+    let nack = network.expect(10);
+    assert!(matches!(&nack.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(1))));
+
+    for fragment_index in 0..3 {
+        network.send(ack(fragment_index));
+    }
+    network.expect_nothing(2);
+    assert!(network.handle.stats().memory.queued_packets > 0);
+
+    network.handle.resume();
+    let acks: Vec<u64> = (0..3).map(|_| ack_index(&network.expect(2))).collect();
+    assert_eq!(acks, vec![0, 1, 2]);
+}
+
+#[test]
+fn full_backlog_drops_with_nack_dropped() {
+    let network = paused(nack_policy(2, OverflowPolicy::NackDropped));
+    for fragment_index in 0..3 {
+        network.send(ack(fragment_index));
+    }
+    let dropped = network.event_recv.recv_timeout(TIMEOUT).expect("no event for the third Ack");
+    assert!(matches!(&dropped, DroneEvent::PacketDropped(packet) if ack_index(packet) == 2));
+
+    network.handle.resume();
+    assert_eq!(ack_index(&network.expect(2)), 0);
+    assert_eq!(ack_index(&network.expect(2)), 1);
+    network.expect_nothing(2);
+    assert_eq!(network.handle.stats().packets_overflowed, 1);
+}
+
+#[test]
+fn full_backlog_shortcuts_with_the_shortcut_policy() {
+    let network = paused(nack_policy(0, OverflowPolicy::Shortcut));
+    network.send(ack(0));
+    let shortcut = network.event_recv.recv_timeout(TIMEOUT).expect("no shortcut for the Ack");
+    assert!(matches!(&shortcut, DroneEvent::ControllerShortcut(packet) if packet.routing_header.current_hop() == Some(2)));
+
+    network.handle.resume();
+    network.expect_nothing(2);
+}
+
+#[test]
+fn full_backlog_leaves_the_rest_in_the_channel_with_block() {
+    let network = paused(nack_policy(1, OverflowPolicy::Block));
+    for fragment_index in 0..3 {
+        network.send(ack(fragment_index));
+    }
+    network.expect_nothing(2);
+    assert_eq!(network.handle.stats().packets_overflowed, 0);
+
+    network.handle.resume();
+    let acks: Vec<u64> = (0..3).map(|_| ack_index(&network.expect(2))).collect();
+    assert_eq!(acks, vec![0, 1, 2]);
+}
+
+#[test]
+fn a_crash_forwards_the_backlog_in_order() {
+    let network = paused(nack_policy(1024, OverflowPolicy::Block));
+    for fragment_index in 0..3 {
+        network.send(ack(fragment_index));
+    }
+    network.expect_nothing(2);
+
+    network.command(DroneCommand::Crash);
+    network.wait_for(DroneState::Crashing);
+    let acks: Vec<u64> = (0..3).map(|_| ack_index(&network.expect(2))).collect();
+    assert_eq!(acks, vec![0, 1, 2]);
+}
+
+#[test]
+fn a_crash_nacks_the_fragments_left_in_the_channel_with_buffer() {
+    let network = paused(DroneOptions { pause_policy: PausePolicy::Buffer, ..DroneOptions::default() });
+    network.send(fragment(&[10, 1, 2], 1));
+    network.expect_nothing(10);
+
+    network.command(DroneCommand::Crash);
+    let nack = network.expect(10);
+    assert!(matches!(&nack.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(1))));
+    network.expect_nothing(2);
+}
@@ -0,0 +1,41 @@
+use rustaceans_wit_attitudes::{assert_pdr_fidelity, measure_pdr, DropMode, DroneOptions, PdrMeasurement};
+
+fn seeded(seed: u64) -> DroneOptions {
+    DroneOptions { seed: Some(seed), ..DroneOptions::default() }
+}
+
+#[test]
+fn random_drops_converge_to_the_pdr() {
+    for (seed, pdr) in [(1, 0.05), (2, 0.1), (3, 0.5), (4, 0.9)] {
+        assert_pdr_fidelity(pdr, seeded(seed), 5_000);
+    }
+}
+
+#[test]
+fn extreme_pdrs_drop_nothing_or_everything() {
+    assert_eq!(measure_pdr(0.0, seeded(5), 1_000).dropped, 0);
+    assert_eq!(measure_pdr(1.0, seeded(6), 1_000).dropped, 1_000);
+}
+
+#[test]
+fn exact_rate_drops_the_exact_count() {
+    let options = DroneOptions { drop_mode: DropMode::ExactRate { window: 20 }, ..DroneOptions::default() };
+    for pdr in [0.1, 0.35, 0.7] {
+        let measurement = measure_pdr(pdr, options.clone(), 2_000);
+        assert_eq!(measurement.dropped, (pdr * 20.0).floor() as u64 * 100, "{}", measurement);
+    }
+}
+
+#[test]
+fn wrong_drop_rates_are_caught() {
+    let measurement = PdrMeasurement { pdr: 0.1, fragments: 10_000, dropped: 1_200 };
+    assert!(!measurement.is_within(4.0));
+    let measurement = PdrMeasurement { pdr: 0.1, fragments: 10_000, dropped: 1_030 };
+    assert!(measurement.is_within(4.0));
+}
+
+#[test]
+#[should_panic(expected = "dropped 0 of 1000 fragments")]
+fn assert_within_reports_the_measurement() {
+    PdrMeasurement { pdr: 0.5, fragments: 1_000, dropped: 0 }.assert_within(4.0);
+}
@@ -0,0 +1,62 @@
+// The named option presets: every one of them is usable, and a drone built with one behaves like its kind of
+// intermediary. Drone 1 between node 10 and node 2, the timed ones run by a Stepper on a ManualClock.
+mod common;
+
+use crossbeam_channel::unbounded;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use common::{fragment, mock_network};
+use rustaceans_wit_attitudes::{DroneOptions, ManualClock, RustaceansWitAttitudesDrone, Stepper};
+use wg_2024::drone::Drone;
+use wg_2024::packet::{NackType, PacketType};
+
+#[test]
+fn every_preset_is_valid() {
+    for name in DroneOptions::PRESETS {
+        let options = DroneOptions::preset(name).unwrap_or_else(|| panic!("no preset {}", name));
+        assert_eq!(options.validate(), Ok(()), "{}", name);
+    }
+    assert!(DroneOptions::preset("unknown").is_none());
+}
+
+#[test]
+fn reliable_rejects_impossible_fragments() {
+    let network = mock_network(1, &[10, 2], DroneOptions::preset("reliable").unwrap());
+    let mut packet = fragment(&[10, 1, 2], 1);
+    if let PacketType::MsgFragment(fragment) = &mut packet.pack_type {
+        fragment.fragment_index = 1;
+    }
+    network.send(packet);
+    let nack = network.expect(10);
+    assert!(matches!(&nack.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::Dropped)));
+    network.expect_nothing(2);
+}
+
+#[test]
+fn slow_holds_fragments_back_for_the_link_latency() {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (send_2, from_2) = unbounded();
+    let (send_10, _from_10) = unbounded();
+    let senders = HashMap::from([(10, send_10), (2, send_2)]);
+    let clock = ManualClock::new();
+    let drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_options(DroneOptions::preset("slow").unwrap())
+        .with_clock(clock.clone());
+    let mut stepper = Stepper::new();
+    stepper.add(drone);
+
+    packet_send.send(fragment(&[10, 1, 2], 1)).unwrap();
+    stepper.run_until_idle();
+    assert!(from_2.try_recv().is_err());
+
+    // 50ms ± 25ms
+    clock.advance(Duration::from_millis(24));
+    stepper.run_until_idle();
+    assert!(from_2.try_recv().is_err());
+    clock.advance(Duration::from_millis(76));
+    stepper.run_until_idle();
+    assert!(matches!(from_2.try_recv().expect("fragment not forwarded").pack_type, PacketType::MsgFragment(_)));
+}
@@ -0,0 +1,52 @@
+// The names other groups' network initializers use for our drone. These only have to compile:
+// a rename that breaks an initializer breaks this file first.
+use std::any::TypeId;
+use std::collections::HashMap;
+
+use crossbeam_channel::unbounded;
+use rustaceans_wit_attitudes::{Drone, MyDrone, RustaceansWitAttitudesDrone};
+
+// the bounds of the initializers that run every drone on a thread of its own
+fn initializer_bounds<T: Drone + Send + 'static>() {}
+
+// how initializers build a drone without knowing its type
+fn build<T: Drone>() -> T {
+    let (controller_send, _controller_recv) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    T::new(1, controller_send, command_recv, packet_recv, HashMap::new(), 0.1)
+}
+
+#[test]
+fn aliases_name_the_same_drone() {
+    assert_eq!(TypeId::of::<MyDrone>(), TypeId::of::<RustaceansWitAttitudesDrone>());
+    assert_eq!(TypeId::of::<rustaceans_wit_attitudes::prelude::MyDrone>(), TypeId::of::<RustaceansWitAttitudesDrone>());
+    assert_eq!(
+        TypeId::of::<rustaceans_wit_attitudes::prelude::RustaceansWitAttitudesDrone>(),
+        TypeId::of::<RustaceansWitAttitudesDrone>()
+    );
+}
+
+#[test]
+fn drone_fits_the_initializers() {
+    initializer_bounds::<MyDrone>();
+    initializer_bounds::<RustaceansWitAttitudesDrone>();
+    let drone: MyDrone = build();
+    // some initializers keep the drones of every group in one collection
+    let _drones: Vec<Box<dyn wg_2024::drone::Drone + Send>> = vec![Box::new(drone)];
+}
+
+#[test]
+#[cfg(feature = "full")]
+fn prelude_has_what_initializers_use() {
+    use rustaceans_wit_attitudes::prelude::*;
+
+    let (controller_send, _controller_recv) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let drone = MyDrone::new(1, controller_send, command_recv, packet_recv, HashMap::new(), 0.1)
+        .with_options(DroneOptions::default());
+    let handle: DroneHandle = spawn(drone).unwrap();
+    assert_eq!(handle.id(), 1);
+    handle.shutdown().unwrap();
+}
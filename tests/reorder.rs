@@ -0,0 +1,95 @@
+// The reorder fault mode, the drone driven synchronously: drone 1 between node 10 and node 2.
+mod common;
+
+use crossbeam_channel::{unbounded, Receiver};
+use std::collections::HashMap;
+
+use common::fragment;
+use rustaceans_wit_attitudes::internals;
+use rustaceans_wit_attitudes::{DroneOptions, ReorderOptions, RustaceansWitAttitudesDrone};
+use wg_2024::controller::DroneCommand;
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+struct Harness {
+    drone: RustaceansWitAttitudesDrone,
+    neighbors: HashMap<NodeId, Receiver<Packet>>,
+}
+
+impl Harness {
+    fn received(&self, node_id: NodeId) -> Vec<Packet> {
+        self.neighbors[&node_id].try_iter().collect()
+    }
+}
+
+// every fragment is held back for one fragment
+fn always_reorder() -> DroneOptions {
+    DroneOptions { reorder: Some(ReorderOptions { probability: 1.0, max_displacement: 1 }), ..DroneOptions::default() }
+}
+
+fn harness(options: DroneOptions) -> Harness {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let mut senders = HashMap::new();
+    let mut neighbors = HashMap::new();
+    for node_id in [10, 2] {
+        let (send, recv) = unbounded();
+        senders.insert(node_id, send);
+        neighbors.insert(node_id, recv);
+    }
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0).with_options(options);
+    internals::start(&mut drone);
+    Harness { drone, neighbors }
+}
+
+fn numbered(fragment_index: u64) -> Packet {
+    let mut packet = fragment(&[10, 1, 2], 1);
+    if let PacketType::MsgFragment(fragment) = &mut packet.pack_type {
+        fragment.fragment_index = fragment_index;
+    }
+    packet
+}
+
+fn fragment_indexes(packets: &[Packet]) -> Vec<u64> {
+    packets.iter().map(|packet| match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => fragment.fragment_index,
+        other => panic!("expected a fragment, got {:?}", other),
+    }).collect()
+}
+
+#[test]
+fn held_fragments_go_out_after_the_next_forwarded_one() {
+    let mut h = harness(always_reorder());
+    internals::handle_packet(&mut h.drone, numbered(0));
+    internals::handle_packet(&mut h.drone, numbered(1));
+    assert!(h.received(2).is_empty());
+
+    internals::set_options(&mut h.drone, DroneOptions::default());
+    internals::handle_packet(&mut h.drone, numbered(2));
+    assert_eq!(fragment_indexes(&h.received(2)), vec![2, 0, 1]);
+}
+
+#[test]
+fn held_fragments_go_out_when_nothing_else_comes() {
+    let mut h = harness(always_reorder());
+    internals::handle_packet(&mut h.drone, numbered(0));
+    assert!(h.received(2).is_empty());
+
+    internals::housekeeping(&mut h.drone);
+    assert_eq!(fragment_indexes(&h.received(2)), vec![0]);
+}
+
+#[test]
+fn held_fragment_is_nacked_when_its_link_goes_away() {
+    let mut h = harness(always_reorder());
+    internals::handle_packet(&mut h.drone, numbered(0));
+    internals::handle_command(&mut h.drone, DroneCommand::RemoveSender(2));
+    internals::housekeeping(&mut h.drone);
+
+    let nacks = h.received(10);
+    assert_eq!(nacks.len(), 1);
+    assert_eq!(nacks[0].routing_header.hops, vec![1, 10]);
+    assert!(matches!(&nacks[0].pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(1))));
+}
@@ -0,0 +1,26 @@
+// Scenario steps run by the drone itself: drone 1 between node 10 and node 2.
+mod common;
+
+use common::{fragment, mock_network_with};
+use rustaceans_wit_attitudes::{Scenario, ScenarioAction};
+use wg_2024::packet::{NackType, PacketType};
+
+#[test]
+fn after_packets_fires_on_the_nth_packet() {
+    let scenario = Scenario::new().after_packets(3, ScenarioAction::SetPdr(1.0));
+    let network = mock_network_with(1, &[10, 2], |drone| drone.with_scenario(scenario));
+    for _ in 0..5 {
+        network.send(fragment(&[10, 1, 2], 1));
+    }
+
+    // the third packet still goes through, the step runs right after it
+    for _ in 0..3 {
+        assert!(matches!(network.expect(2).pack_type, PacketType::MsgFragment(_)));
+    }
+    network.expect_nothing(2);
+    for _ in 0..2 {
+        let nack = network.expect(10);
+        assert!(matches!(&nack.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::Dropped)));
+    }
+    assert_eq!(network.handle.stats().pdr, 1.0);
+}
@@ -0,0 +1,175 @@
+// Seeded schedules: the drones of a small network run in a `Stepper`, a generator picks which drone handles its
+// next input and when each controller command is released, so races between commands and packets that threads
+// hardly ever hit ("Crash arrives while a flood is mid-fan-out") come up within a few hundred seeds, and a failing
+// seed replays exactly.
+//
+// More seeds: cargo test --release --test schedules -- --ignored
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::panic;
+
+use rustaceans_wit_attitudes::{RustaceansWitAttitudesDrone, Stepper};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, Fragment, NodeType, Packet, PacketType};
+
+const CLIENT: NodeId = 100;
+const SERVER: NodeId = 200;
+// 1 - 2 - 4 and 1 - 3 - 4, the client at 1 and the server at 4
+const EDGES: [(NodeId, NodeId); 6] = [(1, 2), (1, 3), (2, 4), (3, 4), (CLIENT, 1), (4, SERVER)];
+const DRONES: [NodeId; 4] = [1, 2, 3, 4];
+const FRAGMENTS: u64 = 4;
+
+struct Network {
+    stepper: Stepper,
+    command_sends: HashMap<NodeId, Sender<DroneCommand>>,
+    event_recv: Receiver<DroneEvent>,
+    client_recv: Receiver<Packet>,
+    server_recv: Receiver<Packet>,
+}
+
+fn network() -> (Network, Sender<Packet>) {
+    let mut packet_sends = HashMap::new();
+    let mut packet_recvs = HashMap::new();
+    for node_id in EDGES.iter().flat_map(|(a, b)| [*a, *b]) {
+        if let Entry::Vacant(entry) = packet_sends.entry(node_id) {
+            let (packet_send, packet_recv) = unbounded();
+            entry.insert(packet_send);
+            packet_recvs.insert(node_id, packet_recv);
+        }
+    }
+    let (event_send, event_recv) = unbounded();
+    let mut stepper = Stepper::new();
+    let mut command_sends = HashMap::new();
+    for id in DRONES {
+        let senders = EDGES
+            .iter()
+            .filter_map(|(a, b)| if *a == id { Some(*b) } else if *b == id { Some(*a) } else { None })
+            .map(|neighbor| (neighbor, packet_sends[&neighbor].clone()))
+            .collect();
+        let (command_send, command_recv) = unbounded();
+        // the drone owns the only receiver of its channel, gone once it crashed
+        let packet_recv = packet_recvs.remove(&id).unwrap();
+        stepper.add(RustaceansWitAttitudesDrone::new(id, event_send.clone(), command_recv, packet_recv, senders, 0.0));
+        command_sends.insert(id, command_send);
+    }
+    let network = Network {
+        stepper,
+        command_sends,
+        event_recv,
+        client_recv: packet_recvs.remove(&CLIENT).unwrap(),
+        server_recv: packet_recvs.remove(&SERVER).unwrap(),
+    };
+    (network, packet_sends[&1].clone())
+}
+
+impl Network {
+    // step a random drone, releasing the next held command now and then, until all is quiet
+    fn explore(&mut self, rng: &mut StdRng, mut held: VecDeque<(NodeId, DroneCommand)>) {
+        let mut idle = 0;
+        while !held.is_empty() || idle < 4 * DRONES.len() {
+            if rng.gen_bool(0.2) {
+                if let Some((id, command)) = held.pop_front() {
+                    self.command_sends[&id].send(command).unwrap();
+                }
+            }
+            let id = DRONES[rng.gen_range(0..DRONES.len())];
+            idle = if self.stepper.step_drone(id) { 0 } else { idle + 1 };
+        }
+        self.stepper.run_until_idle();
+    }
+}
+
+fn fragment(fragment_index: u64) -> Packet {
+    let fragment = Fragment { fragment_index, total_n_fragments: FRAGMENTS, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader { hop_index: 1, hops: vec![CLIENT, 1, 2, 4, SERVER] }, 7, fragment)
+}
+
+fn flood_request() -> Packet {
+    let flood_request = FloodRequest { flood_id: 1, initiator_id: CLIENT, path_trace: vec![(CLIENT, NodeType::Client)] };
+    Packet::new_flood_request(SourceRoutingHeader { hop_index: 0, hops: Vec::new() }, 1, flood_request)
+}
+
+// Drone 2 crashes at a random point of a flood and of a transfer through it, the controller then removes it
+// from the topology as the protocol says.
+fn crash_mid_flood(seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (mut network, drone_1) = network();
+    drone_1.send(flood_request()).unwrap();
+    for fragment_index in 0..FRAGMENTS {
+        drone_1.send(fragment(fragment_index)).unwrap();
+    }
+    let held = VecDeque::from([
+        (2, DroneCommand::Crash),
+        (1, DroneCommand::RemoveSender(2)),
+        (4, DroneCommand::RemoveSender(2)),
+        (2, DroneCommand::RemoveSender(1)),
+        (2, DroneCommand::RemoveSender(4)),
+    ]);
+    network.explore(&mut rng, held);
+
+    let running: Vec<NodeId> = network.stepper.drones().iter().map(RustaceansWitAttitudesDrone::id).collect();
+    assert_eq!(running, vec![1, 3, 4], "drone 2 didn't complete its crash");
+
+    // every fragment is delivered, or Nacked back to the client directly or through the controller
+    let mut fragments = HashSet::new();
+    let mut delivered = 0;
+    // the server gets the flood too
+    for packet in network.server_recv.try_iter().filter(|packet| matches!(packet.pack_type, PacketType::MsgFragment(_))) {
+        assert!(fragments.insert(packet.get_fragment_index()), "fragment {} twice", packet.get_fragment_index());
+        delivered += 1;
+    }
+    let shortcuts = network.event_recv.try_iter().filter_map(|event| match event {
+        DroneEvent::ControllerShortcut(packet) => Some(packet),
+        _ => None,
+    });
+    let mut path_traces = Vec::new();
+    for packet in network.client_recv.try_iter().chain(shortcuts) {
+        match packet.pack_type {
+            PacketType::Nack(nack) => {
+                assert!(fragments.insert(nack.fragment_index), "fragment {} twice", nack.fragment_index);
+            }
+            PacketType::FloodResponse(flood_response) => path_traces.push(flood_response.path_trace),
+            // the client doesn't take part in the flood it initiated
+            PacketType::FloodRequest(_) => {}
+            pack_type => panic!("unexpected {:?}", pack_type),
+        }
+    }
+    let lost: Vec<u64> = (0..FRAGMENTS).filter(|fragment_index| !fragments.contains(fragment_index)).collect();
+    assert!(lost.is_empty(), "{} delivered, lost {:?}", delivered, lost);
+
+    // whatever drone 2 saw of the flood, no answer loops (the stub server never answers, there may be none)
+    for path_trace in &path_traces {
+        let nodes: HashSet<NodeId> = path_trace.iter().map(|(node_id, _)| *node_id).collect();
+        assert_eq!(nodes.len(), path_trace.len(), "loop in {:?}", path_trace);
+        assert_eq!(path_trace[0], (CLIENT, NodeType::Client));
+    }
+}
+
+fn explore_seeds(seeds: std::ops::Range<u64>) {
+    for seed in seeds {
+        if let Err(panic) = panic::catch_unwind(|| crash_mid_flood(seed)) {
+            let message = match (panic.downcast_ref::<String>(), panic.downcast_ref::<&str>()) {
+                (Some(message), _) => message.clone(),
+                (_, Some(message)) => message.to_string(),
+                _ => String::new(),
+            };
+            panic!("seed {}: {}", seed, message);
+        }
+    }
+}
+
+#[test]
+fn a_crash_at_any_point_of_a_flood_and_a_transfer() {
+    explore_seeds(0..300);
+}
+
+#[test]
+#[ignore = "heavy, run with --ignored"]
+fn a_crash_at_any_point_of_a_flood_and_a_transfer_many_seeds() {
+    explore_seeds(300..20_000);
+}
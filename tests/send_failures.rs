@@ -0,0 +1,138 @@
+// Failed sends forced with internals::fail_sends, the drone driven synchronously: drone 1 between node 10 and
+// node 2, with node 3 as a third neighbor.
+mod common;
+
+use crossbeam_channel::{unbounded, Receiver};
+use std::collections::HashMap;
+
+use common::{ack, flood_request, fragment, nack};
+use rustaceans_wit_attitudes::internals::{self, InjectedFailure};
+use rustaceans_wit_attitudes::{AuxEvent, AuxEventKind, DroneOptions, OverflowPolicy, PacketKind, RustaceansWitAttitudesDrone};
+use wg_2024::controller::DroneEvent;
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+struct Harness {
+    drone: RustaceansWitAttitudesDrone,
+    events: Receiver<DroneEvent>,
+    aux: Receiver<AuxEvent>,
+    neighbors: HashMap<NodeId, Receiver<Packet>>,
+}
+
+impl Harness {
+    fn received(&self, node_id: NodeId) -> Vec<Packet> {
+        self.neighbors[&node_id].try_iter().collect()
+    }
+}
+
+fn harness(options: DroneOptions) -> Harness {
+    let (event_send, events) = unbounded();
+    let (aux_send, aux) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let mut senders = HashMap::new();
+    let mut neighbors = HashMap::new();
+    for node_id in [10, 2, 3] {
+        let (send, recv) = unbounded();
+        senders.insert(node_id, send);
+        neighbors.insert(node_id, recv);
+    }
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_options(options)
+        .with_aux_sender(aux_send);
+    internals::start(&mut drone);
+    Harness { drone, events, aux, neighbors }
+}
+
+#[test]
+fn disconnected_ack_becomes_a_shortcut() {
+    let mut h = harness(DroneOptions::default());
+    internals::fail_sends(&mut h.drone, Some(2), Some(PacketKind::Ack), InjectedFailure::Disconnected);
+    internals::handle_packet(&mut h.drone, ack(&[10, 1, 2], 1));
+
+    assert!(h.received(2).is_empty());
+    let events: Vec<DroneEvent> = h.events.try_iter().collect();
+    assert!(matches!(events.as_slice(), [DroneEvent::ControllerShortcut(packet)] if matches!(packet.pack_type, PacketType::Ack(_))));
+}
+
+#[test]
+fn full_channel_shortcuts_a_nack_with_the_shortcut_policy() {
+    let mut h = harness(DroneOptions { overflow_policy: OverflowPolicy::Shortcut, ..DroneOptions::default() });
+    internals::fail_sends(&mut h.drone, Some(2), None, InjectedFailure::Full);
+    internals::handle_packet(&mut h.drone, nack(&[10, 1, 2], 1, NackType::Dropped));
+
+    assert!(h.received(2).is_empty());
+    let events: Vec<DroneEvent> = h.events.try_iter().collect();
+    assert!(matches!(events.as_slice(), [DroneEvent::ControllerShortcut(packet)] if matches!(packet.pack_type, PacketType::Nack(_))));
+}
+
+#[test]
+fn full_channel_nacks_a_fragment_as_dropped() {
+    let mut h = harness(DroneOptions { overflow_policy: OverflowPolicy::NackDropped, ..DroneOptions::default() });
+    internals::fail_sends(&mut h.drone, Some(2), Some(PacketKind::Fragment), InjectedFailure::Full);
+    internals::handle_packet(&mut h.drone, fragment(&[10, 1, 2], 1));
+
+    assert!(h.received(2).is_empty());
+    let nacks = h.received(10);
+    assert_eq!(nacks.len(), 1);
+    assert_eq!(nacks[0].routing_header.hops, vec![1, 10]);
+    assert!(matches!(&nacks[0].pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::Dropped)));
+}
+
+#[test]
+fn disconnected_fragment_is_nacked_as_an_error_in_routing() {
+    let mut h = harness(DroneOptions::default());
+    internals::fail_sends(&mut h.drone, Some(2), Some(PacketKind::Fragment), InjectedFailure::Disconnected);
+    internals::handle_packet(&mut h.drone, fragment(&[10, 1, 2], 1));
+
+    assert!(h.received(2).is_empty());
+    let nacks = h.received(10);
+    assert_eq!(nacks.len(), 1);
+    assert_eq!(nacks[0].routing_header.hops, vec![1, 10]);
+    assert!(matches!(&nacks[0].pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::ErrorInRouting(2))));
+    assert!(!h.aux.try_iter().any(|event| matches!(event.kind, AuxEventKind::Panicked { .. })));
+
+    // still running
+    internals::clear_send_failures(&mut h.drone);
+    internals::handle_packet(&mut h.drone, fragment(&[10, 1, 2], 1));
+    assert_eq!(h.received(2).len(), 1);
+}
+
+#[test]
+fn only_the_chosen_neighbor_fails() {
+    let mut h = harness(DroneOptions::default());
+    internals::fail_sends(&mut h.drone, Some(3), None, InjectedFailure::Disconnected);
+    internals::handle_packet(&mut h.drone, flood_request(1, 10, &[10]));
+
+    assert_eq!(h.received(2).len(), 1);
+    assert!(h.received(3).is_empty());
+    let failed = h.aux.try_iter().find_map(|event| match event.kind {
+        AuxEventKind::FloodSendFailed { to, .. } => Some(to),
+        _ => None,
+    });
+    assert_eq!(failed, Some(3));
+}
+
+#[test]
+fn only_the_chosen_packet_type_fails() {
+    let mut h = harness(DroneOptions::default());
+    internals::fail_sends(&mut h.drone, None, Some(PacketKind::Ack), InjectedFailure::Disconnected);
+    internals::handle_packet(&mut h.drone, fragment(&[10, 1, 2], 1));
+    internals::handle_packet(&mut h.drone, ack(&[2, 1, 10], 1));
+
+    assert_eq!(h.received(2).len(), 1);
+    assert!(h.received(10).is_empty());
+}
+
+#[test]
+fn cleared_failures_send_again() {
+    let mut h = harness(DroneOptions::default());
+    internals::fail_sends(&mut h.drone, None, None, InjectedFailure::Disconnected);
+    internals::handle_packet(&mut h.drone, ack(&[10, 1, 2], 1));
+    assert!(h.received(2).is_empty());
+
+    internals::clear_send_failures(&mut h.drone);
+    internals::handle_packet(&mut h.drone, ack(&[10, 1, 2], 1));
+    assert_eq!(h.received(2).len(), 1);
+}
@@ -0,0 +1,137 @@
+// A neighbor slowed down or stalled with SlowSender: the drone gives up on it after the send timeout and keeps
+// answering the simulation controller. Drone 1 between node 10 and the slow node 2.
+mod common;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{fragment, TIMEOUT};
+use rustaceans_wit_attitudes::{spawn, ChannelSender, DroneHandle, DroneOptions, OverflowPolicy, RustaceansWitAttitudesDrone, SendFailure, SlowSender};
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+struct SlowNetwork {
+    handle: DroneHandle,
+    command_send: Sender<DroneCommand>,
+    packet_send: Sender<Packet>,
+    slow: SlowSender<Packet>,
+    neighbors: HashMap<NodeId, Receiver<Packet>>,
+}
+
+fn slow_network(options: DroneOptions) -> SlowNetwork {
+    let (event_send, _event_recv) = unbounded();
+    let (command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (previous_send, previous_recv) = unbounded();
+    let (next_send, next_recv) = unbounded();
+    let slow = SlowSender::new(next_send);
+    let mut senders: HashMap<NodeId, Box<dyn ChannelSender<Packet>>> = HashMap::new();
+    senders.insert(10, Box::new(previous_send));
+    senders.insert(2, Box::new(slow.clone()));
+    let drone = RustaceansWitAttitudesDrone::with_channels(1, event_send, command_recv, packet_recv, senders, 0.0).with_options(options);
+    let handle = spawn(drone).unwrap();
+    let neighbors = HashMap::from([(10, previous_recv), (2, next_recv)]);
+    SlowNetwork { handle, command_send, packet_send, slow, neighbors }
+}
+
+fn nack_dropped_after(timeout: Duration) -> DroneOptions {
+    DroneOptions { overflow_policy: OverflowPolicy::NackDropped, overflow_timeout: timeout, ..DroneOptions::default() }
+}
+
+#[test]
+fn stalled_send_times_out_as_full() {
+    let (send, recv) = unbounded();
+    let slow = SlowSender::new(send);
+    slow.stall();
+    let started = Instant::now();
+    assert!(matches!(slow.send_timeout(1, Duration::from_millis(50)), Err(SendFailure::Full(1))));
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    assert_eq!(slow.is_full(), Some(true));
+
+    slow.resume();
+    assert!(slow.send_timeout(2, Duration::from_millis(50)).is_ok());
+    assert_eq!(recv.try_recv(), Ok(2));
+}
+
+#[test]
+fn resume_lets_a_waiting_send_through() {
+    let (send, recv) = unbounded();
+    let slow = SlowSender::new(send);
+    slow.stall();
+    let waiting = slow.clone();
+    let sender = thread::spawn(move || ChannelSender::send(&waiting, 1));
+    thread::sleep(Duration::from_millis(50));
+    assert!(recv.try_recv().is_err());
+
+    slow.resume();
+    assert!(sender.join().unwrap().is_ok());
+    assert_eq!(recv.recv_timeout(TIMEOUT), Ok(1));
+}
+
+#[test]
+fn delay_holds_every_send() {
+    let (send, recv) = unbounded();
+    let slow = SlowSender::new(send);
+    slow.set_delay(Duration::from_millis(50));
+    let started = Instant::now();
+    assert!(ChannelSender::send(&slow, 1).is_ok());
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    // shorter than the delay: given up, never sent
+    assert!(matches!(slow.send_timeout(2, Duration::from_millis(10)), Err(SendFailure::Full(2))));
+    assert_eq!(recv.try_iter().collect::<Vec<_>>(), vec![1]);
+}
+
+#[test]
+fn stalled_neighbor_gets_the_fragment_nacked_after_the_send_timeout() {
+    let network = slow_network(nack_dropped_after(Duration::from_millis(50)));
+    network.slow.stall();
+    network.packet_send.send(fragment(&[10, 1, 2], 1)).unwrap();
+
+    let nack = network.neighbors[&10].recv_timeout(TIMEOUT).expect("no Nack for the fragment");
+    assert!(matches!(&nack.pack_type, PacketType::Nack(nack) if matches!(nack.nack_type, NackType::Dropped)));
+    assert!(network.neighbors[&2].try_recv().is_err());
+
+    network.slow.resume();
+    network.packet_send.send(fragment(&[10, 1, 2], 1)).unwrap();
+    assert!(network.neighbors[&2].recv_timeout(TIMEOUT).is_ok());
+}
+
+#[test]
+fn drone_answers_commands_while_a_neighbor_is_stalled() {
+    let network = slow_network(nack_dropped_after(Duration::from_millis(20)));
+    // never resumed: a drone waiting for the neighbor would never see the commands
+    network.slow.stall();
+    for _ in 0..5 {
+        network.packet_send.send(fragment(&[10, 1, 2], 1)).unwrap();
+    }
+    network.command_send.send(DroneCommand::SetPacketDropRate(0.5)).unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+    while network.handle.stats().pdr != 0.5 {
+        assert!(Instant::now() < deadline, "SetPacketDropRate not applied");
+        thread::sleep(Duration::from_millis(1));
+    }
+    // every fragment Nacked, dropped or given up on
+    for _ in 0..5 {
+        network.neighbors[&10].recv_timeout(TIMEOUT).expect("a fragment was never Nacked");
+    }
+}
+
+#[test]
+fn outgoing_queue_keeps_a_slow_neighbor_off_the_drone() {
+    let network = slow_network(DroneOptions { outgoing_queue: Some(8), ..DroneOptions::default() });
+    network.slow.set_delay(Duration::from_millis(200));
+    for _ in 0..3 {
+        network.packet_send.send(fragment(&[10, 1, 2], 1)).unwrap();
+    }
+    network.command_send.send(DroneCommand::SetPacketDropRate(0.5)).unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+    while network.handle.stats().pdr != 0.5 {
+        assert!(Instant::now() < deadline, "SetPacketDropRate not applied");
+        thread::sleep(Duration::from_millis(1));
+    }
+    // applied while the fragments are still on their way
+    assert!(network.neighbors[&2].try_iter().count() < 3);
+}
@@ -0,0 +1,137 @@
+// The forwarding rules of the protocol specification, through the public `Drone` interface only, so that they hold
+// for both drones: the full one, and the spec-only one of `minimal` with `cargo test --no-default-features`.
+// Drone 1 between node 10 and node 2.
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use rustaceans_wit_attitudes::{Drone, RustaceansWitAttitudesDrone};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Ack, FloodRequest, Fragment, NackType, NodeType, Packet, PacketType, FRAGMENT_DSIZE};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Line {
+    thread: JoinHandle<()>,
+    command_send: Sender<DroneCommand>,
+    packet_send: Sender<Packet>,
+    events: Receiver<DroneEvent>,
+    from_10: Receiver<Packet>,
+    from_2: Receiver<Packet>,
+}
+
+fn line(pdr: f32) -> Line {
+    let (event_send, events) = unbounded();
+    let (command_send, command_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    let (send_10, from_10) = unbounded();
+    let (send_2, from_2) = unbounded();
+    let neighbors = HashMap::from([(10, send_10), (2, send_2)]);
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, neighbors, pdr);
+    let thread = thread::spawn(move || drone.run());
+    Line { thread, command_send, packet_send, events, from_10, from_2 }
+}
+
+fn fragment(hops: &[NodeId], hop_index: usize) -> Packet {
+    let fragment = Fragment { fragment_index: 3, total_n_fragments: 5, length: 1, data: [0; FRAGMENT_DSIZE] };
+    Packet::new_fragment(SourceRoutingHeader::new(hops.to_vec(), hop_index), 9, fragment)
+}
+
+fn nack_type(packet: &Packet) -> &NackType {
+    match &packet.pack_type {
+        PacketType::Nack(nack) => &nack.nack_type,
+        other => panic!("expected a Nack, got {:?}", other),
+    }
+}
+
+impl Line {
+    fn send(&self, packet: Packet) {
+        self.packet_send.send(packet).unwrap();
+    }
+}
+
+#[test]
+fn a_fragment_is_forwarded_to_the_next_hop() {
+    let line = line(0.0);
+    line.send(fragment(&[10, 1, 2], 1));
+    let packet = line.from_2.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(packet.routing_header, SourceRoutingHeader::new(vec![10, 1, 2], 2));
+    assert!(matches!(line.events.recv_timeout(TIMEOUT), Ok(DroneEvent::PacketSent(_))));
+}
+
+#[test]
+fn a_dropped_fragment_is_reported_and_nacked() {
+    let line = line(1.0);
+    line.send(fragment(&[10, 1, 2], 1));
+    let nack = line.from_10.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(nack_type(&nack), NackType::Dropped));
+    assert_eq!(nack.routing_header, SourceRoutingHeader::new(vec![1, 10], 1));
+    assert!(matches!(line.events.recv_timeout(TIMEOUT), Ok(DroneEvent::PacketDropped(_))));
+    assert!(line.from_2.try_recv().is_err());
+}
+
+#[test]
+fn routing_errors_are_nacked_with_their_type() {
+    let line = line(0.0);
+    line.send(fragment(&[10, 1, 7], 1));
+    assert!(matches!(nack_type(&line.from_10.recv_timeout(TIMEOUT).unwrap()), NackType::ErrorInRouting(_)));
+    line.send(fragment(&[10, 1], 1));
+    assert!(matches!(nack_type(&line.from_10.recv_timeout(TIMEOUT).unwrap()), NackType::DestinationIsDrone));
+    line.send(fragment(&[10, 5, 2], 1));
+    assert!(matches!(nack_type(&line.from_10.recv_timeout(TIMEOUT).unwrap()), NackType::UnexpectedRecipient(1)));
+}
+
+#[test]
+fn an_ack_that_cant_be_forwarded_goes_to_the_controller() {
+    let line = line(0.0);
+    line.send(Packet::new_ack(SourceRoutingHeader::new(vec![2, 1, 7], 1), 9, 3));
+    let shortcut = loop {
+        match line.events.recv_timeout(TIMEOUT).unwrap() {
+            DroneEvent::ControllerShortcut(packet) => break packet,
+            _ => continue,
+        }
+    };
+    assert!(matches!(shortcut.pack_type, PacketType::Ack(Ack { fragment_index: 3 })));
+}
+
+#[test]
+fn a_new_flood_is_forwarded_and_a_known_one_answered() {
+    let line = line(0.0);
+    let flood_request = FloodRequest { flood_id: 4, initiator_id: 10, path_trace: vec![(10, NodeType::Client)] };
+    let packet = Packet::new_flood_request(SourceRoutingHeader::new(Vec::new(), 0), 9, flood_request);
+    line.send(packet.clone());
+    match line.from_2.recv_timeout(TIMEOUT).unwrap().pack_type {
+        PacketType::FloodRequest(forwarded) => assert_eq!(forwarded.path_trace, vec![(10, NodeType::Client), (1, NodeType::Drone)]),
+        other => panic!("expected a FloodRequest, got {:?}", other),
+    }
+
+    line.send(packet);
+    let response = line.from_10.recv_timeout(TIMEOUT).unwrap();
+    match response.pack_type {
+        PacketType::FloodResponse(flood_response) => {
+            assert_eq!(flood_response.flood_id, 4);
+            assert_eq!(flood_response.path_trace, vec![(10, NodeType::Client), (1, NodeType::Drone)]);
+        }
+        other => panic!("expected a FloodResponse, got {:?}", other),
+    }
+    assert_eq!(response.routing_header.hops, vec![1, 10]);
+}
+
+#[test]
+fn a_crashing_drone_nacks_fragments_and_stops_once_its_senders_are_removed() {
+    let line = line(0.0);
+    line.command_send.send(DroneCommand::Crash).unwrap();
+    line.send(fragment(&[10, 1, 2], 1));
+    assert!(matches!(nack_type(&line.from_10.recv_timeout(TIMEOUT).unwrap()), NackType::ErrorInRouting(1)));
+
+    line.command_send.send(DroneCommand::RemoveSender(10)).unwrap();
+    line.command_send.send(DroneCommand::RemoveSender(2)).unwrap();
+    drop(line.packet_send);
+    let deadline = Instant::now() + TIMEOUT;
+    while !line.thread.is_finished() {
+        assert!(Instant::now() < deadline, "the drone is still running");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,142 @@
+// Nacks counted by type and the CSV export of the statistics, the drone driven synchronously: drone 1 between
+// node 10 and node 2, with node 3 as a third neighbor.
+mod common;
+
+use crossbeam_channel::{unbounded, Receiver};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use common::fragment;
+use rustaceans_wit_attitudes::internals;
+use rustaceans_wit_attitudes::{write_stats_csv, ChaosOptions, DroneOptions, NackCounts, RustaceansWitAttitudesDrone};
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+struct Harness {
+    drone: RustaceansWitAttitudesDrone,
+    neighbors: HashMap<NodeId, Receiver<Packet>>,
+}
+
+fn harness(options: DroneOptions) -> Harness {
+    let (event_send, _events) = unbounded();
+    let (_command_send, command_recv) = unbounded();
+    let (_packet_send, packet_recv) = unbounded();
+    let mut senders = HashMap::new();
+    let mut neighbors = HashMap::new();
+    for node_id in [10, 2, 3] {
+        let (send, recv) = unbounded();
+        senders.insert(node_id, send);
+        neighbors.insert(node_id, recv);
+    }
+    let mut drone = RustaceansWitAttitudesDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0).with_options(options);
+    internals::start(&mut drone);
+    Harness { drone, neighbors }
+}
+
+fn all_nack_types() -> [NackType; 4] {
+    [NackType::ErrorInRouting(2), NackType::DestinationIsDrone, NackType::Dropped, NackType::UnexpectedRecipient(1)]
+}
+
+// fragment 0 of `session_id` from 10 to `to`
+fn fragment_of(session_id: u64, to: NodeId) -> Packet {
+    let fragment = Fragment { fragment_index: 0, total_n_fragments: 1, length: 0, data: [0; 128] };
+    Packet::new_fragment(SourceRoutingHeader::new(vec![10, 1, to], 1), session_id, fragment)
+}
+
+fn csv(drone: &RustaceansWitAttitudesDrone) -> Vec<String> {
+    let mut csv = Vec::new();
+    write_stats_csv(&mut csv, [(drone.id(), drone.stats())]).unwrap();
+    String::from_utf8(csv).unwrap().lines().map(str::to_string).collect()
+}
+
+fn rows<'a>(csv: &'a [String], scope: &str) -> Vec<&'a str> {
+    csv.iter().map(String::as_str).filter(|row| row.split(',').nth(1) == Some(scope)).collect()
+}
+
+#[test]
+fn one_nack_of_each_type_is_counted_once() {
+    let mut h = harness(DroneOptions::default());
+    for nack_type in all_nack_types() {
+        internals::send_nack(&mut h.drone, &fragment(&[10, 1, 2], 1), nack_type);
+    }
+    assert_eq!(h.neighbors[&10].try_iter().count(), 4);
+    let expected = NackCounts { error_in_routing: 1, destination_is_drone: 1, dropped: 1, unexpected_recipient: 1 };
+    assert_eq!(h.drone.stats().nacks_by_type, expected);
+    assert_eq!(h.drone.stats().nacks_sent, 4);
+
+    let csv = csv(&h.drone);
+    assert_eq!(csv[0], "drone,scope,key,counter,value");
+    assert_eq!(rows(&csv, "nack_type"), vec![
+        "1,nack_type,ErrorInRouting,sent,1",
+        "1,nack_type,DestinationIsDrone,sent,1",
+        "1,nack_type,Dropped,sent,1",
+        "1,nack_type,UnexpectedRecipient,sent,1",
+    ]);
+}
+
+#[test]
+fn neighbors_and_sessions_are_written_in_order() {
+    let mut h = harness(DroneOptions::default());
+    // sessions 9 and 3 through 2, session 5 through 3
+    for (session_id, to) in [(9, 2), (3, 2), (5, 3)] {
+        internals::handle_packet(&mut h.drone, fragment_of(session_id, to));
+    }
+    assert_eq!(h.neighbors[&2].try_iter().count(), 2);
+    assert_eq!(h.neighbors[&3].try_iter().count(), 1);
+
+    let csv = csv(&h.drone);
+    assert_eq!(rows(&csv, "neighbor"), vec![
+        "1,neighbor,2,packets_forwarded,2",
+        "1,neighbor,2,packets_received,0",
+        "1,neighbor,2,send_failures,0",
+        "1,neighbor,2,nacks_sent,0",
+        "1,neighbor,3,packets_forwarded,1",
+        "1,neighbor,3,packets_received,0",
+        "1,neighbor,3,send_failures,0",
+        "1,neighbor,3,nacks_sent,0",
+        "1,neighbor,10,packets_forwarded,0",
+        "1,neighbor,10,packets_received,3",
+        "1,neighbor,10,send_failures,0",
+        "1,neighbor,10,nacks_sent,0",
+    ]);
+    assert_eq!(rows(&csv, "session"), vec![
+        "1,session,3,fragments_seen,1",
+        "1,session,3,fragments_dropped,0",
+        "1,session,3,fragments_forwarded,1",
+        "1,session,5,fragments_seen,1",
+        "1,session,5,fragments_dropped,0",
+        "1,session,5,fragments_forwarded,1",
+        "1,session,9,fragments_seen,1",
+        "1,session,9,fragments_dropped,0",
+        "1,session,9,fragments_forwarded,1",
+    ]);
+}
+
+#[test]
+fn misreported_nack_is_counted_with_the_type_sent() {
+    let chaos = ChaosOptions {
+        probability: 1.0,
+        corrupt_path_traces: false,
+        reorder_hops: false,
+        delay_acks: false,
+        ack_delay: Duration::ZERO,
+        misreport_nacks: true,
+    };
+    let mut h = harness(DroneOptions { chaos: Some(chaos), ..DroneOptions::default() });
+    internals::send_nack(&mut h.drone, &fragment(&[10, 1, 2], 1), NackType::Dropped);
+
+    let nack = h.neighbors[&10].try_recv().expect("no Nack sent");
+    let PacketType::Nack(nack) = nack.pack_type else {
+        panic!("expected a Nack, got {:?}", nack.pack_type);
+    };
+    let mut expected = NackCounts::default();
+    match nack.nack_type {
+        NackType::ErrorInRouting(_) => expected.error_in_routing = 1,
+        NackType::DestinationIsDrone => expected.destination_is_drone = 1,
+        NackType::Dropped => panic!("the Nack type wasn't rewritten"),
+        NackType::UnexpectedRecipient(_) => expected.unexpected_recipient = 1,
+    }
+    assert_eq!(h.drone.stats().nacks_by_type, expected);
+    assert!(rows(&csv(&h.drone), "nack_type").contains(&"1,nack_type,Dropped,sent,0"));
+}
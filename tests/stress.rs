@@ -0,0 +1,140 @@
+// Stress harness: fragments blasted through a chain of drones with random pdrs while a controller thread
+// keeps sending commands. Nothing may panic or hang, and every fragment has to be accounted for: delivered to
+// the server or dropped by exactly one drone, which Nacks it back to the client.
+//
+// The heavy run is ignored by default: cargo test --release --test stress -- --ignored
+mod common;
+
+use crossbeam_channel::unbounded;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{drone_network, fragment};
+use rustaceans_wit_attitudes::DroneOptions;
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, PacketType};
+
+const CLIENT: NodeId = 100;
+const SERVER: NodeId = 200;
+// neighbor added and removed by the controller thread, nothing is ever routed to it
+const PHANTOM: NodeId = 150;
+const SESSION_ID: u64 = 7;
+
+struct Stress {
+    drones: NodeId,
+    fragments: u64,
+    max_pdr: f32,
+    seed: u64,
+    deadline: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Report {
+    delivered: u64,
+    nacked: u64,
+}
+
+fn stress(config: Stress) -> Report {
+    let edges: Vec<(NodeId, NodeId)> = (1..config.drones).map(|id| (id, id + 1)).collect();
+    let network = drone_network(&edges, &[(CLIENT, 1), (SERVER, config.drones)], DroneOptions::default());
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    for command_send in network.command_sends.values() {
+        command_send.send(DroneCommand::SetPacketDropRate(rng.gen_range(0.0..config.max_pdr))).unwrap();
+    }
+
+    // the controller keeps changing pdrs and neighbors while the fragments go through
+    let done = Arc::new(AtomicBool::new(false));
+    let controller = {
+        let done = done.clone();
+        let command_sends: Vec<_> = network.command_sends.values().cloned().collect();
+        let max_pdr = config.max_pdr;
+        let mut rng = StdRng::seed_from_u64(config.seed + 1);
+        thread::spawn(move || {
+            let (phantom_send, _phantom_recv) = unbounded();
+            while !done.load(Ordering::Relaxed) {
+                let command_send = &command_sends[rng.gen_range(0..command_sends.len())];
+                let command = match rng.gen_range(0..3) {
+                    0 => DroneCommand::SetPacketDropRate(rng.gen_range(0.0..max_pdr)),
+                    1 => DroneCommand::AddSender(PHANTOM, phantom_send.clone()),
+                    _ => DroneCommand::RemoveSender(PHANTOM),
+                };
+                command_send.send(command).unwrap();
+                thread::sleep(Duration::from_micros(50));
+            }
+        })
+    };
+
+    let route: Vec<NodeId> = [CLIENT].into_iter().chain(1..=config.drones).chain([SERVER]).collect();
+    let client = &network.stubs[&CLIENT];
+    let server = &network.stubs[&SERVER];
+    for fragment_index in 0..config.fragments {
+        let mut packet = fragment(&route, 1);
+        packet.session_id = SESSION_ID;
+        if let PacketType::MsgFragment(fragment) = &mut packet.pack_type {
+            fragment.fragment_index = fragment_index;
+        }
+        client.send.send(packet).unwrap();
+    }
+
+    let mut report = Report::default();
+    let deadline = Instant::now() + config.deadline;
+    while report.delivered + report.nacked < config.fragments {
+        assert!(Instant::now() < deadline, "deadlock? {:?} of {} fragments accounted for", report, config.fragments);
+        while let Ok(packet) = server.recv.try_recv() {
+            assert!(matches!(packet.pack_type, PacketType::MsgFragment(_)), "{:?}", packet.pack_type);
+            report.delivered += 1;
+        }
+        while let Ok(packet) = client.recv.try_recv() {
+            let PacketType::Nack(nack) = packet.pack_type else {
+                panic!("expected a Nack, got {:?}", packet.pack_type);
+            };
+            assert!(matches!(nack.nack_type, NackType::Dropped), "{:?}", nack.nack_type);
+            report.nacked += 1;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    done.store(true, Ordering::Relaxed);
+    controller.join().unwrap();
+
+    // nothing more may come: each fragment was accounted for once
+    assert!(server.recv.recv_timeout(common::QUIET).is_err());
+    assert!(client.recv.try_recv().is_err());
+
+    let mut dropped = 0;
+    let mut reached = config.fragments;
+    for id in 1..=config.drones {
+        let handle = &network.drones[&id];
+        assert!(!handle.is_finished(), "drone {} stopped", id);
+        let stats = handle.stats();
+        let session = &stats.sessions[&SESSION_ID];
+        assert_eq!(session.fragments_seen, reached, "drone {}", id);
+        assert_eq!(session.fragments_forwarded + session.fragments_dropped, session.fragments_seen, "drone {}", id);
+        assert_eq!(stats.nacks_sent, session.fragments_dropped, "drone {}", id);
+        dropped += session.fragments_dropped;
+        reached = session.fragments_forwarded;
+    }
+    assert_eq!(reached, report.delivered);
+    assert_eq!(dropped, report.nacked);
+    for (id, handle) in network.drones {
+        assert!(handle.shutdown().is_ok(), "drone {} panicked", id);
+    }
+    report
+}
+
+#[test]
+fn a_short_chain_accounts_for_every_fragment() {
+    let report = stress(Stress { drones: 4, fragments: 500, max_pdr: 0.2, seed: 1, deadline: Duration::from_secs(30) });
+    assert_eq!(report.delivered + report.nacked, 500);
+}
+
+#[test]
+#[ignore = "heavy, run with --ignored"]
+fn tens_of_thousands_of_fragments_through_twelve_drones() {
+    let report = stress(Stress { drones: 12, fragments: 50_000, max_pdr: 0.1, seed: 2, deadline: Duration::from_secs(300) });
+    assert!(report.delivered > 0 && report.nacked > 0, "{:?}", report);
+}
@@ -0,0 +1,38 @@
+// DroneOptions::unexpected_recipient_policy other than the spec's Nack (tests/spec.rs has that one): drone 1
+// between node 10 and node 2, handed packets whose current hop is another node.
+mod common;
+
+use common::{fragment, mock_network};
+use rustaceans_wit_attitudes::{DroneOptions, UnexpectedRecipientPolicy};
+
+fn options(unexpected_recipient_policy: UnexpectedRecipientPolicy) -> DroneOptions {
+    DroneOptions { unexpected_recipient_policy, ..DroneOptions::default() }
+}
+
+#[test]
+fn drop_tells_no_one() {
+    let network = mock_network(1, &[10, 2], options(UnexpectedRecipientPolicy::Drop));
+    network.send(fragment(&[10, 5, 2], 1));
+    network.expect_nothing(10);
+    network.expect_nothing(2);
+}
+
+#[test]
+fn fix_and_forward_takes_the_place_of_the_current_hop() {
+    let network = mock_network(1, &[10, 2], options(UnexpectedRecipientPolicy::FixAndForward));
+    network.send(fragment(&[10, 5, 2], 1));
+    let forwarded = network.expect(2);
+    assert_eq!(forwarded.routing_header.hops, vec![10, 1, 2]);
+    assert_eq!(forwarded.routing_header.hop_index, 2);
+    network.expect_nothing(10);
+}
+
+#[test]
+fn fix_and_forward_continues_from_its_position_in_the_route() {
+    let network = mock_network(1, &[10, 2], options(UnexpectedRecipientPolicy::FixAndForward));
+    network.send(fragment(&[10, 3, 1, 2], 1));
+    let forwarded = network.expect(2);
+    assert_eq!(forwarded.routing_header.hops, vec![10, 3, 1, 2]);
+    assert_eq!(forwarded.routing_header.hop_index, 3);
+    network.expect_nothing(10);
+}